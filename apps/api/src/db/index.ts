@@ -1,6 +1,6 @@
 import { Database } from "bun:sqlite";
 import { drizzle } from "drizzle-orm/bun-sqlite";
-import { eq, like, or, desc } from "drizzle-orm";
+import { and, eq, like, or, desc } from "drizzle-orm";
 import { users, sessions, profiles, dictationHistory, type User, type Session, type Profile, type DictationEntry } from "./schema";
 
 const sqlite = new Database("parrot.db");
@@ -39,10 +39,23 @@ sqlite.exec(`
     cleaned_text TEXT NOT NULL DEFAULT '',
     provider TEXT NOT NULL DEFAULT 'cloud',
     duration_ms INTEGER NOT NULL DEFAULT 0,
+    translated_text TEXT NOT NULL DEFAULT '',
+    cleanup_note TEXT NOT NULL DEFAULT '',
     created_at TEXT DEFAULT (datetime('now'))
   );
 `);
 
+// Columns added after the first release; CREATE TABLE IF NOT EXISTS won't add them
+function addColumnIfMissing(table: string, column: string, definition: string): void {
+  const columns = sqlite.query(`PRAGMA table_info(${table})`).all() as { name: string }[];
+  if (!columns.some((c) => c.name === column)) {
+    sqlite.exec(`ALTER TABLE ${table} ADD COLUMN ${column} ${definition}`);
+  }
+}
+
+addColumnIfMissing("dictation_history", "translated_text", "TEXT NOT NULL DEFAULT ''");
+addColumnIfMissing("dictation_history", "cleanup_note", "TEXT NOT NULL DEFAULT ''");
+
 // User operations
 export async function createUser(
   email: string,
@@ -208,11 +221,47 @@ export function insertDictation(
     .run();
 }
 
-export function updateDictationCleaned(id: string, cleanedText: string): void {
-  db.update(dictationHistory)
+// The updates only touch the user's own dictations, and return whether one matched
+export function updateDictationCleaned(
+  userId: string,
+  id: string,
+  cleanedText: string
+): boolean {
+  const updated = db
+    .update(dictationHistory)
     .set({ cleanedText })
-    .where(eq(dictationHistory.id, id))
-    .run();
+    .where(and(eq(dictationHistory.id, id), eq(dictationHistory.userId, userId)))
+    .returning({ id: dictationHistory.id })
+    .get();
+  return updated !== undefined;
+}
+
+export function updateDictationTranslated(
+  userId: string,
+  id: string,
+  translatedText: string
+): boolean {
+  const updated = db
+    .update(dictationHistory)
+    .set({ translatedText })
+    .where(and(eq(dictationHistory.id, id), eq(dictationHistory.userId, userId)))
+    .returning({ id: dictationHistory.id })
+    .get();
+  return updated !== undefined;
+}
+
+export function updateDictationNote(
+  userId: string,
+  id: string,
+  cleanupNote: string
+): boolean {
+  const updated = db
+    .update(dictationHistory)
+    .set({ cleanupNote })
+    .where(and(eq(dictationHistory.id, id), eq(dictationHistory.userId, userId)))
+    .returning({ id: dictationHistory.id })
+    .get();
+  return updated !== undefined;
 }
 
 export function getHistory(userId: string): DictationEntry[] {
@@ -237,6 +286,7 @@ export function searchHistory(userId: string, query: string): DictationEntry[] {
     .filter(
       (e) =>
         e.rawText.includes(query) ||
-        e.cleanedText.includes(query)
+        e.cleanedText.includes(query) ||
+        e.translatedText.includes(query)
     );
 }
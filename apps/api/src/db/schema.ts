@@ -38,6 +38,8 @@ export const dictationHistory = sqliteTable("dictation_history", {
   cleanedText: text("cleaned_text").notNull().default(""),
   provider: text("provider").notNull().default("cloud"),
   durationMs: integer("duration_ms").notNull().default(0),
+  translatedText: text("translated_text").notNull().default(""),
+  cleanupNote: text("cleanup_note").notNull().default(""),
   createdAt: text("created_at").default(sql`(datetime('now'))`),
 });
 
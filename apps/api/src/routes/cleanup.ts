@@ -6,6 +6,9 @@ export const cleanup = new Hono();
 
 interface CleanupRequest {
   text: string;
+  target_language?: string;
+  // Command mode: `text` is the user's selection and this is what to do with it
+  instruction?: string;
 }
 
 cleanup.post("/", async (c) => {
@@ -40,7 +43,9 @@ cleanup.post("/", async (c) => {
   }
 
   try {
-    const cleaned = await cleanupText(body.text, apiKey, profile);
+    const cleaned = body.instruction
+      ? await transformText(body.text, body.instruction, apiKey, profile)
+      : await cleanupText(body.text, apiKey, profile, body.target_language);
     return c.json({ text: cleaned });
   } catch (e) {
     return c.json({ error: String(e) }, 500);
@@ -50,7 +55,8 @@ cleanup.post("/", async (c) => {
 async function cleanupText(
   text: string,
   apiKey: string,
-  profile: Profile | null | undefined
+  profile: Profile | null | undefined,
+  targetLanguage?: string
 ): Promise<string> {
   let systemPrompt =
     "You are a text cleanup assistant for voice dictation. " +
@@ -68,7 +74,37 @@ async function cleanupText(
       systemPrompt += `\n\nWriting style: ${profile.writingStyle}`;
     }
   }
+  if (targetLanguage) {
+    systemPrompt += `\n\nTranslate the cleaned text into ${targetLanguage}. Return ONLY the translation.`;
+  }
+
+  return complete(systemPrompt, text, apiKey);
+}
+
+async function transformText(
+  text: string,
+  instruction: string,
+  apiKey: string,
+  profile: Profile | null | undefined
+): Promise<string> {
+  let systemPrompt =
+    "You edit text for the user. Apply the user's spoken instruction to the text they selected. " +
+    "Return ONLY the resulting text, with no explanation or quotes around it." +
+    `\n\nInstruction: ${instruction}`;
+
+  if (profile) {
+    if (profile.customWords && profile.customWords !== "[]") {
+      systemPrompt += `\n\nCustom vocabulary (use these exact spellings when relevant): ${profile.customWords}`;
+    }
+    if (profile.contextPrompt) {
+      systemPrompt += `\n\nContext: ${profile.contextPrompt}`;
+    }
+  }
+
+  return complete(systemPrompt, text, apiKey);
+}
 
+async function complete(systemPrompt: string, text: string, apiKey: string): Promise<string> {
   const resp = await fetch("https://api.openai.com/v1/chat/completions", {
     method: "POST",
     headers: {
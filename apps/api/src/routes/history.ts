@@ -1,5 +1,13 @@
 import { Hono } from "hono";
-import { getSession, getHistory, searchHistory, insertDictation, updateDictationCleaned } from "../db";
+import {
+  getSession,
+  getHistory,
+  searchHistory,
+  insertDictation,
+  updateDictationCleaned,
+  updateDictationTranslated,
+  updateDictationNote,
+} from "../db";
 
 export const history = new Hono();
 
@@ -59,9 +67,21 @@ history.patch("/:id", async (c) => {
   if (!session) return c.json({ error: "Invalid or expired session" }, 401);
 
   const id = c.req.param("id");
-  const body = await c.req.json<{ cleaned_text: string }>();
+  // Any of the fields may be left out
+  const body = await c.req.json<{
+    cleaned_text?: string;
+    translated_text?: string;
+    cleanup_note?: string;
+  }>();
 
-  updateDictationCleaned(id, body.cleaned_text);
+  let found = true;
+  if (body.cleaned_text !== undefined)
+    found &&= updateDictationCleaned(session.userId, id, body.cleaned_text);
+  if (body.translated_text !== undefined)
+    found &&= updateDictationTranslated(session.userId, id, body.translated_text);
+  if (body.cleanup_note !== undefined)
+    found &&= updateDictationNote(session.userId, id, body.cleanup_note);
 
+  if (!found) return c.json({ error: "Dictation not found" }, 404);
   return c.json({ status: "ok" });
 });
@@ -0,0 +1,195 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::db::AppRule;
+
+/// The application that had focus when a dictation started
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ActiveWindow {
+    /// Backend-specific window handle (the X11 window id), to tell two windows of the same app apart
+    #[serde(default)]
+    pub id: u64,
+    /// Stable application identifier, e.g. the X11 WM_CLASS class ("firefox", "Alacritty")
+    pub app_name: String,
+    pub title: String,
+}
+
+/// A way of asking the desktop which window has focus. Each platform/display server
+/// needs its own implementation; `NoBackend` is used where none exists yet (e.g. Wayland,
+/// which deliberately doesn't expose the focused window to clients).
+pub trait WindowBackend: Send + Sync {
+    fn active_window(&self) -> Result<Option<ActiveWindow>>;
+}
+
+/// Managed state wrapping the backend chosen at startup
+pub struct WindowTracker {
+    backend: Box<dyn WindowBackend>,
+}
+
+impl WindowTracker {
+    pub fn new() -> Self {
+        Self {
+            backend: detect_backend(),
+        }
+    }
+
+    /// The focused window, or None if it can't be determined. Failures are logged rather
+    /// than returned because app detection is never worth failing a dictation over.
+    pub fn current(&self) -> Option<ActiveWindow> {
+        match self.backend.active_window() {
+            Ok(window) => window,
+            Err(e) => {
+                eprintln!("Failed to read the focused window: {}", e);
+                None
+            }
+        }
+    }
+}
+
+fn detect_backend() -> Box<dyn WindowBackend> {
+    #[cfg(target_os = "linux")]
+    if std::env::var_os("DISPLAY").is_some() && !crate::insertion::is_wayland() {
+        return Box::new(X11Backend);
+    }
+    Box::new(NoBackend)
+}
+
+struct NoBackend;
+
+impl WindowBackend for NoBackend {
+    fn active_window(&self) -> Result<Option<ActiveWindow>> {
+        Ok(None)
+    }
+}
+
+/// Reads the window in the root window's _NET_ACTIVE_WINDOW property (set by EWMH
+/// window managers), then its WM_CLASS and _NET_WM_NAME / WM_NAME
+#[cfg(target_os = "linux")]
+struct X11Backend;
+
+#[cfg(target_os = "linux")]
+impl WindowBackend for X11Backend {
+    fn active_window(&self) -> Result<Option<ActiveWindow>> {
+        use x11rb::connection::Connection;
+        use x11rb::protocol::xproto::{AtomEnum, ConnectionExt};
+
+        let (conn, screen) = x11rb::connect(None)?;
+        let root = conn.setup().roots[screen].root;
+        let atom =
+            |name: &[u8]| -> Result<u32> { Ok(conn.intern_atom(false, name)?.reply()?.atom) };
+        let active_atom = atom(b"_NET_ACTIVE_WINDOW")?;
+        let wm_name_atom = atom(b"_NET_WM_NAME")?;
+        let utf8_atom = atom(b"UTF8_STRING")?;
+
+        let active = conn
+            .get_property(false, root, active_atom, AtomEnum::WINDOW, 0, 1)?
+            .reply()?;
+        let Some(window) = active
+            .value32()
+            .and_then(|mut v| v.next())
+            .filter(|w| *w != 0)
+        else {
+            return Ok(None);
+        };
+
+        let class = conn
+            .get_property(false, window, AtomEnum::WM_CLASS, AtomEnum::STRING, 0, 256)?
+            .reply()?;
+        // WM_CLASS is "instance\0class\0"; the class is the stable part
+        let app_name = class
+            .value
+            .split(|b| *b == 0)
+            .filter(|part| !part.is_empty())
+            .nth(1)
+            .map(|part| String::from_utf8_lossy(part).into_owned())
+            .unwrap_or_default();
+
+        let mut title = conn
+            .get_property(false, window, wm_name_atom, utf8_atom, 0, 1024)?
+            .reply()?
+            .value;
+        if title.is_empty() {
+            title = conn
+                .get_property(false, window, AtomEnum::WM_NAME, AtomEnum::STRING, 0, 1024)?
+                .reply()?
+                .value;
+        }
+
+        Ok(Some(ActiveWindow {
+            id: window.into(),
+            app_name,
+            title: String::from_utf8_lossy(&title).into_owned(),
+        }))
+    }
+}
+
+impl ActiveWindow {
+    /// Titles change as the user types (e.g. an unsaved-changes marker), so only the
+    /// window handle and app identify a window
+    pub fn is_same_window(&self, other: &ActiveWindow) -> bool {
+        self.id == other.id && self.app_name == other.app_name
+    }
+
+    /// The first rule whose pattern matches this window; empty patterns never match
+    pub fn matching_rule<'a>(&self, rules: &'a [AppRule]) -> Option<&'a AppRule> {
+        let app_name = self.app_name.to_lowercase();
+        let title = self.title.to_lowercase();
+        rules.iter().find(|rule| {
+            let pattern = rule.pattern.trim().to_lowercase();
+            let haystack = match rule.match_on.as_str() {
+                "title" => &title,
+                _ => &app_name,
+            };
+            !pattern.is_empty() && haystack.contains(&pattern)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, match_on: &str, context: &str) -> AppRule {
+        AppRule {
+            pattern: pattern.to_string(),
+            match_on: match_on.to_string(),
+            profile_id: None,
+            context: context.to_string(),
+            output_format: String::new(),
+        }
+    }
+
+    #[test]
+    fn matches_rules() {
+        let rules = [
+            rule("", "app", "empty"),
+            rule("   ", "title", "blank"),
+            rule("Pull Request", "title", "review"),
+            rule("code", "app", "editor"),
+            rule("firefox", "app", "browser"),
+            rule("GitHub", "title", "github"),
+            rule("alacritty", "anything else", "terminal"),
+        ];
+        let cases = [
+            // (app name, title, matching rule's context)
+            ("firefox", "Pull request #12 - GitHub", Some("review")),
+            ("firefox", "Issues - GitHub", Some("browser")),
+            ("chromium", "Issues - GitHub", Some("github")),
+            ("Code", "main.rs - parrot", Some("editor")),
+            // The app name isn't searched for title rules, nor the title for app rules
+            ("github-desktop", "parrot", None),
+            ("slack", "code review", None),
+            ("Alacritty", "~", Some("terminal")),
+            ("", "", None),
+        ];
+        for (app_name, title, expected) in cases {
+            let window = ActiveWindow {
+                id: 1,
+                app_name: app_name.to_string(),
+                title: title.to_string(),
+            };
+            let matched = window.matching_rule(&rules).map(|r| r.context.as_str());
+            assert_eq!(matched, expected, "{} / {}", app_name, title);
+        }
+    }
+}
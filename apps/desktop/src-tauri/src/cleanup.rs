@@ -1,165 +1,1139 @@
 use anyhow::Result;
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::db::{Correction, Database, PromptTemplate};
+use crate::output_format::OutputFormat;
+use crate::prompt_templates;
+
+const DEFAULT_TEMPERATURE: f32 = 0.3;
+/// The Anthropic API requires a limit; the others only get one when it is set
+const DEFAULT_ANTHROPIC_MAX_TOKENS: u32 = 1024;
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Everything from the active profile (and translation mode) that shapes the local cleanup prompt
+#[derive(Default)]
+pub struct PromptContext<'a> {
+    pub custom_words: &'a [String],
+    pub context_prompt: &'a str,
+    pub writing_style: &'a str,
+    pub target_language: Option<&'a str>,
+    /// Application the text will be inserted into, if detected
+    pub app_name: Option<&'a str>,
+    /// Extra instructions from the app rule matching that application
+    pub app_context: &'a str,
+    /// Past corrections of similar dictations, shown as few-shot examples
+    pub examples: &'a [Correction],
+    pub format: OutputFormat,
+    /// Language the dictation was transcribed in, if known
+    pub language: Option<&'a str>,
+    /// The last dictation inserted into the same field
+    pub previous_dictation: &'a str,
+    /// The user's template for `format`; None uses the built-in one
+    pub template: Option<&'a PromptTemplate>,
+}
+
+/// Receives each piece of a streamed reply as it arrives
+pub type OnDelta<'a> = dyn for<'d> FnMut(&'d str) + Send + 'a;
+
+/// An LLM service that cleans up dictations. Chat-style providers only implement `chat`
+/// and get their prompts built here; the Parrot backend builds its own server-side.
+#[async_trait]
+pub trait CleanupProvider: Send + Sync {
+    /// One system + user exchange, returning the model's reply
+    async fn chat(&self, system_prompt: &str, user_text: &str) -> Result<String>;
+
+    /// Whether prompts built from the local profile reach the model. False for the Parrot
+    /// backend, which only knows the server-side profile.
+    fn uses_local_prompt(&self) -> bool {
+        true
+    }
+
+    /// Like `chat`, calling `on_delta` with each piece of the reply as it arrives.
+    /// Providers that can't stream deliver the whole reply as one piece.
+    async fn chat_stream(
+        &self,
+        system_prompt: &str,
+        user_text: &str,
+        on_delta: &mut OnDelta<'_>,
+    ) -> Result<String> {
+        let reply = self.chat(system_prompt, user_text).await?;
+        on_delta(&reply);
+        Ok(reply)
+    }
+
+    async fn cleanup(&self, raw_text: &str, prompt: &PromptContext<'_>) -> Result<String> {
+        let (system_prompt, user_text) = build_cleanup_prompt(prompt, raw_text)?;
+        self.chat(&system_prompt, &user_text).await
+    }
+
+    async fn cleanup_stream(
+        &self,
+        raw_text: &str,
+        prompt: &PromptContext<'_>,
+        on_delta: &mut OnDelta<'_>,
+    ) -> Result<String> {
+        let (system_prompt, user_text) = build_cleanup_prompt(prompt, raw_text)?;
+        self.chat_stream(&system_prompt, &user_text, on_delta).await
+    }
+
+    /// Command mode: apply a spoken `instruction` ("summarize this", "turn into a bulleted
+    /// list") to `selection` and return the text that replaces it
+    async fn transform(
+        &self,
+        selection: &str,
+        instruction: &str,
+        prompt: &PromptContext<'_>,
+    ) -> Result<String> {
+        self.chat(&build_command_prompt(instruction, prompt), selection)
+            .await
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderKind {
+    /// Ollama's native /api/chat
+    Ollama,
+    /// Any OpenAI-compatible /chat/completions endpoint (OpenAI, LM Studio, llama.cpp, vLLM...)
+    OpenAi,
+    /// Anthropic's Messages API
+    Anthropic,
+    /// Our backend, which proxies to OpenAI with the server-side profile
+    Parrot,
+}
+
+impl ProviderKind {
+    fn default_base_url(self) -> &'static str {
+        match self {
+            Self::Ollama => "http://localhost:11434",
+            Self::OpenAi => "https://api.openai.com/v1",
+            Self::Anthropic => "https://api.anthropic.com",
+            Self::Parrot => crate::cloud_api::BACKEND_URL,
+        }
+    }
+
+    fn default_model(self) -> &'static str {
+        match self {
+            Self::Ollama => "llama3.2",
+            Self::OpenAi | Self::Parrot => "gpt-4o-mini",
+            Self::Anthropic => "claude-3-5-haiku-latest",
+        }
+    }
+}
+
+/// Which provider to use and how to call it, read from settings:
+/// - `cleanup_provider`: "ollama", "open_ai", "anthropic" or "parrot"; defaults to Ollama
+///   in local mode and the Parrot backend in cloud mode
+/// - `cleanup_base_url`: overrides the provider's URL (e.g. a mock server, or Ollama's
+///   OpenAI-compatible route at http://localhost:11434/v1)
+/// - `llm_model`: model name, overridden per profile; ignored by the Parrot backend
+/// - `cleanup_temperature` (default 0.3) and `cleanup_max_tokens`
+/// - `llm_api_key`: key for OpenAI/Anthropic, or passed on to the Parrot backend
+#[derive(Clone, Debug)]
+pub struct ProviderSettings {
+    pub kind: ProviderKind,
+    pub base_url: String,
+    pub model: String,
+    pub temperature: f32,
+    pub max_tokens: Option<u32>,
+    pub api_key: Option<String>,
+    /// Parrot backend only
+    pub session_token: Option<String>,
+}
+
+impl ProviderSettings {
+    pub fn load(db: &Database, setup_mode: &str, model: Option<&str>) -> Result<Self> {
+        let kind = match setting(db, "cleanup_provider")? {
+            Some(value) => parse_kind(&value)?,
+            None if setup_mode == "cloud" => ProviderKind::Parrot,
+            None => ProviderKind::Ollama,
+        };
+        let base_url = setting(db, "cleanup_base_url")?
+            .unwrap_or_else(|| kind.default_base_url().to_string())
+            .trim_end_matches('/')
+            .to_string();
+        let model = match model.filter(|m| !m.trim().is_empty()) {
+            Some(model) => model.to_string(),
+            None => setting(db, "llm_model")?.unwrap_or_else(|| kind.default_model().to_string()),
+        };
+        let temperature = match setting(db, "cleanup_temperature")? {
+            Some(v) => parse_temperature(&v)?,
+            None => DEFAULT_TEMPERATURE,
+        };
+        let max_tokens = setting(db, "cleanup_max_tokens")?
+            .map(|v| parse_max_tokens(&v))
+            .transpose()?;
+        Ok(Self {
+            kind,
+            base_url,
+            model,
+            temperature,
+            max_tokens,
+            api_key: setting(db, "llm_api_key")?,
+            session_token: setting(db, "session_token")?,
+        })
+    }
+
+    pub fn into_provider(self, client: reqwest::Client) -> Box<dyn CleanupProvider> {
+        match self.kind {
+            ProviderKind::Ollama => Box::new(OllamaProvider {
+                client,
+                settings: self,
+            }),
+            ProviderKind::OpenAi => Box::new(OpenAiProvider {
+                client,
+                settings: self,
+            }),
+            ProviderKind::Anthropic => Box::new(AnthropicProvider {
+                client,
+                settings: self,
+            }),
+            ProviderKind::Parrot => Box::new(ParrotProvider {
+                client,
+                settings: self,
+            }),
+        }
+    }
+}
+
+/// The provider for one dictation; `model` is the profile's override, if any
+pub fn load_provider(
+    db: &Database,
+    client: reqwest::Client,
+    setup_mode: &str,
+    model: Option<&str>,
+) -> Result<Box<dyn CleanupProvider>> {
+    Ok(ProviderSettings::load(db, setup_mode, model)?.into_provider(client))
+}
+
+/// How cleaned text reaches the user while the model is still generating it
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Streaming {
+    /// Wait for the whole reply
+    Off,
+    /// Send `cleanup-delta` events to the UI as tokens arrive
+    Events,
+    /// Also type the tokens into the target app as they arrive
+    Typing,
+}
+
+impl Streaming {
+    /// Settings `cleanup_streaming` ("true" streams) and `cleanup_stream_typing`
+    /// ("true" also types as tokens arrive)
+    pub fn load(db: &Database) -> Result<Self> {
+        if setting(db, "cleanup_streaming")?.as_deref() != Some("true") {
+            return Ok(Self::Off);
+        }
+        Ok(match setting(db, "cleanup_stream_typing")?.as_deref() {
+            Some("true") => Self::Typing,
+            _ => Self::Events,
+        })
+    }
+}
+
+/// Check a `cleanup_*` setting before it is saved, since a bad one would stop every cleanup
+pub fn validate_setting(key: &str, value: &str) -> Result<()> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(());
+    }
+    match key {
+        "cleanup_provider" => parse_kind(value).map(|_| ()),
+        "cleanup_temperature" => parse_temperature(value).map(|_| ()),
+        "cleanup_max_tokens" => parse_max_tokens(value).map(|_| ()),
+        _ => Ok(()),
+    }
+}
+
+fn parse_kind(value: &str) -> Result<ProviderKind> {
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|_| anyhow::anyhow!("Unknown cleanup provider: {}", value))
+}
+
+fn parse_temperature(value: &str) -> Result<f32> {
+    value
+        .parse::<f32>()
+        .ok()
+        .filter(|t| (0.0..=2.0).contains(t))
+        .ok_or_else(|| anyhow::anyhow!("Invalid value for cleanup_temperature: {}", value))
+}
+
+fn parse_max_tokens(value: &str) -> Result<u32> {
+    value
+        .parse::<u32>()
+        .ok()
+        .filter(|n| *n > 0)
+        .ok_or_else(|| anyhow::anyhow!("Invalid value for cleanup_max_tokens: {}", value))
+}
+
+fn setting(db: &Database, key: &str) -> Result<Option<String>> {
+    Ok(db
+        .get_setting(key)?
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty()))
+}
+
+pub async fn cleanup_text(
+    provider: &dyn CleanupProvider,
+    raw_text: &str,
+    prompt: &PromptContext<'_>,
+) -> Result<String> {
+    if raw_text.trim().is_empty() {
+        return Ok(String::new());
+    }
+    provider.cleanup(raw_text, prompt).await
+}
+
+pub async fn cleanup_text_stream(
+    provider: &dyn CleanupProvider,
+    raw_text: &str,
+    prompt: &PromptContext<'_>,
+    on_delta: &mut OnDelta<'_>,
+) -> Result<String> {
+    if raw_text.trim().is_empty() {
+        return Ok(String::new());
+    }
+    provider.cleanup_stream(raw_text, prompt, on_delta).await
+}
+
+pub async fn transform_text(
+    provider: &dyn CleanupProvider,
+    selection: &str,
+    instruction: &str,
+    prompt: &PromptContext<'_>,
+) -> Result<String> {
+    if instruction.trim().is_empty() {
+        anyhow::bail!("No instruction was heard");
+    }
+    provider.transform(selection, instruction, prompt).await
+}
+
+#[derive(Serialize, Deserialize)]
+struct ChatMessage {
+    role: String,
+    content: String,
+}
+
+fn chat_messages(system_prompt: &str, user_text: &str) -> Vec<ChatMessage> {
+    vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: system_prompt.to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: user_text.to_string(),
+        },
+    ]
+}
+
+/// Call `on_line` with each line of a streamed body (NDJSON or server-sent events). Lines
+/// are split on bytes before decoding, so a character spanning two chunks stays intact.
+async fn for_each_line(
+    mut resp: reqwest::Response,
+    mut on_line: impl FnMut(&str) -> Result<()>,
+) -> Result<()> {
+    let mut buffer = Vec::new();
+    while let Some(chunk) = resp.chunk().await? {
+        buffer.extend_from_slice(&chunk);
+        while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            on_line(String::from_utf8_lossy(&line).trim())?;
+        }
+    }
+    if !buffer.is_empty() {
+        on_line(String::from_utf8_lossy(&buffer).trim())?;
+    }
+    Ok(())
+}
+
+async fn error_for_status(resp: reqwest::Response, service: &str) -> Result<reqwest::Response> {
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        anyhow::bail!("{} API error {}: {}", service, status, body);
+    }
+    Ok(resp)
+}
+
+/// Ollama's native chat endpoint
+struct OllamaProvider {
+    client: reqwest::Client,
+    settings: ProviderSettings,
+}
 
-/// Request body for Ollama's OpenAI-compatible chat endpoint
 #[derive(Serialize)]
 struct OllamaChatRequest {
     model: String,
     messages: Vec<ChatMessage>,
-    temperature: f32,
     stream: bool,
+    options: OllamaOptions,
 }
 
-#[derive(Serialize, Deserialize)]
-struct ChatMessage {
-    role: String,
-    content: String,
+#[derive(Serialize)]
+struct OllamaOptions {
+    temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
 }
 
-/// Response from Ollama's OpenAI-compatible chat endpoint
 #[derive(Deserialize)]
 struct OllamaChatResponse {
-    choices: Vec<OllamaChatChoice>,
+    message: ChatMessage,
+}
+
+/// What Ollama streams instead of a chunk when generation fails partway
+#[derive(Deserialize)]
+struct OllamaStreamError {
+    error: String,
+}
+
+impl OllamaProvider {
+    async fn send(
+        &self,
+        system_prompt: &str,
+        user_text: &str,
+        stream: bool,
+    ) -> Result<reqwest::Response> {
+        let request = OllamaChatRequest {
+            model: self.settings.model.clone(),
+            messages: chat_messages(system_prompt, user_text),
+            stream,
+            options: OllamaOptions {
+                temperature: self.settings.temperature,
+                num_predict: self.settings.max_tokens,
+            },
+        };
+        let resp = self
+            .client
+            .post(format!("{}/api/chat", self.settings.base_url))
+            .json(&request)
+            .send()
+            .await?;
+        error_for_status(resp, "Ollama").await
+    }
+}
+
+#[async_trait]
+impl CleanupProvider for OllamaProvider {
+    async fn chat(&self, system_prompt: &str, user_text: &str) -> Result<String> {
+        let resp = self.send(system_prompt, user_text, false).await?;
+        let chat_resp: OllamaChatResponse = resp.json().await?;
+        Ok(chat_resp.message.content)
+    }
+
+    /// Ollama streams one JSON object per line
+    async fn chat_stream(
+        &self,
+        system_prompt: &str,
+        user_text: &str,
+        on_delta: &mut OnDelta<'_>,
+    ) -> Result<String> {
+        let resp = self.send(system_prompt, user_text, true).await?;
+        let mut reply = String::new();
+        for_each_line(resp, |line| {
+            if line.is_empty() {
+                return Ok(());
+            }
+            if let Ok(e) = serde_json::from_str::<OllamaStreamError>(line) {
+                anyhow::bail!("Ollama API error: {}", e.error);
+            }
+            let chunk: OllamaChatResponse = serde_json::from_str(line)
+                .map_err(|e| anyhow::anyhow!("Invalid Ollama stream line: {}", e))?;
+            if !chunk.message.content.is_empty() {
+                on_delta(&chunk.message.content);
+                reply.push_str(&chunk.message.content);
+            }
+            Ok(())
+        })
+        .await?;
+        Ok(reply)
+    }
+}
+
+/// Any OpenAI-compatible chat completions endpoint
+struct OpenAiProvider {
+    client: reqwest::Client,
+    settings: ProviderSettings,
+}
+
+#[derive(Serialize)]
+struct OpenAiChatRequest {
+    model: String,
+    messages: Vec<ChatMessage>,
+    temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    stream: bool,
 }
 
 #[derive(Deserialize)]
-struct OllamaChatChoice {
+struct OpenAiChatResponse {
+    choices: Vec<OpenAiChatChoice>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiChatChoice {
     message: ChatMessage,
 }
 
-/// Request body for our backend API (profile data is stored server-side)
+/// One server-sent event of a streamed chat completion
+#[derive(Deserialize)]
+struct OpenAiStreamChunk {
+    choices: Vec<OpenAiStreamChoice>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiStreamChoice {
+    #[serde(default)]
+    delta: OpenAiDelta,
+}
+
+#[derive(Deserialize, Default)]
+struct OpenAiDelta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+/// An event reporting that the stream failed partway
+#[derive(Deserialize)]
+struct OpenAiStreamError {
+    error: serde_json::Value,
+}
+
+impl OpenAiProvider {
+    async fn send(
+        &self,
+        system_prompt: &str,
+        user_text: &str,
+        stream: bool,
+    ) -> Result<reqwest::Response> {
+        let request = OpenAiChatRequest {
+            model: self.settings.model.clone(),
+            messages: chat_messages(system_prompt, user_text),
+            temperature: self.settings.temperature,
+            max_tokens: self.settings.max_tokens,
+            stream,
+        };
+        let mut req_builder = self
+            .client
+            .post(format!("{}/chat/completions", self.settings.base_url))
+            .json(&request);
+        // Local OpenAI-compatible servers usually don't want a key
+        if let Some(key) = &self.settings.api_key {
+            req_builder = req_builder.bearer_auth(key);
+        }
+        let resp = req_builder.send().await?;
+        error_for_status(resp, "OpenAI").await
+    }
+}
+
+#[async_trait]
+impl CleanupProvider for OpenAiProvider {
+    async fn chat(&self, system_prompt: &str, user_text: &str) -> Result<String> {
+        let resp = self.send(system_prompt, user_text, false).await?;
+        let chat_resp: OpenAiChatResponse = resp.json().await?;
+        chat_resp
+            .choices
+            .into_iter()
+            .next()
+            .map(|c| c.message.content)
+            .ok_or_else(|| anyhow::anyhow!("OpenAI API returned no choices"))
+    }
+
+    /// Server-sent events: `data: {json}` lines, ending with `data: [DONE]`
+    async fn chat_stream(
+        &self,
+        system_prompt: &str,
+        user_text: &str,
+        on_delta: &mut OnDelta<'_>,
+    ) -> Result<String> {
+        let resp = self.send(system_prompt, user_text, true).await?;
+        let mut reply = String::new();
+        for_each_line(resp, |line| {
+            let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+                return Ok(());
+            };
+            if data == "[DONE]" {
+                return Ok(());
+            }
+            if let Ok(e) = serde_json::from_str::<OpenAiStreamError>(data) {
+                let message = e.error["message"].as_str().map(str::to_string);
+                anyhow::bail!(
+                    "OpenAI API error: {}",
+                    message.unwrap_or(e.error.to_string())
+                );
+            }
+            let chunk: OpenAiStreamChunk = serde_json::from_str(data)
+                .map_err(|e| anyhow::anyhow!("Invalid OpenAI stream event: {}", e))?;
+            for content in chunk.choices.into_iter().filter_map(|c| c.delta.content) {
+                if !content.is_empty() {
+                    on_delta(&content);
+                    reply.push_str(&content);
+                }
+            }
+            Ok(())
+        })
+        .await?;
+        Ok(reply)
+    }
+}
+
+/// Anthropic's Messages API, which takes the system prompt as a separate field
+struct AnthropicProvider {
+    client: reqwest::Client,
+    settings: ProviderSettings,
+}
+
+#[derive(Serialize)]
+struct AnthropicRequest {
+    model: String,
+    system: String,
+    messages: Vec<ChatMessage>,
+    max_tokens: u32,
+    temperature: f32,
+}
+
+#[derive(Deserialize)]
+struct AnthropicResponse {
+    content: Vec<AnthropicContent>,
+}
+
+#[derive(Deserialize)]
+struct AnthropicContent {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    text: String,
+}
+
+#[async_trait]
+impl CleanupProvider for AnthropicProvider {
+    async fn chat(&self, system_prompt: &str, user_text: &str) -> Result<String> {
+        let api_key = self
+            .settings
+            .api_key
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("An API key is required for Anthropic"))?;
+        let request = AnthropicRequest {
+            model: self.settings.model.clone(),
+            system: system_prompt.to_string(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: user_text.to_string(),
+            }],
+            max_tokens: self
+                .settings
+                .max_tokens
+                .unwrap_or(DEFAULT_ANTHROPIC_MAX_TOKENS),
+            temperature: self.settings.temperature.min(1.0),
+        };
+        let resp = self
+            .client
+            .post(format!("{}/v1/messages", self.settings.base_url))
+            .header("x-api-key", api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(&request)
+            .send()
+            .await?;
+        let message: AnthropicResponse = error_for_status(resp, "Anthropic").await?.json().await?;
+        Ok(message
+            .content
+            .into_iter()
+            .filter(|block| block.kind == "text")
+            .map(|block| block.text)
+            .collect())
+    }
+}
+
+/// Our backend API (proxies to OpenAI). Profile data (custom_words, context_prompt,
+/// writing_style) is stored server-side. If the user provided their own API key we
+/// pass it; otherwise the backend uses its own key.
+struct ParrotProvider {
+    client: reqwest::Client,
+    settings: ProviderSettings,
+}
+
 #[derive(Serialize)]
 struct BackendCleanupRequest {
     text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target_language: Option<String>,
+    /// Command mode: `text` is the selection and this is what to do with it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instruction: Option<String>,
 }
 
-/// Response from our backend API
 #[derive(Deserialize)]
 struct BackendCleanupResponse {
     text: String,
 }
 
-pub async fn cleanup_text(
-    raw_text: &str,
-    mode: &str,
-    session_token: Option<&str>,
-    api_key: Option<&str>,
-    model: Option<&str>,
-    context_prompt: &str,
-    writing_style: &str,
-) -> Result<String> {
-    if raw_text.trim().is_empty() {
-        return Ok(String::new());
+impl ParrotProvider {
+    async fn send(&self, request: &BackendCleanupRequest) -> Result<String> {
+        let session_token = self
+            .settings
+            .session_token
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("Session token required for cloud mode"))?;
+
+        let mut req_builder = self
+            .client
+            .post(format!("{}/api/cleanup", self.settings.base_url))
+            .header("Authorization", format!("Bearer {}", session_token))
+            .json(request);
+
+        // Optionally add user's API key if they provided one
+        if let Some(key) = &self.settings.api_key {
+            req_builder = req_builder.header("X-API-Key", key);
+        }
+
+        let resp = req_builder.send().await?;
+        let cleanup_resp: BackendCleanupResponse = error_for_status(resp, "Backend cleanup")
+            .await?
+            .json()
+            .await?;
+        Ok(cleanup_resp.text)
+    }
+}
+
+#[async_trait]
+impl CleanupProvider for ParrotProvider {
+    async fn chat(&self, _system_prompt: &str, _user_text: &str) -> Result<String> {
+        anyhow::bail!("The Parrot backend only supports cleanup and commands")
+    }
+
+    fn uses_local_prompt(&self) -> bool {
+        false
+    }
+
+    /// Profile data lives on the server; only the target language is sent
+    async fn cleanup(&self, raw_text: &str, prompt: &PromptContext<'_>) -> Result<String> {
+        self.send(&BackendCleanupRequest {
+            text: raw_text.to_string(),
+            target_language: prompt.target_language.map(str::to_string),
+            instruction: None,
+        })
+        .await
+    }
+
+    async fn cleanup_stream(
+        &self,
+        raw_text: &str,
+        prompt: &PromptContext<'_>,
+        on_delta: &mut OnDelta<'_>,
+    ) -> Result<String> {
+        let cleaned = self.cleanup(raw_text, prompt).await?;
+        on_delta(&cleaned);
+        Ok(cleaned)
     }
 
-    match mode {
-        "local" => cleanup_with_ollama(raw_text, model, context_prompt, writing_style).await,
-        "cloud" => cleanup_with_backend(raw_text, session_token, api_key).await,
-        _ => anyhow::bail!("Unknown cleanup mode: {}", mode),
+    async fn transform(
+        &self,
+        selection: &str,
+        instruction: &str,
+        _prompt: &PromptContext<'_>,
+    ) -> Result<String> {
+        self.send(&BackendCleanupRequest {
+            text: selection.to_string(),
+            target_language: None,
+            instruction: Some(instruction.to_string()),
+        })
+        .await
     }
 }
 
-/// Use Ollama's local server for text cleanup
-/// Note: custom_words not supported in local mode
-async fn cleanup_with_ollama(
-    raw_text: &str,
-    model: Option<&str>,
-    context_prompt: &str,
-    writing_style: &str,
-) -> Result<String> {
-    let system_prompt = build_system_prompt(context_prompt, writing_style);
-    let model_name = model.unwrap_or("llama3.2");
-
-    let request = OllamaChatRequest {
-        model: model_name.to_string(),
-        messages: vec![
-            ChatMessage {
-                role: "system".to_string(),
-                content: system_prompt,
-            },
-            ChatMessage {
-                role: "user".to_string(),
-                content: raw_text.to_string(),
-            },
-        ],
-        temperature: 0.3,
-        stream: false,
+/// System prompt and user message for a cleanup, from the context's template or the
+/// built-in one for its format
+pub fn build_cleanup_prompt(context: &PromptContext, raw_text: &str) -> Result<(String, String)> {
+    let default;
+    let template = match context.template {
+        Some(template) => template,
+        None => {
+            default = prompt_templates::default_template(context.format);
+            &default
+        }
     };
+    let examples = context
+        .examples
+        .iter()
+        .map(|example| {
+            format!(
+                "Dictation: {}\nYour cleanup: {}\nUser's correction: {}",
+                example.raw_text, example.model_output, example.corrected_text
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let values: HashMap<&str, String> = HashMap::from([
+        ("raw_text", raw_text.to_string()),
+        ("custom_words", context.custom_words.join(", ")),
+        ("app_name", context.app_name.unwrap_or_default().to_string()),
+        ("language", context.language.unwrap_or_default().to_string()),
+        ("previous_dictation", context.previous_dictation.to_string()),
+        ("context_prompt", context.context_prompt.to_string()),
+        ("writing_style", context.writing_style.to_string()),
+        ("app_context", context.app_context.to_string()),
+        ("examples", examples),
+        (
+            "target_language",
+            context.target_language.unwrap_or_default().to_string(),
+        ),
+    ]);
+    let system_prompt =
+        prompt_templates::render(&prompt_templates::system_prompt(template), &values)?;
+    let user_text = prompt_templates::render(&template.user_prompt, &values)?;
+    Ok((system_prompt.trim_end().to_string(), user_text))
+}
 
-    let client = reqwest::Client::new();
-    let resp = client
-        .post("http://localhost:11434/v1/chat/completions")
-        .json(&request)
-        .send()
-        .await?;
+/// Prompt for command mode. Only the parts of the profile that describe the user's
+/// domain apply; the instruction itself decides style and language.
+fn build_command_prompt(instruction: &str, context: &PromptContext) -> String {
+    let mut prompt = format!(
+        "You edit text for the user. Apply the user's spoken instruction to the text they \
+         selected. Return ONLY the resulting text, with no explanation or quotes around it.\n\n\
+         Instruction: {}",
+        instruction.trim()
+    );
 
-    if !resp.status().is_success() {
-        let status = resp.status();
-        let body = resp.text().await.unwrap_or_default();
-        anyhow::bail!("Ollama API error {}: {}", status, body);
+    if !context.context_prompt.is_empty() {
+        prompt.push_str(&format!("\n\nContext: {}", context.context_prompt));
+    }
+    if let Some(app_name) = context.app_name {
+        prompt.push_str(&format!(
+            "\n\nThe text is in the application \"{}\"; format the result accordingly.",
+            app_name
+        ));
+    }
+    if !context.app_context.is_empty() {
+        prompt.push_str(&format!("\n\nApplication context: {}", context.app_context));
+    }
+    if !context.custom_words.is_empty() {
+        prompt.push_str(&format!(
+            "\n\nSpell these words exactly as written: {}",
+            context.custom_words.join(", ")
+        ));
     }
 
-    let chat_resp: OllamaChatResponse = resp.json().await?;
-    let cleaned = chat_resp
-        .choices
-        .first()
-        .map(|c| c.message.content.clone())
-        .unwrap_or_else(|| raw_text.to_string());
-
-    Ok(cleaned)
+    prompt
 }
 
-/// Use our backend API for text cleanup (proxies to OpenAI/Anthropic)
-/// Profile data (custom_words, context_prompt, writing_style) is stored server-side
-/// If user provides their own API key, we pass it; otherwise backend uses its own key
-async fn cleanup_with_backend(
-    raw_text: &str,
-    session_token: Option<&str>,
-    api_key: Option<&str>,
-) -> Result<String> {
-    let session_token = session_token
-        .ok_or_else(|| anyhow::anyhow!("Session token required for cloud mode"))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::mpsc;
 
-    let request = BackendCleanupRequest {
-        text: raw_text.to_string(),
-    };
+    /// One request as the mock server received it
+    struct Received {
+        path: String,
+        headers: HashMap<String, String>,
+        body: serde_json::Value,
+    }
 
-    let client = reqwest::Client::new();
-    let mut req_builder = client
-        .post("http://localhost:3001/api/cleanup")
-        .header("Authorization", format!("Bearer {}", session_token))
-        .json(&request);
+    /// Serve a single request with `status` and `body`; the request comes back on the channel
+    fn serve_once(status: u16, body: &'static str) -> (String, mpsc::Receiver<Received>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let request = read_request(&mut reader);
+            let response = format!(
+                "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            reader.get_mut().write_all(response.as_bytes()).unwrap();
+            tx.send(request).unwrap();
+        });
+        (url, rx)
+    }
 
-    // Optionally add user's API key if they provided one
-    if let Some(key) = api_key {
-        req_builder = req_builder.header("X-API-Key", key);
+    /// Serve a single request with a 200 streamed in `chunks`, each sent separately
+    fn serve_stream(chunks: &'static [&'static [u8]]) -> (String, mpsc::Receiver<Received>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let request = read_request(&mut reader);
+            let stream = reader.get_mut();
+            stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n",
+                )
+                .unwrap();
+            for chunk in chunks {
+                write!(stream, "{:x}\r\n", chunk.len()).unwrap();
+                stream.write_all(chunk).unwrap();
+                stream.write_all(b"\r\n").unwrap();
+                stream.flush().unwrap();
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            stream.write_all(b"0\r\n\r\n").unwrap();
+            tx.send(request).unwrap();
+        });
+        (url, rx)
     }
 
-    let resp = req_builder.send().await?;
+    fn read_request(reader: &mut BufReader<TcpStream>) -> Received {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let path = line.split_whitespace().nth(1).unwrap().to_string();
+        let mut headers = HashMap::new();
+        loop {
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+            let Some((name, value)) = line.trim_end().split_once(':') else {
+                break;
+            };
+            headers.insert(name.to_lowercase(), value.trim().to_string());
+        }
+        let length = headers
+            .get("content-length")
+            .map_or(0, |v| v.parse().unwrap());
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        Received {
+            path,
+            headers,
+            body: serde_json::from_slice(&body).unwrap(),
+        }
+    }
 
-    if !resp.status().is_success() {
-        let status = resp.status();
-        let body = resp.text().await.unwrap_or_default();
-        anyhow::bail!("Backend cleanup API error {}: {}", status, body);
+    /// Stream a reply, returning it with the pieces `on_delta` was called with
+    async fn stream(kind: ProviderKind, url: String) -> (Result<String>, Vec<String>) {
+        let mut deltas = Vec::new();
+        let reply = provider(kind, url)
+            .chat_stream("system", "hello", &mut |delta| {
+                deltas.push(delta.to_string())
+            })
+            .await;
+        (reply, deltas)
     }
 
-    let cleanup_resp: BackendCleanupResponse = resp.json().await?;
-    Ok(cleanup_resp.text)
-}
+    fn provider(kind: ProviderKind, base_url: String) -> Box<dyn CleanupProvider> {
+        ProviderSettings {
+            kind,
+            base_url,
+            model: "test-model".to_string(),
+            temperature: 0.3,
+            max_tokens: Some(256),
+            api_key: Some("test-key".to_string()),
+            session_token: Some("test-session".to_string()),
+        }
+        .into_provider(reqwest::Client::builder().no_proxy().build().unwrap())
+    }
 
-fn build_system_prompt(context_prompt: &str, writing_style: &str) -> String {
-    let mut prompt = String::from(
-        "You are a text cleanup assistant for voice dictation. \
-         Fix grammar, punctuation, and remove filler words (um, uh, like, you know). \
-         Preserve the speaker's meaning and tone. Return ONLY the cleaned text, nothing else.",
-    );
+    #[tokio::test]
+    async fn calls_ollama_chat() {
+        let (url, rx) = serve_once(
+            200,
+            r#"{"message":{"role":"assistant","content":"Hello."}}"#,
+        );
+        let reply = provider(ProviderKind::Ollama, url)
+            .chat("system", "hello")
+            .await
+            .unwrap();
+        assert_eq!(reply, "Hello.");
+        let request = rx.recv().unwrap();
+        assert_eq!(request.path, "/api/chat");
+        assert_eq!(request.body["model"], "test-model");
+        assert_eq!(request.body["stream"], false);
+        assert_eq!(request.body["options"]["num_predict"], 256);
+        assert_eq!(request.body["messages"][0]["role"], "system");
+        assert_eq!(request.body["messages"][1]["content"], "hello");
+    }
 
-    if !context_prompt.is_empty() {
-        prompt.push_str(&format!("\n\nContext: {}", context_prompt));
+    #[tokio::test]
+    async fn calls_openai_chat_completions() {
+        let (url, rx) = serve_once(
+            200,
+            r#"{"choices":[{"message":{"role":"assistant","content":"Hello."}}]}"#,
+        );
+        let reply = provider(ProviderKind::OpenAi, url)
+            .chat("system", "hello")
+            .await
+            .unwrap();
+        assert_eq!(reply, "Hello.");
+        let request = rx.recv().unwrap();
+        assert_eq!(request.path, "/chat/completions");
+        assert_eq!(request.headers["authorization"], "Bearer test-key");
+        assert_eq!(request.body["model"], "test-model");
+        assert_eq!(request.body["max_tokens"], 256);
+        assert_eq!(request.body["messages"][1]["content"], "hello");
     }
-    if !writing_style.is_empty() {
-        prompt.push_str(&format!("\n\nWriting style: {}", writing_style));
+
+    #[tokio::test]
+    async fn calls_anthropic_messages() {
+        let (url, rx) = serve_once(
+            200,
+            r#"{"content":[{"type":"text","text":"Hel"},{"type":"text","text":"lo."}]}"#,
+        );
+        let reply = provider(ProviderKind::Anthropic, url)
+            .chat("system", "hello")
+            .await
+            .unwrap();
+        assert_eq!(reply, "Hello.");
+        let request = rx.recv().unwrap();
+        assert_eq!(request.path, "/v1/messages");
+        assert_eq!(request.headers["x-api-key"], "test-key");
+        assert_eq!(request.headers["anthropic-version"], ANTHROPIC_VERSION);
+        assert_eq!(request.body["system"], "system");
+        assert_eq!(request.body["max_tokens"], 256);
+        assert_eq!(request.body["messages"][0]["role"], "user");
+        assert_eq!(request.body["messages"][0]["content"], "hello");
     }
 
-    prompt
+    #[tokio::test]
+    async fn calls_parrot_backend() {
+        let (url, rx) = serve_once(200, r#"{"text":"Hola."}"#);
+        let prompt = PromptContext {
+            target_language: Some("Spanish"),
+            ..Default::default()
+        };
+        let reply = provider(ProviderKind::Parrot, url)
+            .cleanup("hello", &prompt)
+            .await
+            .unwrap();
+        assert_eq!(reply, "Hola.");
+        let request = rx.recv().unwrap();
+        assert_eq!(request.path, "/api/cleanup");
+        assert_eq!(request.headers["authorization"], "Bearer test-session");
+        assert_eq!(request.headers["x-api-key"], "test-key");
+        assert_eq!(
+            request.body,
+            serde_json::json!({"text": "hello", "target_language": "Spanish"})
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_error_statuses() {
+        for kind in [
+            ProviderKind::Ollama,
+            ProviderKind::OpenAi,
+            ProviderKind::Anthropic,
+            ProviderKind::Parrot,
+        ] {
+            let (url, _rx) = serve_once(429, r#"{"error":"rate limited"}"#);
+            let error = provider(kind, url)
+                .cleanup("hello", &PromptContext::default())
+                .await
+                .unwrap_err()
+                .to_string();
+            assert!(error.contains("429"), "{:?}: {}", kind, error);
+            assert!(error.contains("rate limited"), "{:?}: {}", kind, error);
+        }
+    }
+
+    #[tokio::test]
+    async fn streams_lines_split_across_chunks() {
+        // "é" is split between its two bytes
+        let (url, rx) = serve_stream(&[
+            b"{\"message\":{\"role\":\"assistant\",\"content\":\"Caf",
+            b"\xc3",
+            b"\xa9\"}}\n{\"message\":{\"role\":\"assistant\",\"content\":\" au lait.\"}}\n",
+        ]);
+        let (reply, deltas) = stream(ProviderKind::Ollama, url).await;
+        assert_eq!(reply.unwrap(), "Café au lait.");
+        assert_eq!(deltas, ["Café", " au lait."]);
+        assert_eq!(rx.recv().unwrap().body["stream"], true);
+    }
+
+    #[tokio::test]
+    async fn streams_openai_events() {
+        let (url, rx) = serve_stream(&[
+            b"data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n",
+            b"data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n: keep-alive\n\n",
+            b"data: {\"choices\":[{\"delta\":{\"content\":\"lo.\"}}]}\n\ndata: [DONE]\n\n",
+        ]);
+        let (reply, deltas) = stream(ProviderKind::OpenAi, url).await;
+        assert_eq!(reply.unwrap(), "Hello.");
+        assert_eq!(deltas, ["Hel", "lo."]);
+        assert_eq!(rx.recv().unwrap().body["stream"], true);
+    }
+
+    #[tokio::test]
+    async fn streams_ollama_lines() {
+        let (url, _rx) = serve_stream(&[
+            b"{\"message\":{\"role\":\"assistant\",\"content\":\"Hel\"},\"done\":false}\n",
+            b"{\"message\":{\"role\":\"assistant\",\"content\":\"lo.\"},\"done\":false}\n",
+            b"{\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true,\"eval_count\":2}\n",
+        ]);
+        let (reply, deltas) = stream(ProviderKind::Ollama, url).await;
+        assert_eq!(reply.unwrap(), "Hello.");
+        assert_eq!(deltas, ["Hel", "lo."]);
+    }
+
+    #[tokio::test]
+    async fn reports_errors_partway_through_a_stream() {
+        let (url, _rx) = serve_stream(&[
+            b"{\"message\":{\"role\":\"assistant\",\"content\":\"Hel\"},\"done\":false}\n",
+            b"{\"error\":\"model runner stopped\"}\n",
+        ]);
+        let (reply, deltas) = stream(ProviderKind::Ollama, url).await;
+        let error = reply.unwrap_err().to_string();
+        assert!(error.contains("model runner stopped"), "{}", error);
+        assert_eq!(deltas, ["Hel"]);
+
+        let (url, _rx) = serve_stream(&[
+            b"data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+            b"data: {\"error\":{\"message\":\"overloaded\",\"type\":\"server_error\"}}\n\n",
+        ]);
+        let (reply, deltas) = stream(ProviderKind::OpenAi, url).await;
+        let error = reply.unwrap_err().to_string();
+        assert!(error.contains("overloaded"), "{}", error);
+        assert_eq!(deltas, ["Hel"]);
+    }
+
+    #[test]
+    fn translates_with_custom_templates() {
+        let template = PromptTemplate {
+            format: "plain".to_string(),
+            system_prompt: "Fix the punctuation.".to_string(),
+            user_prompt: "{{raw_text}}".to_string(),
+        };
+        let mut prompt = PromptContext {
+            template: Some(&template),
+            ..Default::default()
+        };
+        let (system_prompt, _) = build_cleanup_prompt(&prompt, "hello").unwrap();
+        assert_eq!(system_prompt, "Fix the punctuation.");
+
+        prompt.target_language = Some("Spanish");
+        let (system_prompt, _) = build_cleanup_prompt(&prompt, "hello").unwrap();
+        assert_eq!(
+            system_prompt,
+            "Fix the punctuation.\n\nTranslate the cleaned text into Spanish. Return ONLY the translation."
+        );
+
+        let placed = PromptTemplate {
+            system_prompt: "Fix the punctuation, then write it in {{target_language}}.".to_string(),
+            ..template.clone()
+        };
+        prompt.template = Some(&placed);
+        let (system_prompt, _) = build_cleanup_prompt(&prompt, "hello").unwrap();
+        assert_eq!(
+            system_prompt,
+            "Fix the punctuation, then write it in Spanish."
+        );
+    }
+
+    #[test]
+    fn validates_settings() {
+        assert!(validate_setting("cleanup_provider", "anthropic").is_ok());
+        assert!(validate_setting("cleanup_provider", "").is_ok());
+        assert!(validate_setting("cleanup_provider", "gemini").is_err());
+        assert!(validate_setting("cleanup_temperature", "0.7").is_ok());
+        assert!(validate_setting("cleanup_temperature", "3").is_err());
+        assert!(validate_setting("cleanup_max_tokens", "0").is_err());
+        assert!(validate_setting("cleanup_base_url", "anything").is_ok());
+    }
 }
@@ -1,7 +1,7 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
-const BACKEND_URL: &str = "http://localhost:3001";
+pub const BACKEND_URL: &str = "http://localhost:3001";
 
 // -- History --
 
@@ -22,6 +22,12 @@ pub struct DictationEntry {
     pub provider: String,
     pub duration_ms: i64,
     pub created_at: String,
+    /// Empty unless the dictation was translated
+    #[serde(default)]
+    pub translated_text: String,
+    /// Why the LLM cleanup was discarded, if it was
+    #[serde(default)]
+    pub cleanup_note: String,
 }
 
 #[derive(Deserialize)]
@@ -30,6 +36,7 @@ struct HistoryResponse {
 }
 
 pub async fn insert_dictation(
+    client: &reqwest::Client,
     session_token: &str,
     id: &str,
     raw_text: &str,
@@ -37,7 +44,6 @@ pub async fn insert_dictation(
     provider: &str,
     duration_ms: i64,
 ) -> Result<()> {
-    let client = reqwest::Client::new();
     let resp = client
         .post(format!("{}/api/history", BACKEND_URL))
         .header("Authorization", format!("Bearer {}", session_token))
@@ -61,15 +67,61 @@ pub async fn insert_dictation(
 }
 
 pub async fn update_dictation_cleaned(
+    client: &reqwest::Client,
     session_token: &str,
     id: &str,
     cleaned_text: &str,
 ) -> Result<()> {
-    let client = reqwest::Client::new();
+    update_dictation(
+        client,
+        session_token,
+        id,
+        serde_json::json!({ "cleaned_text": cleaned_text }),
+    )
+    .await
+}
+
+pub async fn update_dictation_translated(
+    client: &reqwest::Client,
+    session_token: &str,
+    id: &str,
+    translated_text: &str,
+) -> Result<()> {
+    update_dictation(
+        client,
+        session_token,
+        id,
+        serde_json::json!({ "translated_text": translated_text }),
+    )
+    .await
+}
+
+/// Record why the LLM cleanup was discarded
+pub async fn update_dictation_note(
+    client: &reqwest::Client,
+    session_token: &str,
+    id: &str,
+    note: &str,
+) -> Result<()> {
+    update_dictation(
+        client,
+        session_token,
+        id,
+        serde_json::json!({ "cleanup_note": note }),
+    )
+    .await
+}
+
+async fn update_dictation(
+    client: &reqwest::Client,
+    session_token: &str,
+    id: &str,
+    fields: serde_json::Value,
+) -> Result<()> {
     let resp = client
         .patch(format!("{}/api/history/{}", BACKEND_URL, id))
         .header("Authorization", format!("Bearer {}", session_token))
-        .json(&serde_json::json!({ "cleaned_text": cleaned_text }))
+        .json(&fields)
         .send()
         .await?;
 
@@ -82,8 +134,10 @@ pub async fn update_dictation_cleaned(
     Ok(())
 }
 
-pub async fn get_history(session_token: &str) -> Result<Vec<DictationEntry>> {
-    let client = reqwest::Client::new();
+pub async fn get_history(
+    client: &reqwest::Client,
+    session_token: &str,
+) -> Result<Vec<DictationEntry>> {
     let resp = client
         .get(format!("{}/api/history", BACKEND_URL))
         .header("Authorization", format!("Bearer {}", session_token))
@@ -100,8 +154,11 @@ pub async fn get_history(session_token: &str) -> Result<Vec<DictationEntry>> {
     Ok(result.entries)
 }
 
-pub async fn search_history(session_token: &str, query: &str) -> Result<Vec<DictationEntry>> {
-    let client = reqwest::Client::new();
+pub async fn search_history(
+    client: &reqwest::Client,
+    session_token: &str,
+    query: &str,
+) -> Result<Vec<DictationEntry>> {
     let resp = client
         .get(format!("{}/api/history?q={}", BACKEND_URL, urlencoding::encode(query)))
         .header("Authorization", format!("Bearer {}", session_token))
@@ -127,8 +184,7 @@ pub struct Profile {
     pub writing_style: String,
 }
 
-pub async fn get_profile(session_token: &str) -> Result<Profile> {
-    let client = reqwest::Client::new();
+pub async fn get_profile(client: &reqwest::Client, session_token: &str) -> Result<Profile> {
     let resp = client
         .get(format!("{}/api/profile", BACKEND_URL))
         .header("Authorization", format!("Bearer {}", session_token))
@@ -146,12 +202,12 @@ pub async fn get_profile(session_token: &str) -> Result<Profile> {
 }
 
 pub async fn update_profile(
+    client: &reqwest::Client,
     session_token: &str,
     custom_words: &str,
     context_prompt: &str,
     writing_style: &str,
 ) -> Result<()> {
-    let client = reqwest::Client::new();
     let resp = client
         .put(format!("{}/api/profile", BACKEND_URL))
         .header("Authorization", format!("Bearer {}", session_token))
@@ -0,0 +1,260 @@
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+
+use crate::db::{Correction, Database};
+use crate::guardrails::edit_distance;
+
+/// Past corrections looked at when picking examples and suggesting words
+const RECENT_CORRECTIONS: usize = 200;
+/// Few-shot examples added to the cleanup prompt
+const MAX_EXAMPLES: usize = 3;
+/// Longer corrections would crowd the prompt
+const MAX_EXAMPLE_CHARS: usize = 600;
+/// Share of words a past dictation must have in common with this one to serve as an example
+const MIN_EXAMPLE_SIMILARITY: f64 = 0.15;
+/// Beyond this word-level edit distance the user wrote something else rather than fixing it
+const MAX_CORRECTION_DISTANCE: f64 = 0.7;
+/// A term must be fixed in this many dictations before it is suggested, unless its
+/// casing ("GitHub") or digits make it plainly a name
+const MIN_SUGGESTION_COUNT: usize = 2;
+const MAX_SUGGESTIONS: usize = 20;
+/// Dictations longer than this aren't diffed for suggestions
+const MAX_DIFF_WORDS: usize = 500;
+
+/// A term the user keeps correcting, offered as a custom word
+#[derive(serde::Serialize, Clone)]
+pub struct WordSuggestion {
+    pub word: String,
+    /// Dictations in which it was corrected
+    pub count: usize,
+}
+
+/// Setting `learn_from_corrections`: "false" stops recording corrections and adding
+/// them to the prompt
+pub fn is_enabled(db: &Database) -> bool {
+    db.get_setting("learn_from_corrections")
+        .ok()
+        .flatten()
+        .is_none_or(|v| v != "false")
+}
+
+/// Keep the user's edit of a cleaned dictation. Unchanged text, and text replaced
+/// outright rather than corrected, teach nothing and are skipped.
+pub fn record(
+    db: &Database,
+    dictation_id: &str,
+    raw_text: &str,
+    model_output: &str,
+    corrected_text: &str,
+) -> Result<()> {
+    if !is_enabled(db) || model_output.trim() == corrected_text.trim() {
+        return Ok(());
+    }
+    let model_words = lowercase_words(model_output);
+    let corrected_words = lowercase_words(corrected_text);
+    if corrected_words.is_empty() {
+        return Ok(());
+    }
+    let distance = edit_distance(&model_words, &corrected_words) as f64
+        / model_words.len().max(corrected_words.len()) as f64;
+    if distance > MAX_CORRECTION_DISTANCE {
+        return Ok(());
+    }
+    db.save_correction(
+        dictation_id,
+        raw_text,
+        model_output.trim(),
+        corrected_text.trim(),
+    )
+}
+
+/// The past corrections most like `raw_text`, most similar first
+pub fn examples(db: &Database, raw_text: &str) -> Vec<Correction> {
+    if !is_enabled(db) {
+        return Vec::new();
+    }
+    let corrections = match db.get_corrections(RECENT_CORRECTIONS) {
+        Ok(corrections) => corrections,
+        Err(e) => {
+            eprintln!("Failed to load corrections: {}", e);
+            return Vec::new();
+        }
+    };
+    let words = content_words(raw_text);
+    let mut scored: Vec<(f64, Correction)> = corrections
+        .into_iter()
+        .filter(|c| {
+            c.raw_text.len() + c.model_output.len() + c.corrected_text.len() <= MAX_EXAMPLE_CHARS
+        })
+        .map(|c| (similarity(&words, &content_words(&c.raw_text)), c))
+        .filter(|(score, _)| *score >= MIN_EXAMPLE_SIMILARITY)
+        .collect();
+    // Stable, so equally similar corrections stay newest first
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored
+        .into_iter()
+        .take(MAX_EXAMPLES)
+        .map(|(_, c)| c)
+        .collect()
+}
+
+/// Names and terms the user put in place of what the model wrote, skipping those
+/// already in `custom_words`
+pub fn suggest_words(corrections: &[Correction], custom_words: &[String]) -> Vec<WordSuggestion> {
+    let known: HashSet<String> = custom_words
+        .iter()
+        .map(|w| w.trim().to_lowercase())
+        .collect();
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for correction in corrections {
+        let terms: HashSet<String> =
+            corrected_terms(&correction.model_output, &correction.corrected_text)
+                .into_iter()
+                .filter(|t| !known.contains(&t.to_lowercase()))
+                .collect();
+        for term in terms {
+            *counts.entry(term).or_default() += 1;
+        }
+    }
+    let mut suggestions: Vec<WordSuggestion> = counts
+        .into_iter()
+        .filter(|(word, count)| *count >= MIN_SUGGESTION_COUNT || is_distinctive(word))
+        .map(|(word, count)| WordSuggestion { word, count })
+        .collect();
+    suggestions.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.word.cmp(&b.word)));
+    suggestions.truncate(MAX_SUGGESTIONS);
+    suggestions
+}
+
+pub fn load_suggestions(db: &Database, custom_words: &[String]) -> Result<Vec<WordSuggestion>> {
+    Ok(suggest_words(
+        &db.get_corrections(RECENT_CORRECTIONS)?,
+        custom_words,
+    ))
+}
+
+/// Runs of capitalized or alphanumeric words the user wrote where the model wrote
+/// something else ("get hub" → "GitHub", "visual studio" → "Visual Studio")
+fn corrected_terms(model_output: &str, corrected_text: &str) -> Vec<String> {
+    let before = tokens(model_output);
+    let after = tokens(corrected_text);
+    if before.len() > MAX_DIFF_WORDS || after.len() > MAX_DIFF_WORDS {
+        return Vec::new();
+    }
+    let before_words: Vec<&str> = before.iter().map(|t| t.word).collect();
+    let after_words: Vec<&str> = after.iter().map(|t| t.word).collect();
+    let mut terms = Vec::new();
+    for (removed, added) in changed_spans(&before_words, &after_words) {
+        // Pure insertions are additions, not fixes of a misheard word
+        if removed.is_empty() {
+            continue;
+        }
+        let mut run: Vec<&str> = Vec::new();
+        for token in &after[added] {
+            if is_term(token) && !before_words.contains(&token.word) {
+                run.push(token.word);
+            } else if !run.is_empty() {
+                terms.push(run.join(" "));
+                run.clear();
+            }
+        }
+        if !run.is_empty() {
+            terms.push(run.join(" "));
+        }
+    }
+    terms
+}
+
+struct Token<'a> {
+    /// Without surrounding punctuation
+    word: &'a str,
+    sentence_start: bool,
+}
+
+fn tokens(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut sentence_start = true;
+    for raw in text.split_whitespace() {
+        let word = raw.trim_matches(|c: char| !c.is_alphanumeric());
+        if !word.is_empty() {
+            tokens.push(Token {
+                word,
+                sentence_start,
+            });
+        }
+        sentence_start = raw.ends_with(['.', '!', '?', ':']);
+    }
+    tokens
+}
+
+/// Capitalized or containing digits, but not just a capital that starts a sentence
+fn is_term(token: &Token) -> bool {
+    let word = token.word;
+    word.chars().count() >= 2
+        && word.chars().any(char::is_alphabetic)
+        && (is_distinctive(word) || (!token.sentence_start && word.starts_with(char::is_uppercase)))
+}
+
+/// Casing or digits no ordinary word has: "GitHub", "iOS", "K8s"
+fn is_distinctive(term: &str) -> bool {
+    term.split(' ').any(|word| {
+        word.chars().skip(1).any(char::is_uppercase) || word.chars().any(|c| c.is_numeric())
+    })
+}
+
+/// Index ranges that differ between `a` and `b`, paired up, from a longest common subsequence
+fn changed_spans(a: &[&str], b: &[&str]) -> Vec<(std::ops::Range<usize>, std::ops::Range<usize>)> {
+    // common[i][j]: length of the longest common subsequence of a[i..] and b[j..]
+    let mut common = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            common[i][j] = if a[i] == b[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+    let mut spans = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let (mut start_a, mut start_b) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            if start_a < i || start_b < j {
+                spans.push((start_a..i, start_b..j));
+            }
+            i += 1;
+            j += 1;
+            start_a = i;
+            start_b = j;
+        } else if j < b.len() && (i == a.len() || common[i][j + 1] >= common[i + 1][j]) {
+            j += 1;
+        } else {
+            i += 1;
+        }
+    }
+    if start_a < a.len() || start_b < b.len() {
+        spans.push((start_a..a.len(), start_b..b.len()));
+    }
+    spans
+}
+
+fn lowercase_words(text: &str) -> Vec<String> {
+    tokens(text).iter().map(|t| t.word.to_lowercase()).collect()
+}
+
+/// Lower-cased words long enough to say something about the topic
+fn content_words(text: &str) -> HashSet<String> {
+    lowercase_words(text)
+        .into_iter()
+        .filter(|w| w.chars().count() > 3)
+        .collect()
+}
+
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
@@ -1,8 +1,11 @@
 use anyhow::Result;
-use rusqlite::Connection;
+use rusqlite::{Connection, OptionalExtension};
 use std::path::PathBuf;
 use std::sync::Mutex;
 
+/// The profile migrated from the original single-row `profile` table; it can't be deleted
+pub const DEFAULT_PROFILE_ID: i64 = 1;
+
 pub struct Database {
     conn: Mutex<Connection>,
 }
@@ -53,8 +56,99 @@ impl Database {
             );
 
             INSERT OR IGNORE INTO profile (id) VALUES (1);
+
+            CREATE TABLE IF NOT EXISTS profiles (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE,
+                custom_words TEXT NOT NULL DEFAULT '[]',
+                context_prompt TEXT NOT NULL DEFAULT '',
+                writing_style TEXT NOT NULL DEFAULT '',
+                language TEXT NOT NULL DEFAULT '',
+                cleanup_model TEXT NOT NULL DEFAULT ''
+            );
+
+            INSERT OR IGNORE INTO profiles (id, name, custom_words, context_prompt, writing_style)
+                SELECT id, 'Default', custom_words, context_prompt, writing_style FROM profile WHERE id = 1;
+
+            CREATE TABLE IF NOT EXISTS app_rules (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                pattern TEXT NOT NULL,
+                match_on TEXT NOT NULL DEFAULT 'app',
+                profile_id INTEGER,
+                context TEXT NOT NULL DEFAULT ''
+            );
+
+            CREATE TABLE IF NOT EXISTS snippets (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                phrase TEXT NOT NULL,
+                text TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS corrections (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                dictation_id TEXT NOT NULL,
+                raw_text TEXT NOT NULL,
+                model_output TEXT NOT NULL,
+                corrected_text TEXT NOT NULL,
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+
+            CREATE TABLE IF NOT EXISTS prompt_templates (
+                format TEXT PRIMARY KEY,
+                system_prompt TEXT NOT NULL,
+                user_prompt TEXT NOT NULL
+            );
             ",
         )?;
+
+        add_column_if_missing(
+            &conn,
+            "dictation_history",
+            "translated_text",
+            "TEXT NOT NULL DEFAULT ''",
+        )?;
+        add_column_if_missing(
+            &conn,
+            "dictation_history",
+            "target_language",
+            "TEXT NOT NULL DEFAULT ''",
+        )?;
+        add_column_if_missing(
+            &conn,
+            "dictation_history",
+            "app_name",
+            "TEXT NOT NULL DEFAULT ''",
+        )?;
+        add_column_if_missing(
+            &conn,
+            "dictation_history",
+            "cleanup_note",
+            "TEXT NOT NULL DEFAULT ''",
+        )?;
+        add_column_if_missing(
+            &conn,
+            "profiles",
+            "insertion_method",
+            "TEXT NOT NULL DEFAULT ''",
+        )?;
+        add_column_if_missing(
+            &conn,
+            "profiles",
+            "text_normalization",
+            "TEXT NOT NULL DEFAULT ''",
+        )?;
+        add_column_if_missing(
+            &conn,
+            "profiles",
+            "output_format",
+            "TEXT NOT NULL DEFAULT ''",
+        )?;
+        add_column_if_missing(
+            &conn,
+            "app_rules",
+            "output_format",
+            "TEXT NOT NULL DEFAULT ''",
+        )?;
         Ok(())
     }
 
@@ -81,11 +175,12 @@ impl Database {
         cleaned_text: &str,
         provider: &str,
         duration_ms: i64,
+        target_language: &str,
     ) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT INTO dictation_history (id, raw_text, cleaned_text, provider, duration_ms) VALUES (?1, ?2, ?3, ?4, ?5)",
-            rusqlite::params![id, raw_text, cleaned_text, provider, duration_ms],
+            "INSERT INTO dictation_history (id, raw_text, cleaned_text, provider, duration_ms, target_language) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![id, raw_text, cleaned_text, provider, duration_ms, target_language],
         )?;
         Ok(())
     }
@@ -93,7 +188,7 @@ impl Database {
     pub fn get_history(&self) -> Result<Vec<DictationEntry>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, raw_text, cleaned_text, provider, duration_ms, created_at FROM dictation_history ORDER BY created_at DESC",
+            "SELECT id, raw_text, cleaned_text, provider, duration_ms, created_at, translated_text, target_language, app_name, cleanup_note FROM dictation_history ORDER BY created_at DESC",
         )?;
         let entries = stmt
             .query_map([], |row| {
@@ -104,6 +199,10 @@ impl Database {
                     provider: row.get(3)?,
                     duration_ms: row.get(4)?,
                     created_at: row.get(5)?,
+                    translated_text: row.get(6)?,
+                    target_language: row.get(7)?,
+                    app_name: row.get(8)?,
+                    cleanup_note: row.get(9)?,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
@@ -114,7 +213,7 @@ impl Database {
         let conn = self.conn.lock().unwrap();
         let pattern = format!("%{}%", query);
         let mut stmt = conn.prepare(
-            "SELECT id, raw_text, cleaned_text, provider, duration_ms, created_at FROM dictation_history WHERE raw_text LIKE ?1 OR cleaned_text LIKE ?1 ORDER BY created_at DESC",
+            "SELECT id, raw_text, cleaned_text, provider, duration_ms, created_at, translated_text, target_language, app_name, cleanup_note FROM dictation_history WHERE raw_text LIKE ?1 OR cleaned_text LIKE ?1 OR translated_text LIKE ?1 ORDER BY created_at DESC",
         )?;
         let entries = stmt
             .query_map([&pattern], |row| {
@@ -125,27 +224,281 @@ impl Database {
                     provider: row.get(3)?,
                     duration_ms: row.get(4)?,
                     created_at: row.get(5)?,
+                    translated_text: row.get(6)?,
+                    target_language: row.get(7)?,
+                    app_name: row.get(8)?,
+                    cleanup_note: row.get(9)?,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
         Ok(entries)
     }
 
-    pub fn get_profile(&self) -> Result<Profile> {
+    pub fn get_dictation(&self, id: &str) -> Result<Option<DictationEntry>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT custom_words, context_prompt, writing_style FROM profile WHERE id = 1",
+            "SELECT id, raw_text, cleaned_text, provider, duration_ms, created_at, translated_text, target_language, app_name, cleanup_note FROM dictation_history WHERE id = ?1",
         )?;
-        let profile = stmt.query_row([], |row| {
-            Ok(Profile {
-                custom_words: row.get(0)?,
-                context_prompt: row.get(1)?,
-                writing_style: row.get(2)?,
+        let mut entries = stmt.query_map([id], |row| {
+            Ok(DictationEntry {
+                id: row.get(0)?,
+                raw_text: row.get(1)?,
+                cleaned_text: row.get(2)?,
+                provider: row.get(3)?,
+                duration_ms: row.get(4)?,
+                created_at: row.get(5)?,
+                translated_text: row.get(6)?,
+                target_language: row.get(7)?,
+                app_name: row.get(8)?,
+                cleanup_note: row.get(9)?,
             })
         })?;
+        Ok(entries.next().transpose()?)
+    }
+
+    pub fn update_dictation_app(&self, id: &str, app_name: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE dictation_history SET app_name = ?1 WHERE id = ?2",
+            [app_name, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn update_dictation_note(&self, id: &str, note: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE dictation_history SET cleanup_note = ?1 WHERE id = ?2",
+            [note, id],
+        )?;
+        Ok(())
+    }
+
+    /// The default profile, used when a shortcut has no profile of its own
+    pub fn get_profile(&self) -> Result<Profile> {
+        self.get_profile_by_id(DEFAULT_PROFILE_ID)?
+            .ok_or_else(|| anyhow::anyhow!("Default profile is missing"))
+    }
+
+    pub fn get_profile_by_id(&self, id: i64) -> Result<Option<Profile>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, custom_words, context_prompt, writing_style, language, cleanup_model, insertion_method, text_normalization, output_format FROM profiles WHERE id = ?1",
+        )?;
+        let profile = stmt.query_row([id], profile_from_row).optional()?;
         Ok(profile)
     }
 
+    pub fn list_profiles(&self) -> Result<Vec<Profile>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, custom_words, context_prompt, writing_style, language, cleanup_model, insertion_method, text_normalization, output_format FROM profiles ORDER BY id",
+        )?;
+        let profiles = stmt
+            .query_map([], profile_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(profiles)
+    }
+
+    pub fn create_profile(&self, profile: &ProfileInput) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO profiles (name, custom_words, context_prompt, writing_style, language, cleanup_model, insertion_method, text_normalization, output_format) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            rusqlite::params![
+                profile.name,
+                profile.custom_words,
+                profile.context_prompt,
+                profile.writing_style,
+                profile.language,
+                profile.cleanup_model,
+                profile.insertion_method,
+                profile.text_normalization,
+                profile.output_format
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn save_profile(&self, id: i64, profile: &ProfileInput) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE profiles SET name = ?1, custom_words = ?2, context_prompt = ?3, writing_style = ?4, language = ?5, cleanup_model = ?6, insertion_method = ?7, text_normalization = ?8, output_format = ?9 WHERE id = ?10",
+            rusqlite::params![
+                profile.name,
+                profile.custom_words,
+                profile.context_prompt,
+                profile.writing_style,
+                profile.language,
+                profile.cleanup_model,
+                profile.insertion_method,
+                profile.text_normalization,
+                profile.output_format,
+                id
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Rules in the order they are tried; the first match wins
+    pub fn get_app_rules(&self) -> Result<Vec<AppRule>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT pattern, match_on, profile_id, context, output_format FROM app_rules ORDER BY id",
+        )?;
+        let rules = stmt
+            .query_map([], |row| {
+                Ok(AppRule {
+                    pattern: row.get(0)?,
+                    match_on: row.get(1)?,
+                    profile_id: row.get(2)?,
+                    context: row.get(3)?,
+                    output_format: row.get(4)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(rules)
+    }
+
+    /// Replace all rules, keeping their order
+    pub fn save_app_rules(&self, rules: &[AppRule]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM app_rules", [])?;
+        for rule in rules {
+            tx.execute(
+                "INSERT INTO app_rules (pattern, match_on, profile_id, context, output_format) VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![
+                    rule.pattern,
+                    rule.match_on,
+                    rule.profile_id,
+                    rule.context,
+                    rule.output_format
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn get_snippets(&self) -> Result<Vec<Snippet>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT phrase, text FROM snippets ORDER BY id")?;
+        let snippets = stmt
+            .query_map([], |row| {
+                Ok(Snippet {
+                    phrase: row.get(0)?,
+                    text: row.get(1)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(snippets)
+    }
+
+    /// Replace all snippets, keeping their order
+    pub fn save_snippets(&self, snippets: &[Snippet]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM snippets", [])?;
+        for snippet in snippets {
+            tx.execute(
+                "INSERT INTO snippets (phrase, text) VALUES (?1, ?2)",
+                [&snippet.phrase, &snippet.text],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// A dictation corrected again keeps its first model output, so the triple still
+    /// shows what the model got wrong
+    pub fn save_correction(
+        &self,
+        dictation_id: &str,
+        raw_text: &str,
+        model_output: &str,
+        corrected_text: &str,
+    ) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE corrections SET corrected_text = ?1, created_at = datetime('now') WHERE dictation_id = ?2",
+            [corrected_text, dictation_id],
+        )?;
+        if updated == 0 {
+            conn.execute(
+                "INSERT INTO corrections (dictation_id, raw_text, model_output, corrected_text) VALUES (?1, ?2, ?3, ?4)",
+                [dictation_id, raw_text, model_output, corrected_text],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Newest first
+    pub fn get_corrections(&self, limit: usize) -> Result<Vec<Correction>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, dictation_id, raw_text, model_output, corrected_text, created_at FROM corrections ORDER BY created_at DESC, id DESC LIMIT ?1",
+        )?;
+        let corrections = stmt
+            .query_map([limit as i64], |row| {
+                Ok(Correction {
+                    id: row.get(0)?,
+                    dictation_id: row.get(1)?,
+                    raw_text: row.get(2)?,
+                    model_output: row.get(3)?,
+                    corrected_text: row.get(4)?,
+                    created_at: row.get(5)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(corrections)
+    }
+
+    pub fn clear_corrections(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM corrections", [])?;
+        Ok(())
+    }
+
+    /// The saved template for an output format; None means the built-in one is used
+    pub fn get_prompt_template(&self, format: &str) -> Result<Option<PromptTemplate>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT format, system_prompt, user_prompt FROM prompt_templates WHERE format = ?1",
+        )?;
+        let mut templates = stmt.query_map([format], |row| {
+            Ok(PromptTemplate {
+                format: row.get(0)?,
+                system_prompt: row.get(1)?,
+                user_prompt: row.get(2)?,
+            })
+        })?;
+        Ok(templates.next().transpose()?)
+    }
+
+    pub fn save_prompt_template(&self, template: &PromptTemplate) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO prompt_templates (format, system_prompt, user_prompt) VALUES (?1, ?2, ?3)",
+            [&template.format, &template.system_prompt, &template.user_prompt],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_prompt_template(&self, format: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM prompt_templates WHERE format = ?1", [format])?;
+        Ok(())
+    }
+
+    pub fn delete_profile(&self, id: i64) -> Result<()> {
+        if id == DEFAULT_PROFILE_ID {
+            anyhow::bail!("The default profile can't be deleted");
+        }
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM profiles WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
     pub fn update_dictation_cleaned(&self, id: &str, cleaned_text: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
@@ -155,6 +508,15 @@ impl Database {
         Ok(())
     }
 
+    pub fn update_dictation_translated(&self, id: &str, translated_text: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE dictation_history SET translated_text = ?1 WHERE id = ?2",
+            [translated_text, id],
+        )?;
+        Ok(())
+    }
+
     pub fn update_profile(
         &self,
         custom_words: &str,
@@ -163,13 +525,34 @@ impl Database {
     ) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "UPDATE profile SET custom_words = ?1, context_prompt = ?2, writing_style = ?3 WHERE id = 1",
-            [custom_words, context_prompt, writing_style],
+            "UPDATE profiles SET custom_words = ?1, context_prompt = ?2, writing_style = ?3 WHERE id = ?4",
+            rusqlite::params![custom_words, context_prompt, writing_style, DEFAULT_PROFILE_ID],
         )?;
         Ok(())
     }
 }
 
+/// Columns added after the first release; CREATE TABLE IF NOT EXISTS won't add them to existing databases
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|name| name.ok())
+        .any(|name| name == column);
+    if !exists {
+        conn.execute_batch(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, definition
+        ))?;
+    }
+    Ok(())
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone)]
 pub struct DictationEntry {
     pub id: String,
@@ -178,11 +561,120 @@ pub struct DictationEntry {
     pub provider: String,
     pub duration_ms: i64,
     pub created_at: String,
+    pub translated_text: String,
+    pub target_language: String,
+    /// Application that had focus when the dictation started, if it could be detected
+    pub app_name: String,
+    /// Why the LLM output was discarded, if it was
+    pub cleanup_note: String,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Clone)]
 pub struct Profile {
+    pub id: i64,
+    pub name: String,
     pub custom_words: String,
     pub context_prompt: String,
     pub writing_style: String,
+    /// Spoken language hint for transcription, empty to auto-detect
+    pub language: String,
+    /// Overrides the `llm_model` setting when not empty
+    pub cleanup_model: String,
+    /// "clipboard" or "type"; empty uses the `paste_method` setting
+    pub insertion_method: String,
+    /// How spoken numbers are written: "" (standard), "all_digits" or "off"
+    pub text_normalization: String,
+    /// "" (plain), "markdown" or "code"
+    pub output_format: String,
+}
+
+/// Matches the focused application to a profile and/or extra cleanup context
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct AppRule {
+    /// Case-insensitive substring of the app name or window title
+    pub pattern: String,
+    /// "app" or "title"
+    #[serde(default = "default_match_on")]
+    pub match_on: String,
+    /// Used instead of the default profile; a shortcut's own profile still takes precedence
+    #[serde(default)]
+    pub profile_id: Option<i64>,
+    /// Appended to the cleanup prompt, e.g. "This is a terminal: output commands verbatim"
+    #[serde(default)]
+    pub context: String,
+    /// Overrides the profile's output format when not empty
+    #[serde(default)]
+    pub output_format: String,
+}
+
+/// Text inserted when its trigger phrase is dictated
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Snippet {
+    pub phrase: String,
+    /// May contain {date}, {time} and {clipboard}
+    pub text: String,
+}
+
+/// A cleaned dictation the user edited: what was said, what the model made of it,
+/// and what the user wanted instead
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Correction {
+    pub id: i64,
+    pub dictation_id: String,
+    /// The text the model was given
+    pub raw_text: String,
+    pub model_output: String,
+    pub corrected_text: String,
+    pub created_at: String,
+}
+
+/// The cleanup prompt for one output format, with {{variables}} filled in per dictation
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct PromptTemplate {
+    /// "plain", "markdown" or "code"
+    pub format: String,
+    pub system_prompt: String,
+    /// The user message; usually just {{raw_text}}
+    pub user_prompt: String,
+}
+
+fn default_match_on() -> String {
+    "app".to_string()
+}
+
+/// Editable fields of a profile, as sent by the frontend
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct ProfileInput {
+    pub name: String,
+    #[serde(default)]
+    pub custom_words: String,
+    #[serde(default)]
+    pub context_prompt: String,
+    #[serde(default)]
+    pub writing_style: String,
+    #[serde(default)]
+    pub language: String,
+    #[serde(default)]
+    pub cleanup_model: String,
+    #[serde(default)]
+    pub insertion_method: String,
+    #[serde(default)]
+    pub text_normalization: String,
+    #[serde(default)]
+    pub output_format: String,
+}
+
+fn profile_from_row(row: &rusqlite::Row) -> rusqlite::Result<Profile> {
+    Ok(Profile {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        custom_words: row.get(2)?,
+        context_prompt: row.get(3)?,
+        writing_style: row.get(4)?,
+        language: row.get(5)?,
+        cleanup_model: row.get(6)?,
+        insertion_method: row.get(7)?,
+        text_normalization: row.get(8)?,
+        output_format: row.get(9)?,
+    })
 }
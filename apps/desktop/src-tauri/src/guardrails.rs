@@ -0,0 +1,256 @@
+use anyhow::Result;
+use std::collections::HashSet;
+
+use crate::db::Database;
+use crate::output_format::OutputFormat;
+
+/// Openings models put before the text they were asked for ("Here is the cleaned text:")
+const PREAMBLES: &[&str] = &[
+    "here is",
+    "here's",
+    "sure",
+    "certainly",
+    "of course",
+    "okay, here",
+    "ok, here",
+    "cleaned text",
+    "corrected text",
+    "cleaned up text",
+    "the cleaned",
+    "the corrected",
+];
+/// Closings models add after it ("Let me know if you need anything else.")
+const POSTSCRIPTS: &[&str] = &["let me know", "i hope", "hope this helps", "note:", "(note"];
+/// A preamble is a short lead-in, not a sentence of the dictation
+const MAX_PREAMBLE_WORDS: usize = 12;
+
+/// Dictations shorter than this are too short for ratios to mean much
+const MIN_WORDS: usize = 4;
+const MIN_LENGTH_RATIO: f64 = 0.3;
+const MAX_LENGTH_RATIO: f64 = 1.8;
+/// Translations change every word, so only wildly different lengths count
+const MAX_TRANSLATION_RATIO: f64 = 3.0;
+/// Share of the reply's words that must come from the dictation
+const MIN_PRECISION: f64 = 0.5;
+/// Share of the dictation's words that must survive; lower, since fillers are removed
+const MIN_RECALL: f64 = 0.4;
+/// Word-level edit distance relative to the longer text
+const MAX_EDIT_DISTANCE: f64 = 0.6;
+
+/// Setting `cleanup_guardrails`: "false" inserts whatever the LLM returns
+pub fn is_enabled(db: &Database) -> bool {
+    db.get_setting("cleanup_guardrails")
+        .ok()
+        .flatten()
+        .is_none_or(|v| v != "false")
+}
+
+/// Remove chatter around the text the model was asked for: a lead-in line, a closing
+/// remark, code fences and wrapping quotes. Anything the speaker said themselves stays.
+pub fn strip_preamble(raw: &str, reply: &str) -> String {
+    let raw_lower = raw.trim().to_lowercase();
+    let mut text = reply.trim();
+
+    if let Some(inner) = text
+        .strip_prefix("```")
+        .and_then(|rest| rest.strip_suffix("```"))
+    {
+        // Drop the language tag after the opening fence
+        text = inner
+            .split_once('\n')
+            .map_or(inner, |(_, body)| body)
+            .trim();
+    }
+
+    if let Some((lead, rest)) = text.split_once(':') {
+        let lead_lower = lead.trim().to_lowercase();
+        let is_preamble = !lead.contains('\n')
+            && lead.split_whitespace().count() <= MAX_PREAMBLE_WORDS
+            && PREAMBLES.iter().any(|p| lead_lower.starts_with(p))
+            && !raw_lower.starts_with(&lead_lower);
+        if is_preamble && !rest.trim().is_empty() {
+            text = rest.trim();
+        }
+    }
+
+    if let Some((body, last)) = text.rsplit_once('\n') {
+        let last_lower = last.trim().to_lowercase();
+        if POSTSCRIPTS
+            .iter()
+            .any(|p| last_lower.starts_with(p) && !raw_lower.contains(p))
+        {
+            text = body.trim();
+        }
+    }
+
+    for (open, close) in [('"', '"'), ('“', '”'), ('\'', '\'')] {
+        if text.len() > 1
+            && text.starts_with(open)
+            && text.ends_with(close)
+            && !raw_lower.starts_with(open)
+        {
+            text = text[open.len_utf8()..text.len() - close.len_utf8()].trim();
+        }
+    }
+    text.to_string()
+}
+
+/// Check that `cleaned` is still the dictation and not an answer to it, a summary,
+/// or a truncated reply. The error says which check failed.
+pub fn check(raw: &str, cleaned: &str, translating: bool, format: OutputFormat) -> Result<()> {
+    let raw_words = words(raw);
+    let cleaned_words = words(cleaned);
+    if cleaned_words.is_empty() {
+        anyhow::bail!("the LLM returned no text");
+    }
+
+    let ratio = cleaned_words.len() as f64 / raw_words.len().max(MIN_WORDS) as f64;
+    let max_ratio = if translating {
+        MAX_TRANSLATION_RATIO
+    } else {
+        MAX_LENGTH_RATIO
+    };
+    if ratio > max_ratio {
+        anyhow::bail!("the LLM reply is {:.1}× as long as the dictation", ratio);
+    }
+    // Code is much shorter than its description and shares few words with it
+    // ("define a function called get user" is "def get_user():"), so only an
+    // overlong reply counts against it
+    if format == OutputFormat::Code {
+        return Ok(());
+    }
+    if raw_words.len() >= MIN_WORDS && ratio < MIN_LENGTH_RATIO {
+        anyhow::bail!(
+            "the LLM reply kept only {:.0}% of the dictation's length",
+            ratio * 100.0
+        );
+    }
+    if translating {
+        return Ok(());
+    }
+
+    let raw_set: HashSet<&str> = raw_words.iter().map(String::as_str).collect();
+    let cleaned_set: HashSet<&str> = cleaned_words.iter().map(String::as_str).collect();
+    if cleaned_words.len() >= MIN_WORDS {
+        let precision = share_in(&cleaned_words, &raw_set);
+        if precision < MIN_PRECISION {
+            anyhow::bail!(
+                "only {:.0}% of the LLM reply's words were dictated",
+                precision * 100.0
+            );
+        }
+    }
+    if raw_words.len() >= MIN_WORDS {
+        let recall = share_in(&raw_words, &cleaned_set);
+        if recall < MIN_RECALL {
+            anyhow::bail!(
+                "the LLM reply dropped {:.0}% of the dictated words",
+                (1.0 - recall) * 100.0
+            );
+        }
+        let distance = edit_distance(&raw_words, &cleaned_words) as f64
+            / raw_words.len().max(cleaned_words.len()) as f64;
+        if distance > MAX_EDIT_DISTANCE {
+            anyhow::bail!(
+                "the LLM reply changed {:.0}% of the dictation",
+                distance * 100.0
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Lower-cased words without surrounding punctuation
+fn words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|w| {
+            w.trim_matches(|c: char| !c.is_alphanumeric())
+                .to_lowercase()
+        })
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+fn share_in(words: &[String], set: &HashSet<&str>) -> f64 {
+    let found = words.iter().filter(|w| set.contains(w.as_str())).count();
+    found as f64 / words.len() as f64
+}
+
+/// Levenshtein distance over words, or over characters
+pub fn edit_distance<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, word_a) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, word_b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(word_a != word_b);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_cleanups() {
+        let raw = "um so I think we should uh meet on Tuesday to go over the plan";
+        let cleaned = "I think we should meet on Tuesday to go over the plan.";
+        assert!(check(raw, cleaned, false, OutputFormat::Plain).is_ok());
+        assert!(check(
+            raw,
+            "- Meet on Tuesday to go over the plan",
+            false,
+            OutputFormat::Markdown
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn rejects_answers_and_summaries() {
+        let raw = "what is the capital of France and how many people live there";
+        let answer = "The capital of France is Paris, which has a population of roughly two \
+                      million people in the city proper and over twelve million in the wider \
+                      metropolitan area, making it one of the largest cities in Europe.";
+        assert!(check(raw, answer, false, OutputFormat::Plain).is_err());
+        assert!(check(raw, "Capital question.", false, OutputFormat::Plain).is_err());
+        assert!(check(raw, "", false, OutputFormat::Plain).is_err());
+    }
+
+    #[test]
+    fn lets_code_differ_from_its_description() {
+        let raw = "define a function called get user that takes an id and returns the user";
+        let code = "def get_user(id):\n    return user";
+        assert!(check(raw, code, false, OutputFormat::Plain).is_err());
+        assert!(check(raw, code, false, OutputFormat::Code).is_ok());
+        assert!(check(raw, "", false, OutputFormat::Code).is_err());
+        let essay = "Here is how you could write it. ".repeat(10);
+        assert!(check(raw, &essay, false, OutputFormat::Code).is_err());
+    }
+
+    #[test]
+    fn strips_preambles() {
+        let raw = "meet on Tuesday";
+        assert_eq!(
+            strip_preamble(raw, "Here is the cleaned text: Meet on Tuesday."),
+            "Meet on Tuesday."
+        );
+        assert_eq!(
+            strip_preamble(raw, "\"Meet on Tuesday.\""),
+            "Meet on Tuesday."
+        );
+        assert_eq!(
+            strip_preamble(
+                raw,
+                "Meet on Tuesday.\nLet me know if you need anything else."
+            ),
+            "Meet on Tuesday."
+        );
+        assert_eq!(
+            strip_preamble("sure thing: tuesday", "Sure thing: Tuesday."),
+            "Sure thing: Tuesday."
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use anyhow::Result;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use crate::db::Database;
+
+const USER_AGENT: &str = concat!("Parrot/", env!("CARGO_PKG_VERSION"));
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_READ_TIMEOUT_SECS: u64 = 120;
+
+/// Local services (whisper.cpp, Ollama) must never be routed through a proxy
+const NO_PROXY_HOSTS: &str = "localhost,127.0.0.1,::1";
+
+/// Shared HTTP client used by every network module.
+/// reqwest::Client is reference-counted, so handing out clones keeps a single connection pool.
+pub struct HttpClient {
+    client: RwLock<reqwest::Client>,
+}
+
+impl HttpClient {
+    /// Build the client from settings, falling back to defaults if the settings are invalid
+    /// so a bad proxy or certificate path never prevents the app from starting
+    pub fn new(db: &Database) -> Self {
+        let client = build_client(db).unwrap_or_else(|e| {
+            eprintln!("Invalid HTTP settings, using defaults: {}", e);
+            default_builder()
+                .build()
+                .expect("Failed to build default HTTP client")
+        });
+        Self {
+            client: RwLock::new(client),
+        }
+    }
+
+    pub fn get(&self) -> reqwest::Client {
+        self.client.read().unwrap().clone()
+    }
+
+    /// Rebuild the client after one of the `http_*` settings changed
+    pub fn reload(&self, db: &Database) -> Result<()> {
+        let client = build_client(db)?;
+        *self.client.write().unwrap() = client;
+        Ok(())
+    }
+}
+
+pub fn is_http_setting(key: &str) -> bool {
+    key.starts_with("http_")
+}
+
+fn default_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(Duration::from_secs(DEFAULT_CONNECT_TIMEOUT_SECS))
+        .read_timeout(Duration::from_secs(DEFAULT_READ_TIMEOUT_SECS))
+}
+
+/// Settings:
+/// - `http_connect_timeout_secs` / `http_read_timeout_secs`
+/// - `http_proxy`: explicit proxy URL, overrides HTTP_PROXY/HTTPS_PROXY from the environment.
+///   Either way local hosts are reached directly.
+/// - `http_ca_cert_path`: PEM bundle of extra root certificates (corporate networks)
+fn build_client(db: &Database) -> Result<reqwest::Client> {
+    let connect_timeout = timeout_setting(
+        db,
+        "http_connect_timeout_secs",
+        DEFAULT_CONNECT_TIMEOUT_SECS,
+    )?;
+    let read_timeout = timeout_setting(db, "http_read_timeout_secs", DEFAULT_READ_TIMEOUT_SECS)?;
+
+    let mut builder = default_builder()
+        .connect_timeout(connect_timeout)
+        .read_timeout(read_timeout);
+
+    if let Some(proxy_url) = non_empty_setting(db, "http_proxy")? {
+        let proxy = reqwest::Proxy::all(&proxy_url)
+            .map_err(|e| anyhow::anyhow!("Invalid proxy URL {}: {}", proxy_url, e))?
+            .no_proxy(reqwest::NoProxy::from_string(NO_PROXY_HOSTS));
+        builder = builder.proxy(proxy);
+    } else {
+        for proxy in env_proxies(|name| std::env::var(name).ok())? {
+            builder = builder.proxy(proxy);
+        }
+    }
+
+    if let Some(ca_path) = non_empty_setting(db, "http_ca_cert_path")? {
+        let pem = std::fs::read(&ca_path)
+            .map_err(|e| anyhow::anyhow!("Failed to read CA certificates {}: {}", ca_path, e))?;
+        for cert in reqwest::Certificate::from_pem_bundle(&pem)? {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    Ok(builder.build()?)
+}
+
+/// Proxies from HTTPS_PROXY, HTTP_PROXY and ALL_PROXY (or their lowercase forms), with the
+/// local hosts added to NO_PROXY. Left to reqwest, a proxy in the environment without a
+/// NO_PROXY would also carry requests to local services.
+fn env_proxies(var: impl Fn(&str) -> Option<String>) -> Result<Vec<reqwest::Proxy>> {
+    let env = |name: &str| {
+        var(name)
+            .or_else(|| var(&name.to_lowercase()))
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let no_proxy = match env("NO_PROXY") {
+        Some(hosts) => format!("{},{}", NO_PROXY_HOSTS, hosts),
+        None => NO_PROXY_HOSTS.to_string(),
+    };
+
+    let mut proxies = Vec::new();
+    for name in ["HTTPS_PROXY", "HTTP_PROXY", "ALL_PROXY"] {
+        let Some(url) = env(name) else {
+            continue;
+        };
+        let proxy = match name {
+            "HTTPS_PROXY" => reqwest::Proxy::https(&url),
+            "HTTP_PROXY" => reqwest::Proxy::http(&url),
+            _ => reqwest::Proxy::all(&url),
+        }
+        .map_err(|e| anyhow::anyhow!("Invalid proxy URL in {}: {}", name, e))?;
+        proxies.push(proxy.no_proxy(reqwest::NoProxy::from_string(&no_proxy)));
+    }
+    Ok(proxies)
+}
+
+fn non_empty_setting(db: &Database, key: &str) -> Result<Option<String>> {
+    Ok(db
+        .get_setting(key)?
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty()))
+}
+
+fn timeout_setting(db: &Database, key: &str, default_secs: u64) -> Result<Duration> {
+    let secs = match non_empty_setting(db, key)? {
+        Some(v) => v
+            .parse::<u64>()
+            .map_err(|_| anyhow::anyhow!("Invalid value for {}: {}", key, v))?,
+        None => default_secs,
+    };
+    Ok(Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    /// Answer one request with 200 and send back its request line
+    fn serve_once() -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok")
+                .unwrap();
+            tx.send(request_line.trim_end().to_string()).unwrap();
+        });
+        (address, rx)
+    }
+
+    fn client(env: &[(&str, &str)]) -> reqwest::Client {
+        let proxies = env_proxies(|name| {
+            env.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        })
+        .unwrap();
+        let mut builder = reqwest::Client::builder().no_proxy();
+        for proxy in proxies {
+            builder = builder.proxy(proxy);
+        }
+        builder.build().unwrap()
+    }
+
+    #[tokio::test]
+    async fn env_proxy_carries_remote_requests() {
+        let (proxy, rx) = serve_once();
+        let proxy_url = format!("http://{}", proxy);
+        let client = client(&[("http_proxy", proxy_url.as_str())]);
+        client
+            .get("http://parrot.invalid/health")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            rx.recv().unwrap(),
+            "GET http://parrot.invalid/health HTTP/1.1"
+        );
+    }
+
+    #[tokio::test]
+    async fn env_proxy_skips_local_services() {
+        // Nothing answers at the proxy, so a proxied request would fail
+        let proxy_url = "http://192.0.2.1:9";
+        for env in [
+            vec![("HTTP_PROXY", proxy_url)],
+            vec![("ALL_PROXY", proxy_url), ("NO_PROXY", "example.com")],
+        ] {
+            let (local, rx) = serve_once();
+            let response = client(&env)
+                .get(format!("http://{}/api/chat", local))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), 200);
+            assert_eq!(rx.recv().unwrap(), "GET /api/chat HTTP/1.1");
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_env_proxies() {
+        let e = env_proxies(|name| (name == "HTTPS_PROXY").then(|| "http://[::1".to_string()))
+            .unwrap_err();
+        assert!(e.to_string().contains("HTTPS_PROXY"), "{}", e);
+        assert!(env_proxies(|_| Some("  ".to_string())).unwrap().is_empty());
+    }
+
+    #[test]
+    fn recognizes_http_settings() {
+        assert!(is_http_setting("http_proxy"));
+        assert!(is_http_setting("http_read_timeout_secs"));
+        assert!(!is_http_setting("paste_method"));
+    }
+}
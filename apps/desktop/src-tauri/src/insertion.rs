@@ -0,0 +1,756 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::sync::{mpsc, Arc, Mutex, RwLock};
+use std::time::Duration;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::active_window::ActiveWindow;
+use crate::db::Database;
+
+const DEFAULT_RESTORE_DELAY_MS: u64 = 500;
+/// How long to wait for the focused app to answer a simulated copy
+const COPY_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Window classes of terminals, which paste with Ctrl+Shift+V instead of Ctrl+V
+const TERMINAL_CLASSES: &[&str] = &[
+    "gnome-terminal",
+    "gnome-terminal-server",
+    "konsole",
+    "xterm",
+    "uxterm",
+    "alacritty",
+    "kitty",
+    "wezterm",
+    "wezterm-gui",
+    "terminator",
+    "tilix",
+    "xfce4-terminal",
+    "mate-terminal",
+    "lxterminal",
+    "urxvt",
+    "st-256color",
+    "foot",
+    "ghostty",
+    "org.wezfurlong.wezterm",
+];
+
+/// How the paste keystroke reaches the focused application
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PasteBackend {
+    /// Simulated key events via enigo (macOS, Windows, X11)
+    Enigo,
+    /// `wtype`, which uses the Wayland virtual keyboard protocol (wlroots compositors, KDE)
+    Wtype,
+    /// `ydotool`, which goes through uinput and works on any compositor (needs ydotoold running)
+    Ydotool,
+}
+
+/// Which chord pastes. `Auto` picks Cmd+V on macOS, and elsewhere Ctrl+Shift+V in terminals
+/// (when the focused app can be detected) and Ctrl+V everywhere else.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PasteShortcut {
+    Auto,
+    CtrlV,
+    CtrlShiftV,
+    CmdV,
+}
+
+/// How dictated text gets into the focused field
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum InsertionMethod {
+    /// Put the text on the clipboard and press the paste chord
+    Clipboard,
+    /// Type the text as key events, leaving the clipboard untouched
+    Type,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct InsertionSettings {
+    backend: PasteBackend,
+    shortcut: PasteShortcut,
+    method: InsertionMethod,
+    /// None leaves the dictated text on the clipboard
+    restore_delay: Option<Duration>,
+    /// Pause between typed characters, for targets that drop fast input
+    char_delay: Duration,
+}
+
+/// Paste settings, resolved once at startup (and when a `paste_*` setting changes)
+/// so auto-detection doesn't run on every dictation
+pub struct Inserter {
+    settings: RwLock<InsertionSettings>,
+    /// Shared with streamed typing, which may finish after its dictation was cancelled
+    last: Arc<Mutex<Option<LastInsertion>>>,
+}
+
+/// Text Parrot most recently inserted, kept so it can be undone
+#[derive(Clone, Debug)]
+pub struct LastInsertion {
+    pub text: String,
+    /// Window that received the text, if it could be detected
+    pub target: Option<ActiveWindow>,
+}
+
+impl LastInsertion {
+    /// Whether `current` is known to still be the window that received the text. Where
+    /// the focused window can't be detected (macOS, Windows, Wayland) it never is.
+    pub fn is_focused(&self, current: Option<&ActiveWindow>) -> bool {
+        match (&self.target, current) {
+            (Some(target), Some(current)) => target.is_same_window(current),
+            _ => false,
+        }
+    }
+}
+
+impl Inserter {
+    /// Falls back to auto-detection if the settings are invalid
+    pub fn new(db: &Database) -> Self {
+        let settings = load_settings(db).unwrap_or_else(|e| {
+            eprintln!("Invalid paste settings, using defaults: {}", e);
+            InsertionSettings {
+                backend: detect_backend(),
+                shortcut: PasteShortcut::Auto,
+                method: InsertionMethod::Clipboard,
+                restore_delay: Some(Duration::from_millis(DEFAULT_RESTORE_DELAY_MS)),
+                char_delay: Duration::ZERO,
+            }
+        });
+        Self {
+            settings: RwLock::new(settings),
+            last: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn backend(&self) -> PasteBackend {
+        self.settings.read().unwrap().backend
+    }
+
+    pub fn reload(&self, db: &Database) -> Result<()> {
+        let settings = load_settings(db)?;
+        *self.settings.write().unwrap() = settings;
+        Ok(())
+    }
+
+    /// Insert `text` into the focused field. `method` overrides the `paste_method` setting,
+    /// e.g. for a profile used with an app that doesn't accept paste. `target` is the
+    /// focused window, if known, and decides the paste chord.
+    pub fn insert_text(
+        &self,
+        text: &str,
+        method: Option<InsertionMethod>,
+        target: Option<&ActiveWindow>,
+    ) -> Result<()> {
+        let settings = *self.settings.read().unwrap();
+        match method.unwrap_or(settings.method) {
+            InsertionMethod::Clipboard => paste_via_clipboard(text, &settings, target)?,
+            InsertionMethod::Type => type_text(settings.backend, text, settings.char_delay)?,
+        }
+        *self.last.lock().unwrap() = Some(LastInsertion {
+            text: text.to_string(),
+            target: target.cloned(),
+        });
+        Ok(())
+    }
+
+    /// Start typing text that arrives in pieces (streamed cleanup) into `target`. The
+    /// pieces are typed on a blocking thread so slow key events don't hold up the stream.
+    pub fn begin_incremental(&self, target: Option<ActiveWindow>) -> IncrementalInsertion {
+        let settings = *self.settings.read().unwrap();
+        let (pieces, received) = mpsc::channel::<String>();
+        let typer = tauri::async_runtime::spawn_blocking(move || {
+            let mut typed = String::new();
+            for piece in received {
+                if let Err(e) = type_text(settings.backend, &piece, settings.char_delay) {
+                    eprintln!("Typing streamed text failed: {}", e);
+                    return (typed, false);
+                }
+                typed.push_str(&piece);
+            }
+            (typed, true)
+        });
+        IncrementalInsertion {
+            pieces: Some(pieces),
+            typer: Some(typer),
+            last: Arc::clone(&self.last),
+            backend: settings.backend,
+            target,
+            typed: String::new(),
+            failed: false,
+        }
+    }
+
+    pub fn last_insertion(&self) -> Option<LastInsertion> {
+        self.last.lock().unwrap().clone()
+    }
+
+    /// Delete the last inserted text with backspaces, one per grapheme (what a Backspace
+    /// removes in practically every editor). Refuses if focus moved to another window,
+    /// since the backspaces would then delete something else. When the focused window
+    /// can't be detected at all, the user's explicit request is trusted.
+    pub fn undo_last(&self, current: Option<&ActiveWindow>) -> Result<LastInsertion> {
+        let mut last = self.last.lock().unwrap();
+        let Some(insertion) = last.as_ref() else {
+            anyhow::bail!("Nothing to undo");
+        };
+        let undetectable = insertion.target.is_none() && current.is_none();
+        if !undetectable && !insertion.is_focused(current) {
+            anyhow::bail!("Focus changed since the last dictation was inserted");
+        }
+
+        let count = insertion.text.replace("\r\n", "\n").graphemes(true).count();
+        send_backspaces(self.backend(), count)?;
+        Ok(last.take().expect("checked above"))
+    }
+
+    /// The focused app's selected text, read by pressing the platform copy chord (Ctrl+Shift+C
+    /// in terminals) whatever paste shortcut is configured. The clipboard is cleared first
+    /// so an empty selection can't be mistaken for older contents, and put back afterwards.
+    /// None if nothing was selected.
+    pub fn copy_selection(&self, target: Option<&ActiveWindow>) -> Result<Option<String>> {
+        let settings = *self.settings.read().unwrap();
+        let mut clipboard = arboard::Clipboard::new()
+            .map_err(|e| anyhow::anyhow!("Failed to access clipboard: {}", e))?;
+        let snapshot = ClipboardSnapshot::capture(&mut clipboard);
+        let _ = clipboard.clear();
+
+        let copied = send_chord(
+            settings.backend,
+            resolve_shortcut(PasteShortcut::Auto, target),
+            ChordKey::C,
+        );
+        let mut selection = None;
+        if copied.is_ok() {
+            let started = std::time::Instant::now();
+            while started.elapsed() < COPY_TIMEOUT {
+                std::thread::sleep(Duration::from_millis(25));
+                if let Ok(text) = clipboard.get_text() {
+                    if !text.is_empty() {
+                        selection = Some(text);
+                        break;
+                    }
+                }
+            }
+        }
+
+        if let Err(e) = snapshot.restore(&mut clipboard) {
+            eprintln!("Failed to restore clipboard: {}", e);
+        }
+        copied?;
+        Ok(selection)
+    }
+}
+
+/// Text typed piece by piece as it arrives. Always typed rather than pasted, since
+/// pasting every few tokens would churn the clipboard. Call `settle` before
+/// `is_intact`, `finish` or `abort`.
+pub struct IncrementalInsertion {
+    /// Pieces waiting for the typer; None once the stream is over
+    pieces: Option<mpsc::Sender<String>>,
+    /// Returns what it typed, and whether it typed every piece
+    typer: Option<tauri::async_runtime::JoinHandle<(String, bool)>>,
+    last: Arc<Mutex<Option<LastInsertion>>>,
+    backend: PasteBackend,
+    target: Option<ActiveWindow>,
+    typed: String,
+    failed: bool,
+}
+
+impl IncrementalInsertion {
+    /// Queue the next piece for typing. After a failure the rest is skipped.
+    pub fn push(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if let Some(pieces) = &self.pieces {
+            // The typer only hangs up after a failure, which `is_intact` reports
+            let _ = pieces.send(text.to_string());
+        }
+    }
+
+    /// End the stream and wait until every queued piece has been typed
+    pub async fn settle(&mut self) {
+        self.pieces = None;
+        let Some(typer) = self.typer.as_mut() else {
+            return;
+        };
+        let (typed, intact) = typer.await.unwrap_or_else(|e| {
+            eprintln!("Typing streamed text failed: {}", e);
+            (String::new(), false)
+        });
+        self.typer = None;
+        self.typed = typed;
+        self.failed = !intact;
+    }
+
+    /// Whether every piece was typed
+    pub fn is_intact(&self) -> bool {
+        !self.failed
+    }
+
+    /// Record everything typed as one insertion, so undo removes all of it
+    pub fn finish(mut self) {
+        *self.last.lock().unwrap() = Some(LastInsertion {
+            text: std::mem::take(&mut self.typed),
+            target: self.target.take(),
+        });
+    }
+
+    /// Remove what was typed, e.g. when the stream failed halfway
+    pub async fn abort(mut self) -> Result<()> {
+        let count = self.typed.replace("\r\n", "\n").graphemes(true).count();
+        let backend = self.backend;
+        self.typed.clear();
+        tauri::async_runtime::spawn_blocking(move || send_backspaces(backend, count)).await?
+    }
+}
+
+/// A dictation cancelled mid-stream drops its insertion unsettled. The text typed by
+/// then stays in the target app, so it is recorded for undo once the typer stops.
+impl Drop for IncrementalInsertion {
+    fn drop(&mut self) {
+        self.pieces = None;
+        let Some(typer) = self.typer.take() else {
+            return;
+        };
+        let last = Arc::clone(&self.last);
+        let target = self.target.take();
+        tauri::async_runtime::spawn(async move {
+            if let Ok((text, _)) = typer.await {
+                if !text.is_empty() {
+                    *last.lock().unwrap() = Some(LastInsertion { text, target });
+                }
+            }
+        });
+    }
+}
+
+fn send_backspaces(backend: PasteBackend, count: usize) -> Result<()> {
+    if count == 0 {
+        return Ok(());
+    }
+    match backend {
+        PasteBackend::Enigo => {
+            use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+
+            let mut enigo = Enigo::new(&Settings::default())
+                .map_err(|e| anyhow::anyhow!("Failed to simulate keyboard: {}", e))?;
+            for _ in 0..count {
+                enigo.key(Key::Backspace, Direction::Click)?;
+            }
+            Ok(())
+        }
+        PasteBackend::Wtype => {
+            let mut args = Vec::with_capacity(count * 2);
+            for _ in 0..count {
+                args.extend(["-k", "BackSpace"]);
+            }
+            run_tool("wtype", &args)
+        }
+        PasteBackend::Ydotool => {
+            // Linux input event code 14 = Backspace
+            let mut args = vec!["key"];
+            for _ in 0..count {
+                args.extend(["14:1", "14:0"]);
+            }
+            run_tool("ydotool", &args)
+        }
+    }
+}
+
+pub fn is_insertion_setting(key: &str) -> bool {
+    key.starts_with("paste_")
+}
+
+/// Whatever was on the clipboard before dictation replaced it
+enum ClipboardSnapshot {
+    Empty,
+    Text(String),
+    Html {
+        html: String,
+        alt_text: Option<String>,
+    },
+    Image(arboard::ImageData<'static>),
+}
+
+impl ClipboardSnapshot {
+    fn capture(clipboard: &mut arboard::Clipboard) -> Self {
+        if let Ok(html) = clipboard.get().html() {
+            let alt_text = clipboard.get_text().ok();
+            return Self::Html { html, alt_text };
+        }
+        if let Ok(text) = clipboard.get_text() {
+            return Self::Text(text);
+        }
+        if let Ok(image) = clipboard.get_image() {
+            return Self::Image(image);
+        }
+        Self::Empty
+    }
+
+    fn restore(self, clipboard: &mut arboard::Clipboard) -> Result<(), arboard::Error> {
+        match self {
+            Self::Empty => clipboard.clear(),
+            Self::Text(text) => clipboard.set_text(text),
+            Self::Html { html, alt_text } => clipboard.set_html(html, alt_text),
+            Self::Image(image) => clipboard.set_image(image),
+        }
+    }
+}
+
+fn paste_via_clipboard(
+    text: &str,
+    settings: &InsertionSettings,
+    target: Option<&ActiveWindow>,
+) -> Result<()> {
+    let mut clipboard = arboard::Clipboard::new()
+        .map_err(|e| anyhow::anyhow!("Failed to access clipboard: {}", e))?;
+    let snapshot = settings
+        .restore_delay
+        .map(|_| ClipboardSnapshot::capture(&mut clipboard));
+    clipboard
+        .set_text(text)
+        .map_err(|e| anyhow::anyhow!("Failed to set clipboard text: {}", e))?;
+
+    // Small delay so the clipboard owner is ready before the target asks for the contents
+    std::thread::sleep(Duration::from_millis(50));
+    send_paste(
+        settings.backend,
+        resolve_shortcut(settings.shortcut, target),
+    )?;
+
+    match (snapshot, settings.restore_delay) {
+        (Some(snapshot), Some(delay)) => {
+            // The target reads the clipboard asynchronously after the chord, so restoring
+            // happens later on a separate thread instead of holding up the pipeline
+            let text = text.to_string();
+            std::thread::spawn(move || {
+                std::thread::sleep(delay);
+                // Don't overwrite something the user copied in the meantime
+                if clipboard.get_text().ok().as_deref() != Some(text.as_str()) {
+                    return;
+                }
+                if let Err(e) = snapshot.restore(&mut clipboard) {
+                    eprintln!("Failed to restore clipboard: {}", e);
+                }
+            });
+        }
+        _ => {
+            // On Linux the clipboard is served by this process, so keep it alive until the
+            // target application has had a chance to read it
+            #[cfg(target_os = "linux")]
+            std::thread::sleep(Duration::from_millis(150));
+        }
+    }
+    Ok(())
+}
+
+/// Type `text` as key events. Some targets (remote desktops, VMs, terminals with
+/// bracketed paste) ignore the clipboard but accept keystrokes.
+fn type_text(backend: PasteBackend, text: &str, char_delay: Duration) -> Result<()> {
+    let text = text.replace("\r\n", "\n");
+    let delay_ms = char_delay.as_millis().to_string();
+    match backend {
+        PasteBackend::Enigo => type_text_enigo(&text, char_delay),
+        PasteBackend::Wtype => run_tool("wtype", &["-d", &delay_ms, "--", &text]),
+        PasteBackend::Ydotool => {
+            run_tool("ydotool", &["type", "--key-delay", &delay_ms, "--", &text])
+        }
+    }
+}
+
+/// enigo's text input handles any Unicode character (via the layout where possible and
+/// a temporary key mapping otherwise), but line breaks and tabs are sent as real key
+/// presses so editors treat them as Enter/Tab rather than inserting control characters
+fn type_text_enigo(text: &str, char_delay: Duration) -> Result<()> {
+    use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+
+    let mut enigo = Enigo::new(&Settings::default())
+        .map_err(|e| anyhow::anyhow!("Failed to simulate keyboard: {}", e))?;
+    let mut pending = String::new();
+    for c in text.chars() {
+        let key = match c {
+            '\n' => Some(Key::Return),
+            '\t' => Some(Key::Tab),
+            _ => None,
+        };
+        match key {
+            Some(key) => {
+                if !pending.is_empty() {
+                    enigo.text(&pending)?;
+                    pending.clear();
+                }
+                enigo.key(key, Direction::Click)?;
+            }
+            None => pending.push(c),
+        }
+        // With a delay every character is its own event; without one, runs of text go in one call
+        if !char_delay.is_zero() {
+            if !pending.is_empty() {
+                enigo.text(&pending)?;
+                pending.clear();
+            }
+            std::thread::sleep(char_delay);
+        }
+    }
+    if !pending.is_empty() {
+        enigo.text(&pending)?;
+    }
+    Ok(())
+}
+
+/// Settings:
+/// - `paste_backend`: "auto" (default), "enigo", "wtype" or "ydotool"
+/// - `paste_shortcut`: "auto" (default), "ctrl_v", "ctrl_shift_v" or "cmd_v"
+/// - `paste_method`: "clipboard" (default) or "type"
+/// - `paste_restore_clipboard`: "false" leaves the dictated text on the clipboard
+/// - `paste_restore_delay_ms`: how long the target app gets to read the clipboard before
+///   the previous contents come back
+/// - `paste_type_delay_ms`: pause between characters when typing (default 0)
+fn load_settings(db: &Database) -> Result<InsertionSettings> {
+    let backend = match setting(db, "paste_backend")?.as_deref() {
+        None | Some("auto") => detect_backend(),
+        Some(value) => parse_setting(value)?,
+    };
+    let shortcut = match setting(db, "paste_shortcut")?.as_deref() {
+        None => PasteShortcut::Auto,
+        Some(value) => parse_setting(value)?,
+    };
+    let method = match setting(db, "paste_method")?.as_deref() {
+        None => InsertionMethod::Clipboard,
+        Some(value) => parse_setting(value)?,
+    };
+    let restore_delay = match setting(db, "paste_restore_clipboard")?.as_deref() {
+        Some("false") => None,
+        _ => {
+            let ms = match setting(db, "paste_restore_delay_ms")? {
+                Some(v) => v.parse::<u64>().map_err(|_| {
+                    anyhow::anyhow!("Invalid value for paste_restore_delay_ms: {}", v)
+                })?,
+                None => DEFAULT_RESTORE_DELAY_MS,
+            };
+            Some(Duration::from_millis(ms))
+        }
+    };
+    let char_delay = match setting(db, "paste_type_delay_ms")? {
+        Some(v) => Duration::from_millis(
+            v.parse::<u64>()
+                .map_err(|_| anyhow::anyhow!("Invalid value for paste_type_delay_ms: {}", v))?,
+        ),
+        None => Duration::ZERO,
+    };
+    Ok(InsertionSettings {
+        backend,
+        shortcut,
+        method,
+        restore_delay,
+        char_delay,
+    })
+}
+
+fn setting(db: &Database, key: &str) -> Result<Option<String>> {
+    Ok(db
+        .get_setting(key)?
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty()))
+}
+
+pub fn parse_setting<T: serde::de::DeserializeOwned>(value: &str) -> Result<T> {
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|_| anyhow::anyhow!("Unknown paste setting: {}", value))
+}
+
+/// Under Wayland, enigo's X11 events only reach XWayland windows, so prefer a
+/// Wayland-native tool when one is installed
+pub fn detect_backend() -> PasteBackend {
+    if !is_wayland() {
+        return PasteBackend::Enigo;
+    }
+    if command_exists("wtype") {
+        PasteBackend::Wtype
+    } else if command_exists("ydotool") {
+        PasteBackend::Ydotool
+    } else {
+        eprintln!("Wayland session without wtype or ydotool; paste may only reach XWayland apps");
+        PasteBackend::Enigo
+    }
+}
+
+pub fn is_wayland() -> bool {
+    cfg!(target_os = "linux")
+        && (std::env::var_os("WAYLAND_DISPLAY").is_some()
+            || std::env::var("XDG_SESSION_TYPE").is_ok_and(|v| v == "wayland"))
+}
+
+fn command_exists(name: &str) -> bool {
+    Command::new("which")
+        .arg(name)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+fn resolve_shortcut(shortcut: PasteShortcut, target: Option<&ActiveWindow>) -> PasteShortcut {
+    if shortcut != PasteShortcut::Auto {
+        return shortcut;
+    }
+    if cfg!(target_os = "macos") {
+        PasteShortcut::CmdV
+    } else if target.is_some_and(is_terminal) {
+        PasteShortcut::CtrlShiftV
+    } else {
+        PasteShortcut::CtrlV
+    }
+}
+
+fn send_paste(backend: PasteBackend, shortcut: PasteShortcut) -> Result<()> {
+    send_chord(backend, shortcut, ChordKey::V)
+}
+
+/// The letter pressed with a paste chord's modifiers: V pastes, C copies
+#[derive(Clone, Copy)]
+enum ChordKey {
+    V,
+    C,
+}
+
+impl ChordKey {
+    fn letter(self) -> char {
+        match self {
+            Self::V => 'v',
+            Self::C => 'c',
+        }
+    }
+
+    /// Linux input event code
+    fn code(self) -> &'static str {
+        match self {
+            Self::V => "47",
+            Self::C => "46",
+        }
+    }
+}
+
+fn send_chord(backend: PasteBackend, shortcut: PasteShortcut, key: ChordKey) -> Result<()> {
+    match backend {
+        PasteBackend::Enigo => send_chord_enigo(shortcut, key),
+        PasteBackend::Wtype => {
+            let modifiers: &[&str] = match shortcut {
+                PasteShortcut::CtrlShiftV => &["ctrl", "shift"],
+                PasteShortcut::CmdV => &["logo"],
+                _ => &["ctrl"],
+            };
+            let letter = key.letter().to_string();
+            let mut args = Vec::new();
+            for modifier in modifiers {
+                args.extend(["-M", modifier]);
+            }
+            args.extend(["-k", letter.as_str()]);
+            for modifier in modifiers.iter().rev() {
+                args.extend(["-m", modifier]);
+            }
+            run_tool("wtype", &args)
+        }
+        PasteBackend::Ydotool => {
+            // Linux input event codes: 29 = left Ctrl, 42 = left Shift, 125 = left Meta
+            let modifiers: &[&str] = match shortcut {
+                PasteShortcut::CtrlShiftV => &["29", "42"],
+                PasteShortcut::CmdV => &["125"],
+                _ => &["29"],
+            };
+            let mut events = Vec::new();
+            for modifier in modifiers {
+                events.push(format!("{}:1", modifier));
+            }
+            events.push(format!("{}:1", key.code()));
+            events.push(format!("{}:0", key.code()));
+            for modifier in modifiers.iter().rev() {
+                events.push(format!("{}:0", modifier));
+            }
+            let mut args = vec!["key"];
+            args.extend(events.iter().map(String::as_str));
+            run_tool("ydotool", &args)
+        }
+    }
+}
+
+fn send_chord_enigo(shortcut: PasteShortcut, key: ChordKey) -> Result<()> {
+    use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+
+    let mut enigo = Enigo::new(&Settings::default())
+        .map_err(|e| anyhow::anyhow!("Failed to simulate keyboard: {}", e))?;
+    let modifiers: &[Key] = match shortcut {
+        PasteShortcut::CmdV => &[Key::Meta],
+        PasteShortcut::CtrlShiftV => &[Key::Control, Key::Shift],
+        _ => &[Key::Control],
+    };
+    for key in modifiers {
+        enigo.key(*key, Direction::Press)?;
+    }
+    let result = enigo.key(Key::Unicode(key.letter()), Direction::Click);
+    // Always release the modifiers, even if the letter press failed, so none stay stuck down
+    for key in modifiers.iter().rev() {
+        let _ = enigo.key(*key, Direction::Release);
+    }
+    Ok(result?)
+}
+
+fn run_tool(program: &str, args: &[&str]) -> Result<()> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| anyhow::anyhow!("Failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+fn is_terminal(window: &ActiveWindow) -> bool {
+    TERMINAL_CLASSES.contains(&window.app_name.to_lowercase().as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(id: u64, app_name: &str) -> ActiveWindow {
+        ActiveWindow {
+            id,
+            app_name: app_name.to_string(),
+            title: "Untitled".to_string(),
+        }
+    }
+
+    fn insertion(target: Option<ActiveWindow>) -> LastInsertion {
+        LastInsertion {
+            text: "Hello.".to_string(),
+            target,
+        }
+    }
+
+    #[test]
+    fn knows_the_focused_window() {
+        let editor = window(1, "gedit");
+        let renamed = ActiveWindow {
+            title: "*Untitled".to_string(),
+            ..editor.clone()
+        };
+        assert!(insertion(Some(editor.clone())).is_focused(Some(&renamed)));
+        assert!(!insertion(Some(editor.clone())).is_focused(Some(&window(2, "gedit"))));
+        assert!(!insertion(Some(editor.clone())).is_focused(None));
+        assert!(!insertion(None).is_focused(Some(&editor)));
+    }
+
+    #[test]
+    fn undetectable_focus_is_not_the_same_window() {
+        assert!(!insertion(None).is_focused(None));
+    }
+}
@@ -1,76 +1,343 @@
+mod active_window;
 mod audio;
 mod cleanup;
 mod cloud_api;
+mod corrections;
 mod db;
+mod guardrails;
+mod http;
+mod insertion;
+mod output_format;
+mod pipeline;
+mod prompt_templates;
+mod queue;
+mod review;
+mod rule_cleanup;
+mod shortcuts;
+mod snippets;
+mod spoken_commands;
+mod text_normalization;
 mod transcription;
+mod translation;
+mod voice_edit;
 
+use active_window::{ActiveWindow, WindowTracker};
 use audio::AudioRecorder;
 use db::Database;
+use http::HttpClient;
+use insertion::Inserter;
+use output_format::OutputFormat;
+use pipeline::{Pipeline, PipelineState};
+use queue::{DictationJob, DictationQueue};
+use rule_cleanup::{RuleCleaner, RuleMode};
+use shortcuts::{ShortcutBinding, ShortcutRegistry};
+use snippets::SnippetSet;
+use spoken_commands::CommandTable;
 use std::sync::Mutex;
 use std::time::Instant;
 use tauri::{Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+use text_normalization::{Normalizer, NumberStyle};
+use translation::TranslationSettings;
+use voice_edit::{EditIntent, EditState};
+
+/// What the current recording will be turned into, decided by the shortcut that started it
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DictationMode {
+    #[default]
+    Dictate,
+    Translate,
+    /// Apply a spoken instruction to the selected text and replace the selection
+    Command,
+}
+
+/// What was decided when a recording started, carried with it through the queue
+#[derive(Clone, Debug, Default)]
+pub struct DictationContext {
+    pub mode: DictationMode,
+    /// Profile of the shortcut that started the recording; None lets app rules pick one
+    pub profile_id: Option<i64>,
+    /// Application focused when recording started
+    pub window: Option<ActiveWindow>,
+}
 
 pub struct RecorderState {
     recorder: Mutex<AudioRecorder>,
     recording_start: Mutex<Option<Instant>>,
+    recording_context: Mutex<DictationContext>,
     last_wav: Mutex<Option<Vec<u8>>>,
     last_duration_ms: Mutex<u64>,
 }
 
-#[tauri::command]
-fn start_recording(
-    state: tauri::State<'_, RecorderState>,
-    app: tauri::AppHandle,
+fn emit_pipeline_state(app: &tauri::AppHandle) {
+    let _ = app.emit("pipeline-state", app.state::<Pipeline>().state());
+}
+
+/// Move the processing side of the pipeline to `next` and notify the frontend
+fn set_pipeline_state(app: &tauri::AppHandle, next: PipelineState) -> Result<(), String> {
+    app.state::<Pipeline>()
+        .transition(next)
+        .map_err(|e| e.to_string())?;
+    emit_pipeline_state(app);
+    Ok(())
+}
+
+fn begin_recording(
+    app: &tauri::AppHandle,
+    mode: DictationMode,
+    profile_id: Option<i64>,
 ) -> Result<(), String> {
+    let state = app.state::<RecorderState>();
+    let pipeline = app.state::<Pipeline>();
     let mut recorder = state.recorder.lock().unwrap();
-    if recorder.is_recording() {
-        return Ok(());
+    pipeline.start_recording().map_err(|e| e.to_string())?;
+    if let Err(e) = recorder.start() {
+        let _ = pipeline.stop_recording();
+        let _ = set_pipeline_state(app, PipelineState::Error(e.to_string()));
+        return Err(format!("Failed to start recording: {}", e));
     }
-    recorder.start().map_err(|e| e.to_string())?;
     *state.recording_start.lock().unwrap() = Some(Instant::now());
-    app.emit("recording-started", ())
-        .map_err(|e| e.to_string())?;
+    *state.recording_context.lock().unwrap() = DictationContext {
+        mode,
+        profile_id,
+        window: app.state::<WindowTracker>().current(),
+    };
+    emit_pipeline_state(app);
     Ok(())
 }
 
-#[tauri::command]
-fn stop_recording(
-    state: tauri::State<'_, RecorderState>,
-    app: tauri::AppHandle,
-) -> Result<Vec<u8>, String> {
+/// Stop recording and keep the audio in `last_wav` so it can be re-transcribed later
+fn finish_recording(app: &tauri::AppHandle) -> Result<Vec<u8>, String> {
+    let state = app.state::<RecorderState>();
+    let pipeline = app.state::<Pipeline>();
     let mut recorder = state.recorder.lock().unwrap();
-    if !recorder.is_recording() {
-        return Err("Not recording".into());
-    }
+    pipeline.stop_recording().map_err(|e| e.to_string())?;
     let duration_ms = state
         .recording_start
         .lock()
         .unwrap()
         .map(|s| s.elapsed().as_millis() as u64)
         .unwrap_or(0);
-    let wav_data = recorder.stop().map_err(|e| e.to_string())?;
-    app.emit("recording-stopped", duration_ms)
-        .map_err(|e| e.to_string())?;
+    let wav_data = match recorder.stop() {
+        Ok(wav_data) => wav_data,
+        Err(e) => {
+            let _ = set_pipeline_state(app, PipelineState::Error(e.to_string()));
+            return Err(format!("Failed to stop recording: {}", e));
+        }
+    };
+    *state.last_duration_ms.lock().unwrap() = duration_ms;
+    *state.last_wav.lock().unwrap() = Some(wav_data.clone());
+    emit_pipeline_state(app);
+    let _ = app.emit("recording-stopped", duration_ms);
     Ok(wav_data)
 }
 
+/// Stop recording and hand the audio to the dictation queue
+fn finish_and_queue_recording(app: &tauri::AppHandle) -> Result<(), String> {
+    let wav_data = finish_recording(app)?;
+    let state = app.state::<RecorderState>();
+    let duration_ms = *state.last_duration_ms.lock().unwrap();
+    let context = state.recording_context.lock().unwrap().clone();
+    app.state::<DictationQueue>().push(DictationJob {
+        wav_data,
+        duration_ms,
+        context,
+        reply: None,
+    })
+}
+
+#[tauri::command]
+fn start_recording(
+    pipeline: tauri::State<'_, Pipeline>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    if pipeline.is_recording() {
+        return Ok(());
+    }
+    begin_recording(&app, DictationMode::Dictate, None)
+}
+
+#[tauri::command]
+fn stop_recording(app: tauri::AppHandle) -> Result<Vec<u8>, String> {
+    finish_recording(&app)
+}
+
+#[tauri::command]
+fn get_shortcuts(registry: tauri::State<'_, ShortcutRegistry>) -> Vec<ShortcutBinding> {
+    registry.bindings()
+}
+
+/// Saved shortcuts that couldn't be registered at startup, and why
+#[tauri::command]
+fn get_shortcut_problems(registry: tauri::State<'_, ShortcutRegistry>) -> Vec<String> {
+    registry.problems()
+}
+
+/// Re-register global shortcuts at runtime; rejects duplicates and shortcuts owned by other apps
+#[tauri::command]
+fn set_shortcuts(
+    bindings: Vec<ShortcutBinding>,
+    registry: tauri::State<'_, ShortcutRegistry>,
+    processing: tauri::State<'_, ProcessingState>,
+    undo: tauri::State<'_, UndoShortcut>,
+    db: tauri::State<'_, Database>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    let json = serde_json::to_string(&bindings).map_err(|e| e.to_string())?;
+    registry
+        .apply(&app, bindings, &[processing.cancel_shortcut(), undo.get()])
+        .map_err(|e| e.to_string())?;
+    db.set_setting("shortcuts", &json)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_pipeline_state(pipeline: tauri::State<'_, Pipeline>) -> PipelineState {
+    pipeline.state()
+}
+
 #[tauri::command]
 fn is_recording(state: tauri::State<'_, RecorderState>) -> bool {
     state.recorder.lock().unwrap().is_recording()
 }
 
 #[derive(serde::Serialize, Clone)]
-struct DictationResult {
+pub struct DictationResult {
     raw_text: String,
     cleaned_text: String,
+    translated_text: String,
     pasted: bool,
 }
 
+/// Transcription/cleanup running in the background, so it can be aborted by `cancel_dictation`
+pub struct ProcessingState {
+    task: Mutex<Option<tokio::task::AbortHandle>>,
+    /// Setting `cancel_hotkey`; only registered while a dictation is processing
+    cancel_shortcut: Mutex<Shortcut>,
+}
+
+impl ProcessingState {
+    fn cancel_shortcut(&self) -> Shortcut {
+        *self.cancel_shortcut.lock().unwrap()
+    }
+}
+
+/// Global shortcut that removes the last inserted dictation (setting `undo_hotkey`)
+pub struct UndoShortcut(Mutex<Shortcut>);
+
+impl UndoShortcut {
+    fn get(&self) -> Shortcut {
+        *self.0.lock().unwrap()
+    }
+}
+
+/// Tray menu item showing the last inserted text
+pub struct TrayMenu {
+    last_insertion: tauri::menu::MenuItem<tauri::Wry>,
+}
+
+fn show_last_insertion(app: &tauri::AppHandle, text: Option<&str>) {
+    const MAX_CHARS: usize = 40;
+    let label = match text {
+        Some(text) if text.chars().count() > MAX_CHARS => {
+            format!(
+                "Last: {}…",
+                text.chars().take(MAX_CHARS).collect::<String>()
+            )
+        }
+        Some(text) => format!("Last: {}", text),
+        None => "No dictation to undo".to_string(),
+    };
+    if let Some(tray) = app.try_state::<TrayMenu>() {
+        let _ = tray.last_insertion.set_text(label.replace('\n', " "));
+    }
+}
+
+/// Run `call` on a blocking thread: pasting, typing and backspacing sleep between key
+/// events and wait on external tools, which would stall the async runtime
+async fn with_inserter<T: Send + 'static>(
+    app: &tauri::AppHandle,
+    call: impl FnOnce(&Inserter) -> anyhow::Result<T> + Send + 'static,
+) -> Result<T, String> {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || call(&app.state::<Inserter>()))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Remove the last inserted dictation from the focused field
+async fn undo_last_dictation(app: &tauri::AppHandle) -> Result<String, String> {
+    let current = app.state::<WindowTracker>().current();
+    let undone = with_inserter(app, move |inserter| inserter.undo_last(current.as_ref())).await?;
+    show_last_insertion(app, None);
+    let _ = app.emit("insertion-undone", undone.text.clone());
+    Ok(undone.text)
+}
+
+/// `undo_last_dictation` for the shortcut and tray handlers, which can't wait for it
+fn spawn_undo(app: &tauri::AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = undo_last_dictation(&app).await {
+            eprintln!("Undo failed: {}", e);
+        }
+    });
+}
+
+#[tauri::command]
+async fn undo_last_insertion(app: tauri::AppHandle) -> Result<String, String> {
+    undo_last_dictation(&app).await
+}
+
+/// Called by the review overlay when it opens, in case it missed `review-requested`
+#[tauri::command]
+fn get_pending_review(
+    review: tauri::State<'_, review::ReviewState>,
+) -> Option<review::ReviewRequest> {
+    review.pending()
+}
+
+#[tauri::command]
+fn accept_review(
+    text: String,
+    review: tauri::State<'_, review::ReviewState>,
+) -> Result<(), String> {
+    review
+        .decide(review::ReviewDecision::Accept(text))
+        .map_err(|e| e.to_string())
+}
+
+/// Clean the raw transcript again with another writing style; the overlay gets the
+/// result through a new `review-requested` event
+#[tauri::command]
+fn rerun_review_cleanup(
+    writing_style: String,
+    review: tauri::State<'_, review::ReviewState>,
+) -> Result<(), String> {
+    review
+        .decide(review::ReviewDecision::Rerun { writing_style })
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn discard_review(review: tauri::State<'_, review::ReviewState>) -> Result<(), String> {
+    review
+        .decide(review::ReviewDecision::Discard)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_last_insertion(inserter: tauri::State<'_, Inserter>) -> Option<String> {
+    inserter.last_insertion().map(|last| last.text)
+}
+
+/// Re-run the pipeline on the last recording, queued behind any dictation still processing
 #[tauri::command]
 async fn transcribe_last(
     recorder_state: tauri::State<'_, RecorderState>,
-    db: tauri::State<'_, Database>,
-    app: tauri::AppHandle,
+    queue: tauri::State<'_, DictationQueue>,
 ) -> Result<DictationResult, String> {
     let wav_data = recorder_state
         .last_wav
@@ -79,6 +346,386 @@ async fn transcribe_last(
         .clone()
         .ok_or_else(|| "No audio data available".to_string())?;
     let duration_ms = *recorder_state.last_duration_ms.lock().unwrap();
+    let context = recorder_state.recording_context.lock().unwrap().clone();
+
+    let (reply, outcome) = tokio::sync::oneshot::channel();
+    queue.push(DictationJob {
+        wav_data,
+        duration_ms,
+        context,
+        reply: Some(reply),
+    })?;
+    outcome
+        .await
+        .map_err(|_| "Dictation queue stopped".to_string())?
+}
+
+/// Run one dictation through the pipeline. The work happens in its own task
+/// so `cancel_dictation` can abort it; failures leave the pipeline in Error.
+async fn process_dictation(
+    app: &tauri::AppHandle,
+    wav_data: Vec<u8>,
+    duration_ms: u64,
+    context: DictationContext,
+) -> Result<DictationResult, String> {
+    let processing = app.state::<ProcessingState>();
+
+    set_pipeline_state(app, PipelineState::Transcribing)?;
+    let task = match context.mode {
+        DictationMode::Command => {
+            tauri::async_runtime::spawn(run_command(app.clone(), wav_data, duration_ms, context))
+        }
+        _ => {
+            tauri::async_runtime::spawn(run_dictation(app.clone(), wav_data, duration_ms, context))
+        }
+    };
+    // Only grab the cancel key while there is something to cancel
+    {
+        let cancel_shortcut = processing.cancel_shortcut.lock().unwrap();
+        *processing.task.lock().unwrap() = Some(task.inner().abort_handle());
+        if let Err(e) = app.global_shortcut().register(*cancel_shortcut) {
+            eprintln!("Failed to register cancel shortcut: {}", e);
+        }
+    }
+
+    let outcome = task.await;
+
+    {
+        let cancel_shortcut = processing.cancel_shortcut.lock().unwrap();
+        processing.task.lock().unwrap().take();
+        let _ = app.global_shortcut().unregister(*cancel_shortcut);
+    }
+
+    match outcome {
+        Ok(Ok(result)) => Ok(result),
+        Ok(Err(e)) => {
+            let _ = set_pipeline_state(app, PipelineState::Error(e.clone()));
+            Err(e)
+        }
+        Err(tauri::Error::JoinError(e)) if e.is_cancelled() => {
+            Err("Dictation cancelled".to_string())
+        }
+        Err(e) => {
+            let _ = set_pipeline_state(app, PipelineState::Error(e.to_string()));
+            Err(e.to_string())
+        }
+    }
+}
+
+/// Abort the in-flight dictation, if any. Returns whether something was cancelled.
+fn cancel_processing(app: &tauri::AppHandle) -> bool {
+    let processing = app.state::<ProcessingState>();
+    let Some(task) = processing.task.lock().unwrap().take() else {
+        return false;
+    };
+    task.abort();
+
+    let db = app.state::<Database>();
+    let keep_audio = db
+        .get_setting("keep_cancelled_audio")
+        .ok()
+        .flatten()
+        .is_some_and(|v| v == "true");
+    if !keep_audio {
+        app.state::<RecorderState>().last_wav.lock().unwrap().take();
+    }
+
+    app.state::<review::ReviewState>().clear();
+    review::hide_window(app);
+    let _ = set_pipeline_state(app, PipelineState::Idle);
+    let _ = app.emit("dictation-cancelled", ());
+    true
+}
+
+#[tauri::command]
+fn cancel_dictation(app: tauri::AppHandle) -> bool {
+    cancel_processing(&app)
+}
+
+/// LLM cleanup with the configured provider. Failures are logged and return None so
+/// the caller can fall back to the raw transcript.
+async fn llm_cleanup(
+    provider: &dyn cleanup::CleanupProvider,
+    raw_text: &str,
+    prompt: &cleanup::PromptContext<'_>,
+) -> Option<String> {
+    match cleanup::cleanup_text(provider, raw_text, prompt).await {
+        Ok(cleaned) => Some(cleaned),
+        Err(e) => {
+            eprintln!("LLM cleanup failed: {}", e);
+            None
+        }
+    }
+}
+
+/// Strip chatter from an LLM reply and, unless guardrails are off, check it is still
+/// the dictation. A rejected reply comes back as the reason, for the history entry.
+fn guard_cleanup(
+    db: &Database,
+    raw_text: &str,
+    reply: &str,
+    translating: bool,
+    format: OutputFormat,
+) -> Result<String, String> {
+    let text = guardrails::strip_preamble(raw_text, reply);
+    if !guardrails::is_enabled(db) {
+        return Ok(text);
+    }
+    guardrails::check(raw_text, &text, translating, format).map_err(|e| {
+        eprintln!("LLM cleanup rejected: {}", e);
+        format!("LLM output discarded: {}", e)
+    })?;
+    Ok(text)
+}
+
+/// Streaming variant of `llm_cleanup`: each piece goes out as a `cleanup-delta` event
+/// and, with `typing`, straight into the target app
+async fn llm_cleanup_streaming(
+    app: &tauri::AppHandle,
+    provider: &dyn cleanup::CleanupProvider,
+    raw_text: &str,
+    prompt: &cleanup::PromptContext<'_>,
+    mut typing: Option<&mut insertion::IncrementalInsertion>,
+) -> Option<String> {
+    let mut on_delta = |delta: &str| {
+        let _ = app.emit("cleanup-delta", delta);
+        if let Some(typing) = typing.as_mut() {
+            typing.push(delta);
+        }
+    };
+    match cleanup::cleanup_text_stream(provider, raw_text, prompt, &mut on_delta).await {
+        Ok(cleaned) => Some(cleaned),
+        Err(e) => {
+            eprintln!("LLM cleanup failed: {}", e);
+            None
+        }
+    }
+}
+
+/// The chosen profile, falling back to the default one if it has since been deleted
+fn active_profile(db: &Database, profile_id: Option<i64>) -> anyhow::Result<db::Profile> {
+    if let Some(profile) = profile_id
+        .map(|id| db.get_profile_by_id(id))
+        .transpose()?
+        .flatten()
+    {
+        return Ok(profile);
+    }
+    db.get_profile()
+}
+
+/// The profile and app rule for a dictation. A shortcut's own profile wins; otherwise
+/// the first app rule matching the focused app picks it.
+fn dictation_profile(
+    db: &Database,
+    context: &DictationContext,
+) -> anyhow::Result<(db::Profile, Option<db::AppRule>)> {
+    let app_rules = db.get_app_rules()?;
+    let app_rule = context
+        .window
+        .as_ref()
+        .and_then(|w| w.matching_rule(&app_rules))
+        .cloned();
+    let profile_id = context
+        .profile_id
+        .or_else(|| app_rule.as_ref().and_then(|r| r.profile_id));
+    Ok((active_profile(db, profile_id)?, app_rule))
+}
+
+/// Record an edit of a local history entry's cleaned text as a correction
+fn learn_correction(db: &Database, id: &str, corrected_text: &str) {
+    let result = db.get_dictation(id).and_then(|entry| match entry {
+        Some(entry) if entry.translated_text.is_empty() => {
+            let model_output = match entry.cleaned_text.as_str() {
+                "" => &entry.raw_text,
+                cleaned => cleaned,
+            };
+            corrections::record(db, id, &entry.raw_text, model_output, corrected_text)
+        }
+        _ => Ok(()),
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to record correction: {}", e);
+    }
+}
+
+/// Apply `utterance` to the previous dictation if it is an edit command ("scratch that",
+/// "replace X with Y", "make that more formal"). Only considered while the previous
+/// dictation is still in the focused window; returns None for ordinary dictation.
+#[allow(clippy::too_many_arguments)]
+async fn try_voice_edit(
+    app: &tauri::AppHandle,
+    utterance: &str,
+    setup_mode: &str,
+    session_token: Option<&str>,
+    provider: Option<&dyn cleanup::CleanupProvider>,
+    profile: &db::Profile,
+    custom_words: &[String],
+) -> Result<Option<DictationResult>, String> {
+    let db = app.state::<Database>();
+    let inserter = app.state::<Inserter>();
+    if !voice_edit::is_enabled(&db) {
+        return Ok(None);
+    }
+    let current = app.state::<WindowTracker>().current();
+    let Some(last) = inserter
+        .last_insertion()
+        .filter(|last| last.is_focused(current.as_ref()))
+    else {
+        return Ok(None);
+    };
+    let client = app.state::<HttpClient>().get();
+
+    // Only providers that see the local prompt can classify; the Parrot backend can't
+    let local_provider = provider.filter(|p| p.uses_local_prompt());
+    let mut intent = voice_edit::classify(utterance);
+    if let Some(provider) = local_provider
+        .filter(|_| intent == EditIntent::Dictation && voice_edit::is_llm_enabled(&db))
+    {
+        intent = voice_edit::classify_with_llm(provider, utterance)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("Edit intent classification failed: {}", e);
+                EditIntent::Dictation
+            });
+    }
+
+    // A replacement fixes a word the model got wrong; a rewrite only changes style
+    let is_correction = matches!(intent, EditIntent::Replace { .. });
+    let edited = match intent {
+        EditIntent::Dictation => return Ok(None),
+        EditIntent::Scratch => None,
+        // "change the date to Friday" with no "the date" in the last dictation is new text
+        EditIntent::Replace { find, replacement } => {
+            match voice_edit::replace_ignore_case(&last.text, &find, &replacement) {
+                Some(edited) => Some(edited),
+                None => return Ok(None),
+            }
+        }
+        EditIntent::Rewrite { instruction } => {
+            let Some(provider) = local_provider else {
+                return Err("Rewriting the last dictation needs a cleanup provider other than the Parrot backend".to_string());
+            };
+            set_pipeline_state(app, PipelineState::Cleaning)?;
+            let prompt = cleanup::PromptContext {
+                custom_words,
+                context_prompt: &profile.context_prompt,
+                writing_style: &instruction,
+                ..Default::default()
+            };
+            let rewritten = llm_cleanup(provider, &last.text, &prompt)
+                .await
+                .ok_or("Cleanup failed; the last dictation was left as it was")?;
+            // A rewrite may change every word, so only the chatter is removed
+            Some(guardrails::strip_preamble(&last.text, &rewritten))
+        }
+    };
+
+    set_pipeline_state(app, PipelineState::Inserting)?;
+    let window = current.clone();
+    with_inserter(app, move |inserter| inserter.undo_last(window.as_ref())).await?;
+    let edit_state = app.state::<EditState>();
+    let target = edit_state.last();
+    let pasted = match &edited {
+        Some(text) => {
+            let insertion_method = insertion::parse_setting(&profile.insertion_method).ok();
+            let (text_to_insert, window) = (text.clone(), current.clone());
+            with_inserter(app, move |inserter| {
+                inserter.insert_text(&text_to_insert, insertion_method, window.as_ref())
+            })
+            .await?;
+            show_last_insertion(app, Some(text));
+            true
+        }
+        None => {
+            show_last_insertion(app, None);
+            edit_state.set(None);
+            false
+        }
+    };
+
+    // The edited text replaces the original in its history entry; a scratched
+    // dictation keeps its entry, since it was still dictated
+    if let (Some(text), Some(target)) = (&edited, &target) {
+        match setup_mode {
+            "local" => {
+                let _ = match target.field {
+                    voice_edit::HistoryField::Cleaned => {
+                        if is_correction {
+                            learn_correction(&db, &target.history_id, text);
+                        }
+                        db.update_dictation_cleaned(&target.history_id, text)
+                    }
+                    voice_edit::HistoryField::Translated => {
+                        db.update_dictation_translated(&target.history_id, text)
+                    }
+                };
+            }
+            "cloud" => {
+                if let Some(token) = session_token {
+                    let id = &target.history_id;
+                    let _ = match target.field {
+                        voice_edit::HistoryField::Cleaned => {
+                            cloud_api::update_dictation_cleaned(&client, token, id, text).await
+                        }
+                        voice_edit::HistoryField::Translated => {
+                            cloud_api::update_dictation_translated(&client, token, id, text).await
+                        }
+                    };
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let result = DictationResult {
+        raw_text: utterance.to_string(),
+        cleaned_text: edited.unwrap_or_default(),
+        translated_text: String::new(),
+        pasted,
+    };
+    set_pipeline_state(app, PipelineState::Idle)?;
+    let _ = app.emit("dictation-edited", result.clone());
+    Ok(Some(result))
+}
+
+async fn run_dictation(
+    app: tauri::AppHandle,
+    wav_data: Vec<u8>,
+    duration_ms: u64,
+    context: DictationContext,
+) -> Result<DictationResult, String> {
+    let db = app.state::<Database>();
+    let client = app.state::<HttpClient>().get();
+
+    let (profile, app_rule) = dictation_profile(&db, &context).map_err(|e| e.to_string())?;
+    let app_rule = app_rule.as_ref();
+    let custom_words: Vec<String> = serde_json::from_str(&profile.custom_words).unwrap_or_default();
+    let app_name = context
+        .window
+        .as_ref()
+        .map(|w| w.app_name.as_str())
+        .filter(|name| !name.is_empty());
+
+    let translation = match context.mode {
+        DictationMode::Dictate | DictationMode::Command => None,
+        DictationMode::Translate => {
+            Some(TranslationSettings::load(&db).map_err(|e| e.to_string())?)
+        }
+    };
+    let source_language = translation
+        .as_ref()
+        .and_then(|t| t.source_language.as_deref())
+        .or(Some(profile.language.as_str()).filter(|l| !l.is_empty()));
+    let target_language = translation.as_ref().map(|t| t.target_language.as_str());
+    // Translations are written as plain text
+    let output_format = match context.mode {
+        DictationMode::Dictate => OutputFormat::resolve(&profile, app_rule).unwrap_or_else(|e| {
+            eprintln!("Invalid output format: {}", e);
+            OutputFormat::Plain
+        }),
+        DictationMode::Command | DictationMode::Translate => OutputFormat::Plain,
+    };
 
     let setup_mode = db
         .get_setting("setup_mode")
@@ -86,133 +733,581 @@ async fn transcribe_last(
         .unwrap_or_else(|| "local".to_string());
     let session_token = db.get_setting("session_token").map_err(|e| e.to_string())?;
     let api_key = db.get_setting("api_key").map_err(|e| e.to_string())?;
+    // A bad provider setting costs the LLM pass, not the dictation
+    let provider = cleanup::load_provider(
+        &db,
+        client.clone(),
+        &setup_mode,
+        Some(profile.cleanup_model.as_str()),
+    )
+    .map_err(|e| eprintln!("Cleanup provider unavailable, skipping LLM cleanup: {}", e))
+    .ok();
 
-    // Step 1: Transcribe
-    let _ = app.emit("transcription-started", ());
+    // Step 1: Transcribe in the source language so history keeps the original
     let raw_text = transcription::transcribe_audio(
+        &client,
         &wav_data,
         &setup_mode,
         session_token.as_deref(),
         api_key.as_deref(),
+        source_language,
+        false,
     )
     .await
     .map_err(|e| e.to_string())?;
 
-    // Save initial entry
-    let id = uuid::Uuid::new_v4().to_string();
-    match setup_mode.as_str() {
-        "local" => {
-            db.insert_dictation(&id, &raw_text, "", "local", duration_ms as i64)
-                .map_err(|e| e.to_string())?;
-        }
-        "cloud" => {
-            let token = session_token
-                .as_deref()
-                .ok_or_else(|| "Session token required for cloud mode".to_string())?;
-            cloud_api::insert_dictation(token, &id, &raw_text, "", "cloud", duration_ms as i64)
-                .await
-                .map_err(|e| e.to_string())?;
+    // Step 1a: A command like "scratch that" edits the previous dictation instead
+    if context.mode == DictationMode::Dictate {
+        if let Some(result) = try_voice_edit(
+            &app,
+            &raw_text,
+            &setup_mode,
+            session_token.as_deref(),
+            provider.as_deref(),
+            &profile,
+            &custom_words,
+        )
+        .await?
+        {
+            return Ok(result);
         }
-        _ => return Err(format!("Unknown setup mode: {}", setup_mode)),
     }
 
-    // Step 2: LLM cleanup
-    let _ = app.emit("cleanup-started", ());
+    // Step 1b: Turn spoken commands ("new line", "period", ...) into formatting
+    let raw_text = if spoken_commands::is_enabled(&db) {
+        match CommandTable::load(&db, source_language, output_format) {
+            Ok(table) => table.apply(&raw_text),
+            Err(e) => {
+                eprintln!("Spoken formatting commands skipped: {}", e);
+                raw_text
+            }
+        }
+    } else {
+        raw_text
+    };
 
-    let cleaned_text = match setup_mode.as_str() {
+    // Step 1c: Expand snippet triggers; a dictation that is only a trigger is inserted
+    // exactly as stored, while history keeps what was said
+    let expansion = match context.mode {
+        DictationMode::Dictate => match SnippetSet::load(&db) {
+            Ok(snippets) => snippets.expand(&raw_text),
+            Err(e) => {
+                eprintln!("Snippets skipped: {}", e);
+                None
+            }
+        },
+        DictationMode::Command | DictationMode::Translate => None,
+    };
+    let verbatim = expansion.as_ref().is_some_and(|e| e.whole);
+    let expanded = expansion.map_or_else(|| raw_text.clone(), |e| e.text);
+
+    // Save initial entry
+    let id = uuid::Uuid::new_v4().to_string();
+    match setup_mode.as_str() {
         "local" => {
-            let llm_model = db.get_setting("llm_model").map_err(|e| e.to_string())?;
-            let profile = db.get_profile().map_err(|e| e.to_string())?;
-            match cleanup::cleanup_text(
+            db.insert_dictation(
+                &id,
                 &raw_text,
+                "",
                 "local",
-                None,
-                None,
-                llm_model.as_deref(),
-                &profile.context_prompt,
-                &profile.writing_style,
+                duration_ms as i64,
+                target_language.unwrap_or(""),
             )
-            .await
-            {
-                Ok(cleaned) => {
-                    let _ = db.update_dictation_cleaned(&id, &cleaned);
-                    cleaned
-                }
-                Err(e) => {
-                    eprintln!("LLM cleanup failed: {}", e);
-                    raw_text.clone()
-                }
+            .map_err(|e| e.to_string())?;
+            if let Some(app_name) = app_name {
+                let _ = db.update_dictation_app(&id, app_name);
             }
         }
         "cloud" => {
             let token = session_token
                 .as_deref()
                 .ok_or_else(|| "Session token required for cloud mode".to_string())?;
-            let llm_api_key = db.get_setting("llm_api_key").map_err(|e| e.to_string())?;
-            match cleanup::cleanup_text(
+            cloud_api::insert_dictation(
+                &client,
+                token,
+                &id,
                 &raw_text,
-                "cloud",
-                Some(token),
-                llm_api_key.as_deref(),
-                None,
-                "",
                 "",
+                "cloud",
+                duration_ms as i64,
             )
             .await
-            {
-                Ok(cleaned) => {
-                    let _ = cloud_api::update_dictation_cleaned(token, &id, &cleaned).await;
-                    cleaned
+            .map_err(|e| e.to_string())?;
+        }
+        _ => return Err(format!("Unknown setup mode: {}", setup_mode)),
+    }
+
+    // Step 2: LLM cleanup (and translation, in translation mode)
+    set_pipeline_state(&app, PipelineState::Cleaning)?;
+
+    // Corrections show the model the user's preferences; they don't carry over to translations
+    let examples = match translation {
+        None if provider.as_ref().is_some_and(|p| p.uses_local_prompt()) => {
+            corrections::examples(&db, &expanded)
+        }
+        _ => Vec::new(),
+    };
+    // What was last inserted into the same window, for templates that continue it
+    let previous_dictation = app
+        .state::<Inserter>()
+        .last_insertion()
+        .filter(|last| last.is_focused(context.window.as_ref()))
+        .map(|last| last.text)
+        .unwrap_or_default();
+    let template = prompt_templates::load(&db, output_format).unwrap_or_else(|e| {
+        eprintln!("Prompt template unavailable, using the built-in one: {}", e);
+        prompt_templates::default_template(output_format)
+    });
+    let prompt = cleanup::PromptContext {
+        custom_words: &custom_words,
+        context_prompt: &profile.context_prompt,
+        writing_style: &profile.writing_style,
+        target_language,
+        app_name,
+        app_context: app_rule.map(|r| r.context.as_str()).unwrap_or(""),
+        examples: &examples,
+        format: output_format,
+        language: source_language,
+        previous_dictation: &previous_dictation,
+        template: Some(&template),
+    };
+    let streaming = cleanup::Streaming::load(&db).unwrap_or_else(|e| {
+        eprintln!("Invalid streaming settings: {}", e);
+        cleanup::Streaming::Off
+    });
+    let rules = RuleMode::load(&db).unwrap_or_else(|e| {
+        eprintln!("Invalid rule cleanup setting: {}", e);
+        RuleMode::Fallback
+    });
+    // Numbers, dates and amounts are written out before either cleanup sees the text
+    let normalized = match NumberStyle::parse(&profile.text_normalization) {
+        _ if verbatim => expanded,
+        Ok(style) => Normalizer::new(source_language, style).normalize(&expanded),
+        Err(e) => {
+            eprintln!("Text normalization skipped: {}", e);
+            expanded
+        }
+    };
+    let rule_cleaned = (rules != RuleMode::Off && !verbatim)
+        .then(|| RuleCleaner::new(source_language, &custom_words).clean(&normalized));
+    // With the pre-pass the LLM gets the tidied text; history keeps the transcript
+    let llm_input = match (rules, &rule_cleaned) {
+        (RuleMode::PrePass, Some(tidied)) => tidied.clone(),
+        _ => normalized.clone(),
+    };
+    let llm = provider
+        .as_deref()
+        .filter(|_| rules != RuleMode::Only && !verbatim);
+    let inserter = app.state::<Inserter>();
+    // Text typed as it streams can't be reviewed first, so review takes precedence
+    let mut live_typing =
+        (llm.is_some() && streaming == cleanup::Streaming::Typing && !review::is_enabled(&db))
+            .then(|| inserter.begin_incremental(app.state::<WindowTracker>().current()));
+    let llm_output = match (llm, streaming) {
+        (None, _) => None,
+        (Some(provider), cleanup::Streaming::Off) => {
+            llm_cleanup(provider, &llm_input, &prompt).await
+        }
+        (Some(provider), _) => {
+            llm_cleanup_streaming(&app, provider, &llm_input, &prompt, live_typing.as_mut()).await
+        }
+    };
+    // Step 2a: A reply that answers, summarizes or loses the dictation is dropped,
+    // with the reason kept in history
+    let streamed = llm_output.clone();
+    let llm_output = match llm_output {
+        Some(reply) => match guard_cleanup(
+            &db,
+            &llm_input,
+            &reply,
+            translation.is_some(),
+            prompt.format,
+        ) {
+            Ok(text) => Some(text),
+            Err(note) => {
+                match (setup_mode.as_str(), session_token.as_deref()) {
+                    ("local", _) => {
+                        let _ = db.update_dictation_note(&id, &note);
+                    }
+                    (_, Some(token)) => {
+                        let _ = cloud_api::update_dictation_note(&client, token, &id, &note).await;
+                    }
+                    _ => {}
                 }
-                Err(e) => {
-                    eprintln!("LLM cleanup failed: {}", e);
-                    raw_text.clone()
+                None
+            }
+        },
+        None => None,
+    };
+    // Step 2b: Format fixes the model can't be relied on for; a snippet stays as stored
+    let finish = |text: String| match translation.is_none() && !verbatim {
+        true => output_format.post_process(&text),
+        false => text,
+    };
+    let llm_output = llm_output.map(finish);
+    // Keep the typed text only if the whole reply made it in unchanged; otherwise take
+    // it back out so the fallback below doesn't land next to half a sentence
+    if let Some(typing) = live_typing.as_mut() {
+        typing.settle().await;
+    }
+    let typed_live = match live_typing {
+        Some(typing)
+            if llm_output.is_some()
+                && llm_output.as_deref() == streamed.as_deref().map(str::trim)
+                && typing.is_intact() =>
+        {
+            typing.finish();
+            true
+        }
+        Some(typing) => {
+            if let Err(e) = typing.abort().await {
+                eprintln!("Failed to remove partially typed text: {}", e);
+            }
+            false
+        }
+        None => false,
+    };
+    // Rule-based cleanup (or at least normalization) stands in for a missing or discarded reply. Translations
+    // have no such fallback here; Whisper's translate task covers them below.
+    let cleanup_output = match &translation {
+        None => llm_output
+            .or_else(|| rule_cleaned.map(finish))
+            .or_else(|| Some(finish(normalized)).filter(|text| *text != raw_text)),
+        Some(_) => llm_output,
+    };
+    if let (Some(output), "cloud", Some(token)) = (
+        &cleanup_output,
+        setup_mode.as_str(),
+        session_token.as_deref(),
+    ) {
+        let _ = match translation {
+            None => cloud_api::update_dictation_cleaned(&client, token, &id, output).await,
+            Some(_) => cloud_api::update_dictation_translated(&client, token, &id, output).await,
+        };
+    }
+
+    let (mut cleaned_text, mut translated_text) = match &translation {
+        None => match cleanup_output {
+            Some(cleaned) => {
+                if setup_mode == "local" {
+                    let _ = db.update_dictation_cleaned(&id, &cleaned);
+                }
+                (cleaned, String::new())
+            }
+            None => (raw_text.clone(), String::new()),
+        },
+        Some(settings) => {
+            let translated = match cleanup_output {
+                Some(translated) => translated,
+                // Without an LLM, Whisper's translate task can still produce English
+                None if setup_mode == "local" && settings.whisper_can_translate() => {
+                    transcription::transcribe_audio(
+                        &client,
+                        &wav_data,
+                        &setup_mode,
+                        None,
+                        None,
+                        source_language,
+                        true,
+                    )
+                    .await
+                    .unwrap_or_else(|e| {
+                        eprintln!("Whisper translation failed: {}", e);
+                        String::new()
+                    })
                 }
+                None => String::new(),
+            };
+            if setup_mode == "local" && !translated.is_empty() {
+                let _ = db.update_dictation_translated(&id, &translated);
             }
+            (String::new(), translated)
         }
-        _ => raw_text.clone(),
     };
 
-    // Step 3: Copy to clipboard and paste
-    let output_text = if cleaned_text.is_empty() {
-        &raw_text
+    let mut output_text = if !translated_text.is_empty() {
+        translated_text.clone()
+    } else if !cleaned_text.is_empty() {
+        cleaned_text.clone()
     } else {
-        &cleaned_text
+        raw_text.clone()
+    };
+
+    // Optional review: the user can edit the text, pick the raw transcript, or re-run
+    // cleanup with another style before anything is inserted
+    if review::is_enabled(&db) {
+        let mut request = review::ReviewRequest {
+            raw_text: raw_text.clone(),
+            cleaned_text: output_text.clone(),
+            writing_style: profile.writing_style.clone(),
+            can_rerun: provider.is_some(),
+        };
+        loop {
+            set_pipeline_state(&app, PipelineState::Reviewing)?;
+            let decision = review::request_review(&app, request.clone())
+                .await
+                .map_err(|e| e.to_string())?;
+            match decision {
+                review::ReviewDecision::Accept(text) => {
+                    output_text = text;
+                    break;
+                }
+                review::ReviewDecision::Discard => {
+                    review::hide_window(&app);
+                    set_pipeline_state(&app, PipelineState::Idle)?;
+                    let _ = app.emit("dictation-discarded", ());
+                    return Ok(DictationResult {
+                        raw_text,
+                        cleaned_text,
+                        translated_text,
+                        pasted: false,
+                    });
+                }
+                review::ReviewDecision::Rerun { writing_style } => {
+                    set_pipeline_state(&app, PipelineState::Cleaning)?;
+                    let restyled = cleanup::PromptContext {
+                        writing_style: &writing_style,
+                        ..prompt
+                    };
+                    let reply = match provider.as_deref() {
+                        Some(provider) => llm_cleanup(provider, &llm_input, &restyled).await,
+                        None => None,
+                    };
+                    if let Some(cleaned) = reply.and_then(|reply| {
+                        guard_cleanup(
+                            &db,
+                            &llm_input,
+                            &reply,
+                            translation.is_some(),
+                            restyled.format,
+                        )
+                        .ok()
+                    }) {
+                        request.cleaned_text = finish(cleaned);
+                    }
+                    request.writing_style = writing_style;
+                }
+            }
+        }
+        review::hide_window(&app);
+        // Give the window manager a moment to hand focus back to the target app
+        tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+
+        // An edited cleanup is a correction to learn from; picking the raw transcript isn't.
+        // Corrections live in the local database, like the history they refer to.
+        if setup_mode == "local"
+            && translation.is_none()
+            && output_text != request.cleaned_text
+            && output_text != raw_text
+        {
+            if let Err(e) =
+                corrections::record(&db, &id, &llm_input, &request.cleaned_text, &output_text)
+            {
+                eprintln!("Failed to record correction: {}", e);
+            }
+        }
+
+        // History keeps what was actually inserted
+        if setup_mode == "local" {
+            let _ = match &translation {
+                None => db.update_dictation_cleaned(&id, &output_text),
+                Some(_) => db.update_dictation_translated(&id, &output_text),
+            };
+        }
+        match &translation {
+            None => cleaned_text = output_text.clone(),
+            Some(_) => translated_text = output_text.clone(),
+        }
+    }
+
+    // Step 3: Copy to clipboard and paste
+    set_pipeline_state(&app, PipelineState::Inserting)?;
+    let output_text = &output_text;
+    let insertion_method = insertion::parse_setting(&profile.insertion_method).ok();
+    // Focus may have moved since recording started, so look again for the paste chord
+    let target = app.state::<WindowTracker>().current();
+    let inserted = match typed_live {
+        true => Ok(()),
+        false => {
+            let text = output_text.clone();
+            with_inserter(&app, move |inserter| {
+                inserter.insert_text(&text, insertion_method, target.as_ref())
+            })
+            .await
+        }
+    };
+    let pasted = match inserted {
+        Ok(()) => {
+            show_last_insertion(&app, Some(output_text));
+            app.state::<EditState>().set(Some(voice_edit::EditTarget {
+                history_id: id.clone(),
+                field: if translated_text.is_empty() {
+                    voice_edit::HistoryField::Cleaned
+                } else {
+                    voice_edit::HistoryField::Translated
+                },
+            }));
+            true
+        }
+        Err(e) => {
+            eprintln!("Insertion failed: {}", e);
+            false
+        }
     };
-    let pasted = copy_and_paste(output_text);
 
     let result = DictationResult {
         raw_text: raw_text.clone(),
         cleaned_text: cleaned_text.clone(),
+        translated_text: translated_text.clone(),
         pasted,
     };
+    set_pipeline_state(&app, PipelineState::Idle)?;
     let _ = app.emit("dictation-complete", result.clone());
     Ok(result)
 }
 
-fn copy_and_paste(text: &str) -> bool {
-    use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+/// Command mode: copy the selection from the focused app, transcribe the spoken
+/// instruction, and replace the selection with what the LLM makes of it
+async fn run_command(
+    app: tauri::AppHandle,
+    wav_data: Vec<u8>,
+    duration_ms: u64,
+    context: DictationContext,
+) -> Result<DictationResult, String> {
+    let db = app.state::<Database>();
+    let client = app.state::<HttpClient>().get();
 
-    // Copy to clipboard
-    let mut clipboard = arboard::Clipboard::new();
-    let Ok(ref mut cb) = clipboard else {
-        eprintln!("Failed to access clipboard");
-        return false;
+    let (profile, app_rule) = dictation_profile(&db, &context).map_err(|e| e.to_string())?;
+    let custom_words: Vec<String> = serde_json::from_str(&profile.custom_words).unwrap_or_default();
+    let app_name = context
+        .window
+        .as_ref()
+        .map(|w| w.app_name.as_str())
+        .filter(|name| !name.is_empty());
+    let setup_mode = db
+        .get_setting("setup_mode")
+        .map_err(|e| e.to_string())?
+        .unwrap_or_else(|| "local".to_string());
+    let session_token = db.get_setting("session_token").map_err(|e| e.to_string())?;
+    let api_key = db.get_setting("api_key").map_err(|e| e.to_string())?;
+    let provider = cleanup::load_provider(
+        &db,
+        client.clone(),
+        &setup_mode,
+        Some(profile.cleanup_model.as_str()),
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Step 1: Grab the selection before anything else can move focus. A toggle shortcut
+    // may still be held, and its modifiers would turn the copy chord into something else.
+    tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+    let target = app.state::<WindowTracker>().current();
+    let selection = with_inserter(&app, move |inserter| {
+        inserter.copy_selection(target.as_ref())
+    })
+    .await?
+    .ok_or_else(|| "Select some text before giving a command".to_string())?;
+
+    // Step 2: Transcribe the instruction
+    let language = Some(profile.language.as_str()).filter(|l| !l.is_empty());
+    let instruction = transcription::transcribe_audio(
+        &client,
+        &wav_data,
+        &setup_mode,
+        session_token.as_deref(),
+        api_key.as_deref(),
+        language,
+        false,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    // Step 3: Transform the selection
+    set_pipeline_state(&app, PipelineState::Cleaning)?;
+    let prompt = cleanup::PromptContext {
+        custom_words: &custom_words,
+        context_prompt: &profile.context_prompt,
+        app_name,
+        app_context: app_rule.as_ref().map(|r| r.context.as_str()).unwrap_or(""),
+        ..Default::default()
     };
-    if cb.set_text(text).is_err() {
-        eprintln!("Failed to set clipboard text");
-        return false;
+    let result_text = cleanup::transform_text(provider.as_ref(), &selection, &instruction, &prompt)
+        .await
+        .map_err(|e| e.to_string())?;
+    let result_text = guardrails::strip_preamble(&selection, &result_text);
+
+    // History keeps the instruction as the raw text and the result as the cleaned text
+    let id = uuid::Uuid::new_v4().to_string();
+    match setup_mode.as_str() {
+        "local" => {
+            if let Err(e) = db.insert_dictation(
+                &id,
+                &instruction,
+                &result_text,
+                "local",
+                duration_ms as i64,
+                "",
+            ) {
+                eprintln!("Failed to save command to history: {}", e);
+            } else if let Some(app_name) = app_name {
+                if let Err(e) = db.update_dictation_app(&id, app_name) {
+                    eprintln!("Failed to save command app: {}", e);
+                }
+            }
+        }
+        "cloud" => {
+            if let Some(token) = session_token.as_deref() {
+                if let Err(e) = cloud_api::insert_dictation(
+                    &client,
+                    token,
+                    &id,
+                    &instruction,
+                    &result_text,
+                    "cloud",
+                    duration_ms as i64,
+                )
+                .await
+                {
+                    eprintln!("Failed to save command to history: {}", e);
+                }
+            }
+        }
+        _ => {}
     }
 
-    // Small delay then Cmd+V to paste into focused field
-    std::thread::sleep(std::time::Duration::from_millis(50));
-    let Ok(mut enigo) = Enigo::new(&Settings::default()) else {
-        return false;
+    // Step 4: Pasting over the selection (still selected after the copy) replaces it
+    set_pipeline_state(&app, PipelineState::Inserting)?;
+    let insertion_method = insertion::parse_setting(&profile.insertion_method).ok();
+    let target = app.state::<WindowTracker>().current();
+    let text = result_text.clone();
+    let inserted = with_inserter(&app, move |inserter| {
+        inserter.insert_text(&text, insertion_method, target.as_ref())
+    })
+    .await;
+    let pasted = match inserted {
+        Ok(()) => {
+            show_last_insertion(&app, Some(&result_text));
+            app.state::<EditState>().set(Some(voice_edit::EditTarget {
+                history_id: id,
+                field: voice_edit::HistoryField::Cleaned,
+            }));
+            true
+        }
+        Err(e) => {
+            eprintln!("Insertion failed: {}", e);
+            false
+        }
     };
-    let _ = enigo.key(Key::Meta, Direction::Press);
-    let _ = enigo.key(Key::Unicode('v'), Direction::Click);
-    let _ = enigo.key(Key::Meta, Direction::Release);
-    true
+
+    let result = DictationResult {
+        raw_text: instruction,
+        cleaned_text: result_text,
+        translated_text: String::new(),
+        pasted,
+    };
+    set_pipeline_state(&app, PipelineState::Idle)?;
+    let _ = app.emit("dictation-complete", result.clone());
+    Ok(result)
 }
 
 /// DictationEntry type used by both local and cloud modes in command responses
@@ -224,10 +1319,17 @@ struct DictationEntry {
     provider: String,
     duration_ms: i64,
     created_at: String,
+    translated_text: String,
+    target_language: String,
+    app_name: String,
+    cleanup_note: String,
 }
 
 #[tauri::command]
-async fn get_history(db: tauri::State<'_, Database>) -> Result<Vec<DictationEntry>, String> {
+async fn get_history(
+    db: tauri::State<'_, Database>,
+    http: tauri::State<'_, HttpClient>,
+) -> Result<Vec<DictationEntry>, String> {
     let setup_mode = db
         .get_setting("setup_mode")
         .map_err(|e| e.to_string())?
@@ -245,6 +1347,10 @@ async fn get_history(db: tauri::State<'_, Database>) -> Result<Vec<DictationEntr
                     provider: e.provider,
                     duration_ms: e.duration_ms,
                     created_at: e.created_at,
+                    translated_text: e.translated_text,
+                    target_language: e.target_language,
+                    app_name: e.app_name,
+                    cleanup_note: e.cleanup_note,
                 })
                 .collect())
         }
@@ -253,7 +1359,7 @@ async fn get_history(db: tauri::State<'_, Database>) -> Result<Vec<DictationEntr
                 .get_setting("session_token")
                 .map_err(|e| e.to_string())?
                 .ok_or_else(|| "Session token required for cloud mode".to_string())?;
-            let entries = cloud_api::get_history(&session_token)
+            let entries = cloud_api::get_history(&http.get(), &session_token)
                 .await
                 .map_err(|e| e.to_string())?;
             Ok(entries
@@ -265,6 +1371,10 @@ async fn get_history(db: tauri::State<'_, Database>) -> Result<Vec<DictationEntr
                     provider: e.provider,
                     duration_ms: e.duration_ms,
                     created_at: e.created_at,
+                    translated_text: e.translated_text,
+                    target_language: String::new(),
+                    app_name: String::new(),
+                    cleanup_note: e.cleanup_note,
                 })
                 .collect())
         }
@@ -276,6 +1386,7 @@ async fn get_history(db: tauri::State<'_, Database>) -> Result<Vec<DictationEntr
 async fn search_history(
     query: &str,
     db: tauri::State<'_, Database>,
+    http: tauri::State<'_, HttpClient>,
 ) -> Result<Vec<DictationEntry>, String> {
     let setup_mode = db
         .get_setting("setup_mode")
@@ -294,6 +1405,10 @@ async fn search_history(
                     provider: e.provider,
                     duration_ms: e.duration_ms,
                     created_at: e.created_at,
+                    translated_text: e.translated_text,
+                    target_language: e.target_language,
+                    app_name: e.app_name,
+                    cleanup_note: e.cleanup_note,
                 })
                 .collect())
         }
@@ -302,7 +1417,7 @@ async fn search_history(
                 .get_setting("session_token")
                 .map_err(|e| e.to_string())?
                 .ok_or_else(|| "Session token required for cloud mode".to_string())?;
-            let entries = cloud_api::search_history(&session_token, query)
+            let entries = cloud_api::search_history(&http.get(), &session_token, query)
                 .await
                 .map_err(|e| e.to_string())?;
             Ok(entries
@@ -314,6 +1429,10 @@ async fn search_history(
                     provider: e.provider,
                     duration_ms: e.duration_ms,
                     created_at: e.created_at,
+                    translated_text: e.translated_text,
+                    target_language: String::new(),
+                    app_name: String::new(),
+                    cleanup_note: e.cleanup_note,
                 })
                 .collect())
         }
@@ -321,14 +1440,186 @@ async fn search_history(
     }
 }
 
+/// Replace a dictation's cleaned text with the user's correction. Local history entries
+/// are also kept as corrections to learn from.
+#[tauri::command]
+async fn correct_dictation(
+    id: String,
+    text: String,
+    db: tauri::State<'_, Database>,
+    http: tauri::State<'_, HttpClient>,
+) -> Result<(), String> {
+    if text.trim().is_empty() {
+        return Err("The corrected text can't be empty".to_string());
+    }
+    let setup_mode = db
+        .get_setting("setup_mode")
+        .map_err(|e| e.to_string())?
+        .unwrap_or_else(|| "local".to_string());
+
+    match setup_mode.as_str() {
+        "local" => {
+            learn_correction(&db, &id, &text);
+            db.update_dictation_cleaned(&id, &text)
+                .map_err(|e| e.to_string())
+        }
+        "cloud" => {
+            let session_token = db
+                .get_setting("session_token")
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "Session token required for cloud mode".to_string())?;
+            cloud_api::update_dictation_cleaned(&http.get(), &session_token, &id, &text)
+                .await
+                .map_err(|e| e.to_string())
+        }
+        _ => Err(format!("Unknown setup mode: {}", setup_mode)),
+    }
+}
+
+/// Terms the user keeps correcting that the profile's custom words don't cover yet
+#[tauri::command]
+fn suggest_custom_words(
+    profile_id: i64,
+    db: tauri::State<'_, Database>,
+) -> Result<Vec<corrections::WordSuggestion>, String> {
+    let profile = db
+        .get_profile_by_id(profile_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Profile {} not found", profile_id))?;
+    let custom_words: Vec<String> = serde_json::from_str(&profile.custom_words).unwrap_or_default();
+    corrections::load_suggestions(&db, &custom_words).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn clear_corrections(db: tauri::State<'_, Database>) -> Result<(), String> {
+    db.clear_corrections().map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 fn get_setting(key: &str, state: tauri::State<'_, Database>) -> Result<Option<String>, String> {
     state.get_setting(key).map_err(|e| e.to_string())
 }
 
+/// Move the undo shortcut to `shortcut`. If it can't be registered the old one stays.
+fn change_undo_hotkey(
+    app: &tauri::AppHandle,
+    shortcut: Shortcut,
+    name: &str,
+) -> Result<(), String> {
+    let undo = app.state::<UndoShortcut>();
+    let mut current = undo.0.lock().unwrap();
+    if *current == shortcut {
+        return Ok(());
+    }
+    let taken = shortcut == app.state::<ProcessingState>().cancel_shortcut()
+        || app
+            .state::<ShortcutRegistry>()
+            .binding_for(&shortcut)
+            .is_some();
+    if taken {
+        return Err(format!("{} is already used by another shortcut", name));
+    }
+
+    let global_shortcut = app.global_shortcut();
+    let _ = global_shortcut.unregister(*current);
+    if let Err(e) = global_shortcut.register(shortcut) {
+        let _ = global_shortcut.register(*current);
+        return Err(format!("Failed to register {}: {}", name, e));
+    }
+    *current = shortcut;
+    Ok(())
+}
+
+/// Move the cancel shortcut to `shortcut`. It's only registered while a dictation is
+/// processing, so otherwise there's nothing to re-register until the next one.
+fn change_cancel_hotkey(
+    app: &tauri::AppHandle,
+    shortcut: Shortcut,
+    name: &str,
+) -> Result<(), String> {
+    // Checked before locking: changing the undo shortcut takes the two locks the other way round
+    let taken = shortcut == app.state::<UndoShortcut>().get()
+        || app
+            .state::<ShortcutRegistry>()
+            .binding_for(&shortcut)
+            .is_some();
+    let processing = app.state::<ProcessingState>();
+    let mut current = processing.cancel_shortcut.lock().unwrap();
+    if *current == shortcut {
+        return Ok(());
+    }
+    if taken {
+        return Err(format!("{} is already used by another shortcut", name));
+    }
+
+    if processing.task.lock().unwrap().is_some() {
+        let global_shortcut = app.global_shortcut();
+        let _ = global_shortcut.unregister(*current);
+        if let Err(e) = global_shortcut.register(shortcut) {
+            let _ = global_shortcut.register(*current);
+            return Err(format!("Failed to register {}: {}", name, e));
+        }
+    }
+    *current = shortcut;
+    Ok(())
+}
+
 #[tauri::command]
-fn set_setting(key: &str, value: &str, state: tauri::State<'_, Database>) -> Result<(), String> {
-    state.set_setting(key, value).map_err(|e| e.to_string())
+fn set_setting(
+    key: &str,
+    value: &str,
+    state: tauri::State<'_, Database>,
+    http: tauri::State<'_, HttpClient>,
+    inserter: tauri::State<'_, Inserter>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    if key == "formatting_commands" {
+        spoken_commands::validate_table(value).map_err(|e| e.to_string())?;
+    }
+    if key == "rule_cleanup" {
+        RuleMode::parse(value).map_err(|e| e.to_string())?;
+    }
+    // Empty restores the default
+    if matches!(key, "cancel_hotkey" | "undo_hotkey") && !value.trim().is_empty() {
+        value
+            .trim()
+            .parse::<Shortcut>()
+            .map_err(|e| format!("Invalid shortcut {}: {}", value.trim(), e))?;
+    }
+    if matches!(key, "cancel_hotkey" | "undo_hotkey") {
+        let default = if key == "cancel_hotkey" {
+            DEFAULT_CANCEL_HOTKEY
+        } else {
+            DEFAULT_UNDO_HOTKEY
+        };
+        let hotkey = Some(value.trim())
+            .filter(|v| !v.is_empty())
+            .unwrap_or(default);
+        let shortcut = hotkey
+            .parse()
+            .map_err(|e| format!("Invalid shortcut {}: {}", hotkey, e))?;
+        if key == "cancel_hotkey" {
+            change_cancel_hotkey(&app, shortcut, hotkey)?;
+        } else {
+            change_undo_hotkey(&app, shortcut, hotkey)?;
+        }
+    }
+    // Provider settings are read per dictation, so report a bad value now rather than then
+    cleanup::validate_setting(key, value).map_err(|e| e.to_string())?;
+    state.set_setting(key, value).map_err(|e| e.to_string())?;
+    if http::is_http_setting(key) {
+        http.reload(&state).map_err(|e| e.to_string())?;
+    }
+    if insertion::is_insertion_setting(key) {
+        inserter.reload(&state).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// The paste backend in use, so settings can show what "auto" resolved to
+#[tauri::command]
+fn get_paste_backend(inserter: tauri::State<'_, Inserter>) -> insertion::PasteBackend {
+    inserter.backend()
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Clone)]
@@ -339,7 +1630,10 @@ struct ProfileData {
 }
 
 #[tauri::command]
-async fn get_profile(db: tauri::State<'_, Database>) -> Result<ProfileData, String> {
+async fn get_profile(
+    db: tauri::State<'_, Database>,
+    http: tauri::State<'_, HttpClient>,
+) -> Result<ProfileData, String> {
     let setup_mode = db
         .get_setting("setup_mode")
         .map_err(|e| e.to_string())?
@@ -359,7 +1653,7 @@ async fn get_profile(db: tauri::State<'_, Database>) -> Result<ProfileData, Stri
                 .get_setting("session_token")
                 .map_err(|e| e.to_string())?
                 .ok_or_else(|| "Session token required for cloud mode".to_string())?;
-            let p = cloud_api::get_profile(&session_token)
+            let p = cloud_api::get_profile(&http.get(), &session_token)
                 .await
                 .map_err(|e| e.to_string())?;
             Ok(ProfileData {
@@ -378,6 +1672,7 @@ async fn update_profile(
     context_prompt: &str,
     writing_style: &str,
     db: tauri::State<'_, Database>,
+    http: tauri::State<'_, HttpClient>,
 ) -> Result<(), String> {
     let setup_mode = db
         .get_setting("setup_mode")
@@ -393,14 +1688,213 @@ async fn update_profile(
                 .get_setting("session_token")
                 .map_err(|e| e.to_string())?
                 .ok_or_else(|| "Session token required for cloud mode".to_string())?;
-            cloud_api::update_profile(&session_token, custom_words, context_prompt, writing_style)
-                .await
-                .map_err(|e| e.to_string())
+            cloud_api::update_profile(
+                &http.get(),
+                &session_token,
+                custom_words,
+                context_prompt,
+                writing_style,
+            )
+            .await
+            .map_err(|e| e.to_string())
         }
         _ => Err(format!("Unknown setup mode: {}", setup_mode)),
     }
 }
 
+/// Named profiles live in the local database; in cloud mode the server keeps a single profile
+#[tauri::command]
+fn list_profiles(db: tauri::State<'_, Database>) -> Result<Vec<db::Profile>, String> {
+    db.list_profiles().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn create_profile(
+    profile: db::ProfileInput,
+    db: tauri::State<'_, Database>,
+) -> Result<db::Profile, String> {
+    validate_profile(&profile)?;
+    let id = db.create_profile(&profile).map_err(|e| e.to_string())?;
+    db.get_profile_by_id(id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Profile was not created".to_string())
+}
+
+#[tauri::command]
+fn save_profile(
+    id: i64,
+    profile: db::ProfileInput,
+    db: tauri::State<'_, Database>,
+) -> Result<(), String> {
+    validate_profile(&profile)?;
+    db.save_profile(id, &profile).map_err(|e| e.to_string())
+}
+
+/// Shortcuts still pointing at a deleted profile fall back to the default profile
+#[tauri::command]
+fn delete_profile(id: i64, db: tauri::State<'_, Database>) -> Result<(), String> {
+    db.delete_profile(id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_app_rules(db: tauri::State<'_, Database>) -> Result<Vec<db::AppRule>, String> {
+    db.get_app_rules().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn save_app_rules(rules: Vec<db::AppRule>, db: tauri::State<'_, Database>) -> Result<(), String> {
+    for rule in &rules {
+        if rule.pattern.trim().is_empty() {
+            return Err("App rules need a pattern".to_string());
+        }
+        if rule.match_on != "app" && rule.match_on != "title" {
+            return Err(format!("Unknown rule target: {}", rule.match_on));
+        }
+        OutputFormat::parse(&rule.output_format).map_err(|e| e.to_string())?;
+    }
+    db.save_app_rules(&rules).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_snippets(db: tauri::State<'_, Database>) -> Result<Vec<db::Snippet>, String> {
+    db.get_snippets().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn save_snippets(snippets: Vec<db::Snippet>, db: tauri::State<'_, Database>) -> Result<(), String> {
+    snippets::validate(&snippets).map_err(|e| e.to_string())?;
+    db.save_snippets(&snippets).map_err(|e| e.to_string())
+}
+
+/// The cleanup prompt for an output format: the user's template or the built-in one
+#[tauri::command]
+fn get_prompt_template(
+    format: String,
+    db: tauri::State<'_, Database>,
+) -> Result<db::PromptTemplate, String> {
+    let format = OutputFormat::parse(&format).map_err(|e| e.to_string())?;
+    prompt_templates::load(&db, format).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn save_prompt_template(
+    template: db::PromptTemplate,
+    db: tauri::State<'_, Database>,
+) -> Result<(), String> {
+    prompt_templates::validate(&template).map_err(|e| e.to_string())?;
+    let format = OutputFormat::parse(&template.format).map_err(|e| e.to_string())?;
+    db.save_prompt_template(&db::PromptTemplate {
+        format: format.as_str().to_string(),
+        ..template
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Go back to the built-in template for an output format and return it
+#[tauri::command]
+fn reset_prompt_template(
+    format: String,
+    db: tauri::State<'_, Database>,
+) -> Result<db::PromptTemplate, String> {
+    let format = OutputFormat::parse(&format).map_err(|e| e.to_string())?;
+    db.delete_prompt_template(format.as_str())
+        .map_err(|e| e.to_string())?;
+    Ok(prompt_templates::default_template(format))
+}
+
+#[derive(serde::Serialize)]
+struct PromptPreview {
+    system_prompt: String,
+    user_prompt: String,
+    /// The model's reply, for a dry run
+    output: Option<String>,
+}
+
+/// Fill in `template` for `sample_text` with the profile's settings, as a dictation would.
+/// With `run`, the prompt is also sent to the profile's cleanup model; nothing is
+/// inserted or saved either way.
+#[tauri::command]
+async fn preview_prompt_template(
+    template: db::PromptTemplate,
+    sample_text: String,
+    profile_id: Option<i64>,
+    run: bool,
+    db: tauri::State<'_, Database>,
+    http: tauri::State<'_, HttpClient>,
+    inserter: tauri::State<'_, Inserter>,
+) -> Result<PromptPreview, String> {
+    prompt_templates::validate(&template).map_err(|e| e.to_string())?;
+    let format = OutputFormat::parse(&template.format).map_err(|e| e.to_string())?;
+    let profile = active_profile(&db, profile_id).map_err(|e| e.to_string())?;
+    let custom_words: Vec<String> = serde_json::from_str(&profile.custom_words).unwrap_or_default();
+    let examples = corrections::examples(&db, &sample_text);
+    let previous_dictation = inserter
+        .last_insertion()
+        .map(|last| last.text)
+        .unwrap_or_default();
+    let prompt = cleanup::PromptContext {
+        custom_words: &custom_words,
+        context_prompt: &profile.context_prompt,
+        writing_style: &profile.writing_style,
+        examples: &examples,
+        format,
+        language: Some(profile.language.as_str()).filter(|l| !l.is_empty()),
+        previous_dictation: &previous_dictation,
+        template: Some(&template),
+        ..Default::default()
+    };
+    let (system_prompt, user_prompt) =
+        cleanup::build_cleanup_prompt(&prompt, &sample_text).map_err(|e| e.to_string())?;
+
+    let output = if run {
+        let setup_mode = db
+            .get_setting("setup_mode")
+            .map_err(|e| e.to_string())?
+            .unwrap_or_else(|| "local".to_string());
+        let provider = cleanup::load_provider(
+            &db,
+            http.get(),
+            &setup_mode,
+            Some(profile.cleanup_model.as_str()),
+        )
+        .map_err(|e| e.to_string())?;
+        if !provider.uses_local_prompt() {
+            return Err(
+                "Trying a template needs a cleanup provider other than the Parrot backend"
+                    .to_string(),
+            );
+        }
+        let reply = provider
+            .chat(&system_prompt, &user_prompt)
+            .await
+            .map_err(|e| e.to_string())?;
+        Some(format.post_process(&guardrails::strip_preamble(&sample_text, &reply)))
+    } else {
+        None
+    };
+    Ok(PromptPreview {
+        system_prompt,
+        user_prompt,
+        output,
+    })
+}
+
+fn validate_profile(profile: &db::ProfileInput) -> Result<(), String> {
+    if profile.name.trim().is_empty() {
+        return Err("Profile name can't be empty".to_string());
+    }
+    if serde_json::from_str::<Vec<String>>(&profile.custom_words).is_err() {
+        return Err("Custom words must be a JSON array of strings".to_string());
+    }
+    if !profile.insertion_method.is_empty() {
+        insertion::parse_setting::<insertion::InsertionMethod>(&profile.insertion_method)
+            .map_err(|e| e.to_string())?;
+    }
+    NumberStyle::parse(&profile.text_normalization).map_err(|e| e.to_string())?;
+    OutputFormat::parse(&profile.output_format).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
 #[tauri::command]
 fn check_command_exists(name: String) -> bool {
     use std::process::Command;
@@ -441,13 +1935,41 @@ async fn install_tool(name: String) -> Result<String, String> {
     }
 }
 
+const DEFAULT_CANCEL_HOTKEY: &str = "Escape";
+const DEFAULT_UNDO_HOTKEY: &str = "CmdOrCtrl+Shift+Backspace";
+
+/// A shortcut setting read at startup; an invalid one falls back to `default` rather than
+/// keeping Parrot from starting
+fn hotkey_setting(db: &Database, key: &str, default: &str) -> Shortcut {
+    let hotkey = db
+        .get_setting(key)
+        .ok()
+        .flatten()
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| default.to_string());
+    hotkey.trim().parse().unwrap_or_else(|e| {
+        eprintln!("Invalid {} {}, using {}: {}", key, hotkey, default, e);
+        default.parse().expect("Invalid default hotkey")
+    })
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let db = Database::new().expect("Failed to initialize database");
+    let http_client = HttpClient::new(&db);
+    let inserter = Inserter::new(&db);
+    let cancel_shortcut = hotkey_setting(&db, "cancel_hotkey", DEFAULT_CANCEL_HOTKEY);
+    let undo_shortcut = hotkey_setting(&db, "undo_hotkey", DEFAULT_UNDO_HOTKEY);
+    let (dictation_queue, dictation_jobs) = DictationQueue::new();
+    let processing_state = ProcessingState {
+        task: Mutex::new(None),
+        cancel_shortcut: Mutex::new(cancel_shortcut),
+    };
     let recorder = AudioRecorder::new().expect("Failed to initialize audio recorder");
     let recorder_state = RecorderState {
         recorder: Mutex::new(recorder),
         recording_start: Mutex::new(None),
+        recording_context: Mutex::new(DictationContext::default()),
         last_wav: Mutex::new(None),
         last_duration_ms: Mutex::new(0),
     };
@@ -456,66 +1978,98 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(
             tauri_plugin_global_shortcut::Builder::new()
-                .with_shortcut("CmdOrCtrl+Shift+Space")
-                .expect("Failed to register default shortcut")
-                .with_handler(|app, _shortcut, event| {
+                .with_handler(move |app, shortcut, event| {
                     use tauri_plugin_global_shortcut::ShortcutState;
-                    let state = app.state::<RecorderState>();
-                    match event.state() {
-                        ShortcutState::Pressed => {
-                            let mut recorder = state.recorder.lock().unwrap();
-                            if !recorder.is_recording() {
-                                if let Err(e) = recorder.start() {
-                                    eprintln!("Failed to start recording: {}", e);
-                                    return;
-                                }
-                                *state.recording_start.lock().unwrap() = Some(Instant::now());
-                                let _ = app.emit("recording-started", ());
-                            }
+                    if *shortcut == app.state::<ProcessingState>().cancel_shortcut() {
+                        if event.state() == ShortcutState::Pressed {
+                            cancel_processing(app);
                         }
-                        ShortcutState::Released => {
-                            let mut recorder = state.recorder.lock().unwrap();
-                            if recorder.is_recording() {
-                                let duration_ms = state
-                                    .recording_start
-                                    .lock()
-                                    .unwrap()
-                                    .map(|s| s.elapsed().as_millis() as u64)
-                                    .unwrap_or(0);
-                                match recorder.stop() {
-                                    Ok(wav_data) => {
-                                        *state.last_duration_ms.lock().unwrap() = duration_ms;
-                                        *state.last_wav.lock().unwrap() = Some(wav_data);
-                                        let _ = app.emit("recording-stopped", duration_ms);
-                                    }
-                                    Err(e) => eprintln!("Failed to stop recording: {}", e),
-                                }
-                            }
+                        return;
+                    }
+                    if *shortcut == app.state::<UndoShortcut>().get() {
+                        if event.state() == ShortcutState::Pressed {
+                            spawn_undo(app);
                         }
+                        return;
                     }
+                    shortcuts::handle_shortcut(app, shortcut, event.state());
                 })
                 .build(),
         )
         .plugin(tauri_plugin_clipboard_manager::init())
         .manage(db)
+        .manage(http_client)
+        .manage(inserter)
+        .manage(WindowTracker::new())
         .manage(recorder_state)
+        .manage(processing_state)
+        .manage(Pipeline::new())
+        .manage(dictation_queue)
+        .manage(ShortcutRegistry::new())
+        .manage(UndoShortcut(Mutex::new(undo_shortcut)))
+        .manage(review::ReviewState::new())
+        .manage(EditState::new())
         .invoke_handler(tauri::generate_handler![
             start_recording,
             stop_recording,
             is_recording,
+            get_pipeline_state,
+            get_shortcuts,
+            get_shortcut_problems,
+            set_shortcuts,
             transcribe_last,
+            cancel_dictation,
+            undo_last_insertion,
+            get_last_insertion,
+            get_pending_review,
+            accept_review,
+            rerun_review_cleanup,
+            discard_review,
             get_history,
             search_history,
             get_setting,
             set_setting,
+            get_paste_backend,
             get_profile,
             update_profile,
+            list_profiles,
+            create_profile,
+            save_profile,
+            delete_profile,
+            get_app_rules,
+            save_app_rules,
+            get_snippets,
+            save_snippets,
+            get_prompt_template,
+            save_prompt_template,
+            reset_prompt_template,
+            preview_prompt_template,
+            correct_dictation,
+            suggest_custom_words,
+            clear_corrections,
             check_command_exists,
             install_tool,
         ])
-        .setup(|app| {
+        .setup(move |app| {
             setup_tray(app.handle())?;
 
+            // A shortcut taken by another app shouldn't stop Parrot from starting,
+            // nor the other shortcuts from working
+            let db = app.state::<Database>();
+            let bindings = shortcuts::load_bindings(&db).unwrap_or_else(|e| {
+                eprintln!("Invalid shortcut settings, using defaults: {}", e);
+                shortcuts::default_bindings()
+            });
+            app.state::<ShortcutRegistry>().apply_available(
+                app.handle(),
+                bindings,
+                &[cancel_shortcut, undo_shortcut],
+            );
+            if let Err(e) = app.global_shortcut().register(undo_shortcut) {
+                eprintln!("Failed to register undo shortcut: {}", e);
+            }
+            tauri::async_runtime::spawn(queue::run_worker(app.handle().clone(), dictation_jobs));
+
             // Hide window on close instead of quitting
             let window = app.get_webview_window("main").unwrap();
             let w = window.clone();
@@ -538,11 +2092,18 @@ fn setup_tray(app: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Error>>
 
     let open = MenuItemBuilder::with_id("open", "Open Parrot").build(app)?;
     let toggle = MenuItemBuilder::with_id("toggle", "Start Recording").build(app)?;
+    let last = MenuItemBuilder::with_id("last", "No dictation to undo")
+        .enabled(false)
+        .build(app)?;
+    let undo = MenuItemBuilder::with_id("undo", "Undo Last Dictation").build(app)?;
     let quit = MenuItemBuilder::with_id("quit", "Quit").build(app)?;
 
     let menu = MenuBuilder::new(app)
-        .items(&[&open, &toggle, &quit])
+        .items(&[&open, &toggle, &last, &undo, &quit])
         .build()?;
+    app.manage(TrayMenu {
+        last_insertion: last,
+    });
 
     let icon = tauri::image::Image::from_bytes(include_bytes!("../icons/tray-icon.png"))?;
 
@@ -559,32 +2120,20 @@ fn setup_tray(app: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Error>>
                 }
             }
             "toggle" => {
-                let state = app.state::<RecorderState>();
-                let mut recorder = state.recorder.lock().unwrap();
-                if recorder.is_recording() {
-                    let duration_ms = state
-                        .recording_start
-                        .lock()
-                        .unwrap()
-                        .map(|s| s.elapsed().as_millis() as u64)
-                        .unwrap_or(0);
-                    match recorder.stop() {
-                        Ok(wav_data) => {
-                            *state.last_duration_ms.lock().unwrap() = duration_ms;
-                            *state.last_wav.lock().unwrap() = Some(wav_data);
-                            let _ = app.emit("recording-stopped", duration_ms);
-                        }
-                        Err(e) => eprintln!("Failed to stop recording: {}", e),
-                    }
+                let result = if app.state::<Pipeline>().is_recording() {
+                    finish_and_queue_recording(app)
                 } else {
-                    if let Err(e) = recorder.start() {
-                        eprintln!("Failed to start recording: {}", e);
-                        return;
-                    }
-                    *state.recording_start.lock().unwrap() = Some(Instant::now());
-                    let _ = app.emit("recording-started", ());
+                    begin_recording(app, DictationMode::Dictate, None)
+                };
+                if let Err(e) = result {
+                    eprintln!("{}", e);
                 }
             }
+            "undo" => {
+                // Clicking the tray moves focus away on some desktops; undo_last then
+                // refuses rather than deleting the wrong text
+                spawn_undo(app);
+            }
             "quit" => {
                 app.exit(0);
             }
@@ -0,0 +1,391 @@
+use anyhow::Result;
+
+use crate::db::{AppRule, Profile};
+use crate::spoken_commands::{FormattingAction, IdentifierCase};
+
+/// Characters that mark a line as code rather than prose
+const CODE_CHARS: &[char] = &['=', '(', ')', '{', '}', '[', ']', ';', '_', '<', '>'];
+/// Line openings of comments, whose sentences keep their periods
+const COMMENT_MARKERS: &[&str] = &["//", "#", "--", "/*", "*", "\"\"\"", "<!--"];
+
+/// What the cleaned text is written as, chosen per profile or per app rule
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum OutputFormat {
+    /// Prose without markup
+    #[default]
+    Plain,
+    /// Headings, lists and emphasis
+    Markdown,
+    /// Code and comments, with identifiers and spoken symbols
+    Code,
+}
+
+impl OutputFormat {
+    /// "" or "plain", "markdown", "code"
+    pub fn parse(value: &str) -> Result<Self> {
+        Ok(match value {
+            "" | "plain" => Self::Plain,
+            "markdown" => Self::Markdown,
+            "code" => Self::Code,
+            other => anyhow::bail!("Unknown output format: {}", other),
+        })
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Plain => "plain",
+            Self::Markdown => "markdown",
+            Self::Code => "code",
+        }
+    }
+
+    /// An app rule's format wins over the profile's, since it knows what the app edits
+    pub fn resolve(profile: &Profile, app_rule: Option<&AppRule>) -> Result<Self> {
+        let value = app_rule
+            .map(|r| r.output_format.as_str())
+            .filter(|f| !f.is_empty())
+            .unwrap_or(&profile.output_format);
+        Self::parse(value)
+    }
+
+    /// Opening of the cleanup prompt; the profile's context and style follow it
+    pub fn system_prompt(self) -> &'static str {
+        match self {
+            Self::Plain => {
+                "You are a text cleanup assistant for voice dictation. \
+                 Fix grammar, punctuation, and remove filler words (um, uh, like, you know). \
+                 Preserve the speaker's meaning and tone. Keep existing line breaks and list items. \
+                 Return ONLY the cleaned text, nothing else."
+            }
+            Self::Markdown => {
+                "You are a text cleanup assistant for voice dictation into a Markdown document. \
+                 Fix grammar, punctuation, and remove filler words (um, uh, like, you know). \
+                 Format the text as Markdown: # headings for the section titles the speaker \
+                 announces, - for bullet lists, 1. for numbered steps, and **bold** or *italics* \
+                 only where the speaker asks for emphasis. Keep existing headings, line breaks \
+                 and list items. Preserve the speaker's meaning and wording. \
+                 Return ONLY the Markdown, without code fences."
+            }
+            Self::Code => {
+                "You are a dictation assistant for a code editor. Turn the dictation into the \
+                 code or code comment the speaker means. Write spoken symbols as characters \
+                 (\"equals\" is =, \"open bracket\" is [), keep identifiers that are already \
+                 written (user_id, getUserName) exactly, and name spoken identifiers in \
+                 snake_case or camelCase following the conventions of the language in use. \
+                 Prose dictated as a comment goes into that language's comment syntax. \
+                 Don't add code the speaker didn't say. \
+                 Return ONLY the code, without code fences or explanation."
+            }
+        }
+    }
+
+    /// Spoken phrases for this format, on top of the language's formatting commands.
+    /// They are English, the language code is dictated in.
+    pub fn spoken_commands(self) -> Vec<(&'static str, FormattingAction)> {
+        use FormattingAction::*;
+        let join = |s: &str| Join(s.to_string());
+        let symbol = |s: &str| Symbol(s.to_string());
+        let attach = |s: &str| Attach(s.to_string());
+        let open = |s: &str| Open(s.to_string());
+        let line = |s: &str| LinePrefix(s.to_string());
+
+        match self {
+            Self::Plain => Vec::new(),
+            Self::Markdown => vec![
+                ("heading", line("# ")),
+                ("heading one", line("# ")),
+                ("heading 1", line("# ")),
+                ("heading two", line("## ")),
+                ("heading 2", line("## ")),
+                ("heading three", line("### ")),
+                ("heading 3", line("### ")),
+                ("numbered item", line("1. ")),
+                ("block quote", line("> ")),
+                ("checkbox", line("- [ ] ")),
+            ],
+            Self::Code => vec![
+                ("open paren", join("(")),
+                ("open bracket", join("[")),
+                ("close bracket", attach("]")),
+                ("open brace", symbol("{")),
+                ("close brace", symbol("}")),
+                ("open angle", join("<")),
+                ("close angle", attach(">")),
+                ("dot", join(".")),
+                ("underscore", join("_")),
+                ("double colon", join("::")),
+                ("equals", symbol("=")),
+                ("double equals", symbol("==")),
+                ("triple equals", symbol("===")),
+                ("not equals", symbol("!=")),
+                ("plus equals", symbol("+=")),
+                ("minus equals", symbol("-=")),
+                ("plus sign", symbol("+")),
+                ("minus sign", symbol("-")),
+                ("asterisk", symbol("*")),
+                ("less than", symbol("<")),
+                ("greater than", symbol(">")),
+                ("arrow", symbol("->")),
+                ("fat arrow", symbol("=>")),
+                ("pipe", symbol("|")),
+                ("ampersand", symbol("&")),
+                ("hash sign", open("#")),
+                ("at sign", open("@")),
+                ("dollar sign", open("$")),
+                ("snake case", Case(IdentifierCase::Snake)),
+                ("camel case", Case(IdentifierCase::Camel)),
+                ("pascal case", Case(IdentifierCase::Pascal)),
+                ("kebab case", Case(IdentifierCase::Kebab)),
+                ("constant case", Case(IdentifierCase::Constant)),
+                ("screaming snake case", Case(IdentifierCase::Constant)),
+            ],
+        }
+    }
+
+    /// Fixes the model can't be relied on for: no stray markup in plain text, consistent
+    /// Markdown lists and spacing, no sentence periods after code. Fenced code blocks are
+    /// kept as written.
+    pub fn post_process(self, text: &str) -> String {
+        match self {
+            Self::Plain => outside_code_blocks(text, plain_line),
+            Self::Markdown => markdown(text),
+            Self::Code => text.lines().map(code_line).collect::<Vec<_>>().join("\n"),
+        }
+    }
+}
+
+/// `format` applied to each line outside ``` fenced blocks, which are kept as written
+fn outside_code_blocks(text: &str, format: impl Fn(&str) -> String) -> String {
+    let mut in_code = false;
+    text.lines()
+        .map(|line| {
+            if is_fence(line) {
+                in_code = !in_code;
+                line.to_string()
+            } else if in_code {
+                line.to_string()
+            } else {
+                format(line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn is_fence(line: &str) -> bool {
+    line.trim_start().starts_with("```")
+}
+
+/// "## Title" → "Title", "**word**" → "word", "* item" → "- item"
+fn plain_line(line: &str) -> String {
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let mut rest = line.trim_start();
+    let hashes = rest.len() - rest.trim_start_matches('#').len();
+    if (1..=6).contains(&hashes) && rest[hashes..].starts_with(' ') {
+        rest = rest[hashes..].trim_start();
+    }
+    let mut line = match list_bullet(rest) {
+        Some(item) => format!("{}- {}", indent, item),
+        None => format!("{}{}", indent, rest),
+    };
+    // Only "**": "__" also appears in identifiers such as __init__
+    let count = line.matches("**").count();
+    if count > 0 && count.is_multiple_of(2) {
+        line = line.replace("**", "");
+    }
+    line.trim_end().to_string()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LineKind {
+    Blank,
+    Heading,
+    ListItem,
+    Text,
+    /// Inside a fenced block, fences included
+    Code,
+}
+
+/// One list marker, numbered lists counted up, and blank lines around headings and lists.
+/// Fenced code blocks are left alone.
+fn markdown(text: &str) -> String {
+    let mut lines: Vec<(LineKind, String)> = Vec::new();
+    // Indent and number of the numbered item on the line before
+    let mut numbered: Option<(usize, u32)> = None;
+    let mut in_code = false;
+    for line in text.lines() {
+        let fence = is_fence(line);
+        if in_code || fence {
+            let opening = fence && !in_code;
+            if opening
+                && lines
+                    .last()
+                    .is_some_and(|(kind, _)| *kind == LineKind::Heading)
+            {
+                lines.push((LineKind::Blank, String::new()));
+            }
+            if fence {
+                in_code = !in_code;
+            }
+            numbered = None;
+            lines.push((LineKind::Code, line.to_string()));
+            continue;
+        }
+        let line = line.trim_end();
+        let indent = line.len() - line.trim_start().len();
+        let rest = line.trim_start();
+        let (kind, line) = if rest.is_empty() {
+            (LineKind::Blank, String::new())
+        } else if let Some(item) = list_bullet(rest) {
+            (LineKind::ListItem, format!("{}- {}", &line[..indent], item))
+        } else if let Some((number, item)) = numbered_item(rest) {
+            let number = match numbered {
+                Some((previous_indent, previous)) if previous_indent == indent => previous + 1,
+                _ => number,
+            };
+            numbered = Some((indent, number));
+            (
+                LineKind::ListItem,
+                format!("{}{}. {}", &line[..indent], number, item),
+            )
+        } else if is_heading(rest) {
+            (LineKind::Heading, rest.to_string())
+        } else {
+            (LineKind::Text, line.to_string())
+        };
+        if numbered_item(rest).is_none() {
+            numbered = None;
+        }
+
+        let previous = lines.last().map(|(kind, _)| *kind);
+        let needs_gap = match (previous, kind) {
+            (None | Some(LineKind::Blank), _) | (_, LineKind::Blank) => false,
+            (Some(LineKind::Heading), _) | (_, LineKind::Heading) => true,
+            (Some(LineKind::Text), LineKind::ListItem) => true,
+            // Without the gap the text would continue the last item
+            (Some(LineKind::ListItem), LineKind::Text) => true,
+            _ => false,
+        };
+        if needs_gap {
+            lines.push((LineKind::Blank, String::new()));
+        }
+        if kind == LineKind::Blank && previous.is_none_or(|p| p == LineKind::Blank) {
+            continue;
+        }
+        lines.push((kind, line));
+    }
+    while lines
+        .last()
+        .is_some_and(|(kind, _)| *kind == LineKind::Blank)
+    {
+        lines.pop();
+    }
+    lines
+        .into_iter()
+        .map(|(_, line)| line)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The text of a "* ", "• " or "+ " bullet. "+ 5" is a number rather than a bullet.
+fn list_bullet(line: &str) -> Option<&str> {
+    ["- ", "* ", "• ", "+ "]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))
+        .filter(|item| !(line.starts_with('+') && item.starts_with(|c: char| c.is_ascii_digit())))
+}
+
+/// "3. Step" or "3) Step" → (3, "Step")
+fn numbered_item(line: &str) -> Option<(u32, &str)> {
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let rest = line[digits..]
+        .strip_prefix(". ")
+        .or_else(|| line[digits..].strip_prefix(") "))?;
+    Some((line[..digits].parse().ok()?, rest))
+}
+
+fn is_heading(line: &str) -> bool {
+    let hashes = line.len() - line.trim_start_matches('#').len();
+    (1..=6).contains(&hashes) && line[hashes..].starts_with(' ')
+}
+
+/// "user_id = 5." → "user_id = 5"; comments keep their punctuation
+fn code_line(line: &str) -> String {
+    let line = line.trim_end();
+    let trimmed = line.trim_start();
+    let is_comment = COMMENT_MARKERS.iter().any(|m| trimmed.starts_with(m));
+    match line.strip_suffix('.') {
+        Some(code)
+            if !is_comment
+                && !code.ends_with('.')
+                && (code.contains(CODE_CHARS) || code.contains("->")) =>
+        {
+            code.to_string()
+        }
+        _ => line.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_spaces_headings() {
+        assert_eq!(
+            markdown("# Notes\nFirst point.\n## Next\n\n\nMore."),
+            "# Notes\n\nFirst point.\n\n## Next\n\nMore."
+        );
+    }
+
+    #[test]
+    fn markdown_renumbers_lists() {
+        let cases = [
+            ("1. One\n1. Two\n1. Three", "1. One\n2. Two\n3. Three"),
+            ("3) One\n7. Two", "3. One\n4. Two"),
+            // A nested list counts on its own
+            ("1. One\n  1. A\n  1. B", "1. One\n  1. A\n  2. B"),
+            ("* One\n+ Two\n• Three", "- One\n- Two\n- Three"),
+            ("Intro\n- One\nAfter", "Intro\n\n- One\n\nAfter"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(markdown(input), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn plus_followed_by_a_number_is_not_a_bullet() {
+        assert_eq!(markdown("+ 5"), "+ 5");
+        assert_eq!(plain_line("+ 5 points"), "+ 5 points");
+        assert_eq!(plain_line("+ Milk"), "- Milk");
+    }
+
+    #[test]
+    fn code_blocks_are_kept_as_written() {
+        let code = "```python\n# comment\n* args\n\n\n1. not_a_list\n```";
+        assert_eq!(
+            markdown(&format!("# Example\n{}\n# After", code)),
+            format!("# Example\n\n{}\n\n# After", code)
+        );
+        assert_eq!(
+            OutputFormat::Plain.post_process(&format!("**Run**\n{}", code)),
+            format!("Run\n{}", code)
+        );
+    }
+
+    #[test]
+    fn plain_strips_markup() {
+        let cases = [
+            ("## Title", "Title"),
+            ("  ### Indented", "  Indented"),
+            ("#hashtag", "#hashtag"),
+            ("A **bold** word", "A bold word"),
+            ("Stray ** marker", "Stray ** marker"),
+            ("* item", "- item"),
+            ("call __init__ here", "call __init__ here"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(plain_line(input), expected, "{:?}", input);
+        }
+    }
+}
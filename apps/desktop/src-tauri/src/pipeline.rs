@@ -0,0 +1,195 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::sync::Mutex;
+
+/// Phase of the dictation pipeline, emitted to the frontend as the `pipeline-state` event.
+/// Serialized as `{ "state": "transcribing" }` or `{ "state": "error", "message": "..." }`.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "state", content = "message", rename_all = "snake_case")]
+pub enum PipelineState {
+    Idle,
+    Recording,
+    Transcribing,
+    Cleaning,
+    /// Waiting for the user to accept the text in the review overlay
+    Reviewing,
+    Inserting,
+    Error(String),
+}
+
+impl PipelineState {
+    fn name(&self) -> &'static str {
+        match self {
+            PipelineState::Idle => "idle",
+            PipelineState::Recording => "recording",
+            PipelineState::Transcribing => "transcribing",
+            PipelineState::Cleaning => "cleaning",
+            PipelineState::Reviewing => "reviewing",
+            PipelineState::Inserting => "inserting",
+            PipelineState::Error(_) => "error",
+        }
+    }
+}
+
+/// Whether the processing side of the pipeline may move from `from` to `to`.
+/// Processing can always be cancelled back to Idle, and anything can fail into Error
+/// (including a microphone failure while otherwise idle). Reviewing can go back to
+/// Cleaning when the user re-runs cleanup with a different style.
+pub fn can_transition(from: &PipelineState, to: &PipelineState) -> bool {
+    use PipelineState::*;
+    matches!(
+        (from, to),
+        (Idle | Error(_), Transcribing)
+            | (Transcribing, Cleaning)
+            | (Cleaning, Reviewing)
+            | (Reviewing, Cleaning)
+            | (Transcribing | Cleaning | Reviewing, Inserting)
+            | (Transcribing | Cleaning | Reviewing | Inserting, Idle)
+            | (_, Error(_))
+            | (Error(_), Idle)
+    )
+}
+
+/// Owns the current pipeline state and rejects invalid transitions.
+/// Recording is tracked separately from processing so the next dictation can be recorded
+/// (and queued) while the previous one is still being transcribed or cleaned up.
+/// Has no Tauri or audio dependencies so it can be driven directly.
+pub struct Pipeline {
+    tracks: Mutex<Tracks>,
+}
+
+struct Tracks {
+    recording: bool,
+    processing: PipelineState,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self {
+            tracks: Mutex::new(Tracks {
+                recording: false,
+                processing: PipelineState::Idle,
+            }),
+        }
+    }
+
+    /// The state shown to the user: recording takes precedence over background processing
+    pub fn state(&self) -> PipelineState {
+        let tracks = self.tracks.lock().unwrap();
+        if tracks.recording {
+            PipelineState::Recording
+        } else {
+            tracks.processing.clone()
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.tracks.lock().unwrap().recording
+    }
+
+    pub fn start_recording(&self) -> Result<()> {
+        let mut tracks = self.tracks.lock().unwrap();
+        if tracks.recording {
+            anyhow::bail!("Already recording");
+        }
+        tracks.recording = true;
+        Ok(())
+    }
+
+    pub fn stop_recording(&self) -> Result<()> {
+        let mut tracks = self.tracks.lock().unwrap();
+        if !tracks.recording {
+            anyhow::bail!("Not recording");
+        }
+        tracks.recording = false;
+        Ok(())
+    }
+
+    /// Move the processing side to `next`, returning the previous state
+    pub fn transition(&self, next: PipelineState) -> Result<PipelineState> {
+        let mut tracks = self.tracks.lock().unwrap();
+        if next == PipelineState::Recording || !can_transition(&tracks.processing, &next) {
+            anyhow::bail!(
+                "Invalid pipeline transition: {} -> {}",
+                tracks.processing.name(),
+                next.name()
+            );
+        }
+        Ok(std::mem::replace(&mut tracks.processing, next))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PipelineState::*;
+
+    fn error() -> PipelineState {
+        Error("failed".to_string())
+    }
+
+    #[test]
+    fn allows_pipeline_transitions() {
+        for (from, to) in [
+            (Idle, Transcribing),
+            (error(), Transcribing),
+            (Transcribing, Cleaning),
+            (Cleaning, Reviewing),
+            (Reviewing, Cleaning),
+            (Transcribing, Inserting),
+            (Cleaning, Inserting),
+            (Reviewing, Inserting),
+            (Transcribing, Idle),
+            (Inserting, Idle),
+            (Idle, error()),
+            (Inserting, error()),
+            (error(), Idle),
+        ] {
+            assert!(can_transition(&from, &to), "{:?} -> {:?}", from, to);
+        }
+    }
+
+    #[test]
+    fn rejects_pipeline_transitions() {
+        for (from, to) in [
+            (Idle, Cleaning),
+            (Idle, Inserting),
+            (Idle, Idle),
+            (Transcribing, Transcribing),
+            (Transcribing, Reviewing),
+            (Cleaning, Transcribing),
+            (Inserting, Cleaning),
+            (Inserting, Transcribing),
+            (error(), Cleaning),
+        ] {
+            assert!(!can_transition(&from, &to), "{:?} -> {:?}", from, to);
+        }
+    }
+
+    #[test]
+    fn tracks_processing_state() {
+        let pipeline = Pipeline::new();
+        assert_eq!(pipeline.transition(Transcribing).unwrap(), Idle);
+        assert_eq!(pipeline.transition(Cleaning).unwrap(), Transcribing);
+        assert!(pipeline.transition(Transcribing).is_err());
+        assert!(pipeline.transition(Recording).is_err());
+        assert_eq!(pipeline.state(), Cleaning);
+        assert_eq!(pipeline.transition(Inserting).unwrap(), Cleaning);
+        assert_eq!(pipeline.transition(Idle).unwrap(), Inserting);
+    }
+
+    #[test]
+    fn records_while_processing() {
+        let pipeline = Pipeline::new();
+        pipeline.transition(Transcribing).unwrap();
+        pipeline.start_recording().unwrap();
+        assert!(pipeline.is_recording());
+        assert!(pipeline.start_recording().is_err());
+        // Recording is what the user sees; processing carries on underneath
+        assert_eq!(pipeline.state(), Recording);
+        pipeline.transition(Cleaning).unwrap();
+        pipeline.stop_recording().unwrap();
+        assert!(pipeline.stop_recording().is_err());
+        assert_eq!(pipeline.state(), Cleaning);
+    }
+}
@@ -0,0 +1,312 @@
+use anyhow::Result;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crate::db::{Database, PromptTemplate};
+use crate::output_format::OutputFormat;
+
+/// Variables a template can use, as `{{name}}` or as a `{{#name}}...{{/name}}` section
+/// that is only kept when the variable isn't empty
+pub const VARIABLES: &[&str] = &[
+    "raw_text",
+    "custom_words",
+    "app_name",
+    "language",
+    "previous_dictation",
+    "context_prompt",
+    "writing_style",
+    "app_context",
+    "examples",
+    "target_language",
+];
+
+/// Everything after the format's opening in the built-in cleanup prompt
+const DEFAULT_SECTIONS: &str = "\
+{{#context_prompt}}
+Context: {{context_prompt}}
+
+{{/context_prompt}}
+{{#writing_style}}
+Writing style: {{writing_style}}
+
+{{/writing_style}}
+{{#app_name}}
+The text will be inserted into the application \"{{app_name}}\"; format it accordingly.
+
+{{/app_name}}
+{{#app_context}}
+Application context: {{app_context}}
+
+{{/app_context}}
+{{#custom_words}}
+Spell these words exactly as written: {{custom_words}}
+
+{{/custom_words}}
+{{#examples}}
+The user corrected some of your earlier cleanups. Follow the same preferences:
+
+{{examples}}
+
+{{/examples}}
+";
+
+/// Closes the built-in prompt, and is added to templates that don't place it themselves
+const TRANSLATION_SECTION: &str = "\
+{{#target_language}}
+Translate the cleaned text into {{target_language}}. Return ONLY the translation.
+{{/target_language}}
+";
+
+pub fn default_template(format: OutputFormat) -> PromptTemplate {
+    PromptTemplate {
+        format: format.as_str().to_string(),
+        system_prompt: format!(
+            "{}\n\n{}{}",
+            format.system_prompt(),
+            DEFAULT_SECTIONS,
+            TRANSLATION_SECTION
+        ),
+        user_prompt: "{{raw_text}}".to_string(),
+    }
+}
+
+/// The template's system prompt, with the translation instruction appended when neither
+/// part of the template uses {{target_language}}; translations share the Plain template,
+/// which users may have written without them in mind
+pub fn system_prompt(template: &PromptTemplate) -> Cow<'_, str> {
+    let uses_target_language = [&template.system_prompt, &template.user_prompt]
+        .iter()
+        .filter_map(|part| parse(part).ok())
+        .flatten()
+        .any(|token| {
+            matches!(token, Token::Variable(name) | Token::Open(name) if name == "target_language")
+        });
+    if uses_target_language {
+        return Cow::Borrowed(&template.system_prompt);
+    }
+    Cow::Owned(format!(
+        "{}\n\n{}",
+        template.system_prompt.trim_end(),
+        TRANSLATION_SECTION
+    ))
+}
+
+/// The user's template for `format`, or the built-in one
+pub fn load(db: &Database, format: OutputFormat) -> Result<PromptTemplate> {
+    Ok(db
+        .get_prompt_template(format.as_str())?
+        .unwrap_or_else(|| default_template(format)))
+}
+
+/// Check a template before it is saved: known variables, closed sections, and the
+/// dictation somewhere in the prompt
+pub fn validate(template: &PromptTemplate) -> Result<()> {
+    OutputFormat::parse(&template.format)?;
+    if template.system_prompt.trim().is_empty() {
+        anyhow::bail!("The system prompt can't be empty");
+    }
+    if template.user_prompt.trim().is_empty() {
+        anyhow::bail!("The user message can't be empty; it usually is {{{{raw_text}}}}");
+    }
+    let system =
+        parse(&template.system_prompt).map_err(|e| anyhow::anyhow!("System prompt: {}", e))?;
+    let user = parse(&template.user_prompt).map_err(|e| anyhow::anyhow!("User message: {}", e))?;
+    let has_raw_text = system
+        .iter()
+        .chain(&user)
+        .any(|token| *token == Token::Variable("raw_text"));
+    if !has_raw_text {
+        anyhow::bail!(
+            "The template must include {{{{raw_text}}}}, or the model never sees the dictation"
+        );
+    }
+    Ok(())
+}
+
+/// Fill in `template`; variables missing from `values` count as empty
+pub fn render(template: &str, values: &HashMap<&str, String>) -> Result<String> {
+    let mut out = String::with_capacity(template.len());
+    // Sections we're inside of; text only counts while all of them are kept
+    let mut kept: Vec<bool> = Vec::new();
+    for token in parse(template)? {
+        let value = |name: &str| values.get(name).map_or("", String::as_str);
+        match token {
+            Token::Text(text) if kept.iter().all(|k| *k) => out.push_str(text),
+            Token::Variable(name) if kept.iter().all(|k| *k) => out.push_str(value(name)),
+            Token::Open(name) => kept.push(!value(name).trim().is_empty()),
+            Token::Close(_) => {
+                kept.pop();
+            }
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token<'a> {
+    Text(&'a str),
+    Variable(&'a str),
+    Open(&'a str),
+    Close(&'a str),
+}
+
+fn parse(template: &str) -> Result<Vec<Token<'_>>> {
+    let mut tokens = Vec::new();
+    let mut open_sections: Vec<&str> = Vec::new();
+    let mut rest = template;
+    // Whether `rest` starts a line
+    let mut line_begins = true;
+    while let Some(start) = rest.find("{{") {
+        let text = &rest[..start];
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| anyhow::anyhow!("\"{{{{\" without a closing \"}}}}\""))?;
+        let tag = after[..end].trim();
+        let (token, name) = match (tag.strip_prefix('#'), tag.strip_prefix('/')) {
+            (Some(name), _) => (Token::Open(name.trim()), name.trim()),
+            (_, Some(name)) => (Token::Close(name.trim()), name.trim()),
+            _ => (Token::Variable(tag), tag),
+        };
+        if !VARIABLES.contains(&name) {
+            anyhow::bail!(
+                "Unknown variable \"{}\"; available: {}",
+                name,
+                VARIABLES.join(", ")
+            );
+        }
+        let mut rest_after = &after[end + 2..];
+        let mut text = text;
+        let mut standalone = false;
+        if matches!(token, Token::Open(_) | Token::Close(_)) {
+            // A section tag alone on its line takes the line with it
+            let line_start = text.rfind('\n').map_or(0, |i| i + 1);
+            let at_line_start =
+                text[line_start..].trim().is_empty() && (line_start > 0 || line_begins);
+            let line_end = rest_after.find('\n');
+            let at_line_end = rest_after[..line_end.unwrap_or(rest_after.len())]
+                .trim()
+                .is_empty();
+            if at_line_start && at_line_end {
+                text = &text[..line_start];
+                rest_after = &rest_after[line_end.map_or(rest_after.len(), |i| i + 1)..];
+                standalone = true;
+            }
+        }
+        line_begins = standalone;
+        match token {
+            Token::Open(name) => open_sections.push(name),
+            Token::Close(name) => match open_sections.pop() {
+                Some(open) if open == name => {}
+                Some(open) => anyhow::bail!(
+                    "{{{{/{}}}}} closes {{{{#{}}}}}; sections must be closed in order",
+                    name,
+                    open
+                ),
+                None => anyhow::bail!("{{{{/{}}}}} has no matching {{{{#{}}}}}", name, name),
+            },
+            _ => {}
+        }
+        if !text.is_empty() {
+            tokens.push(Token::Text(text));
+        }
+        tokens.push(token);
+        rest = rest_after;
+    }
+    if let Some(open) = open_sections.pop() {
+        anyhow::bail!("{{{{#{}}}}} is never closed with {{{{/{}}}}}", open, open);
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest));
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(system_prompt: &str, user_prompt: &str) -> PromptTemplate {
+        PromptTemplate {
+            format: "plain".to_string(),
+            system_prompt: system_prompt.to_string(),
+            user_prompt: user_prompt.to_string(),
+        }
+    }
+
+    fn error(system_prompt: &str, user_prompt: &str) -> String {
+        validate(&template(system_prompt, user_prompt))
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn rejects_unknown_variables() {
+        let e = error("Clean up {{rawtext}}", "{{raw_text}}");
+        assert!(e.contains("Unknown variable \"rawtext\""), "{}", e);
+        let e = error("Clean up.", "{{#mood}}{{/mood}}{{raw_text}}");
+        assert!(e.contains("Unknown variable \"mood\""), "{}", e);
+    }
+
+    #[test]
+    fn rejects_broken_sections() {
+        let cases = [
+            ("{{#app_name}}In {{app_name}}", "never closed"),
+            ("{{/app_name}}", "has no matching"),
+            (
+                "{{#app_name}}{{#language}}{{/app_name}}{{/language}}",
+                "must be closed in order",
+            ),
+            ("{{app_name", "without a closing"),
+        ];
+        for (system_prompt, expected) in cases {
+            let e = error(system_prompt, "{{raw_text}}");
+            assert!(e.contains(expected), "{:?}: {}", system_prompt, e);
+        }
+    }
+
+    #[test]
+    fn requires_raw_text() {
+        let e = error("Clean up the text.", "Here it is");
+        assert!(e.contains("must include {{raw_text}}"), "{}", e);
+        // A section named after it doesn't show the dictation
+        let e = error("Clean up.", "{{#raw_text}}Text{{/raw_text}}");
+        assert!(e.contains("must include {{raw_text}}"), "{}", e);
+        assert!(validate(&template("{{raw_text}}", "Clean this up.")).is_ok());
+    }
+
+    #[test]
+    fn default_templates_are_valid() {
+        for format in [
+            OutputFormat::Plain,
+            OutputFormat::Markdown,
+            OutputFormat::Code,
+        ] {
+            validate(&default_template(format)).unwrap();
+        }
+    }
+
+    #[test]
+    fn standalone_section_lines_leave_no_blank_lines() {
+        let template = "Start\n{{#app_name}}\nApp: {{app_name}}\n{{/app_name}}\nEnd";
+        let values = HashMap::from([("app_name", "Slack".to_string())]);
+        assert_eq!(render(template, &values).unwrap(), "Start\nApp: Slack\nEnd");
+        assert_eq!(render(template, &HashMap::new()).unwrap(), "Start\nEnd");
+        // Indented tags are still alone on their line
+        let template = "Start\n  {{#app_name}}  \nApp\n  {{/app_name}}\nEnd";
+        assert_eq!(render(template, &values).unwrap(), "Start\nApp\nEnd");
+    }
+
+    #[test]
+    fn removes_empty_sections() {
+        let template = "A{{#language}} in {{language}}{{/language}}.";
+        let values = HashMap::from([("language", "  ".to_string())]);
+        assert_eq!(render(template, &values).unwrap(), "A.");
+        let values = HashMap::from([("language", "German".to_string())]);
+        assert_eq!(render(template, &values).unwrap(), "A in German.");
+        // A kept section inside a removed one stays removed
+        let template = "{{#app_name}}[{{#language}}{{language}}{{/language}}]{{/app_name}}";
+        assert_eq!(render(template, &values).unwrap(), "");
+    }
+}
@@ -0,0 +1,48 @@
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{DictationContext, DictationResult};
+
+/// A finished recording waiting to be transcribed, cleaned up and pasted
+pub struct DictationJob {
+    pub wav_data: Vec<u8>,
+    pub duration_ms: u64,
+    pub context: DictationContext,
+    /// Set when a caller (e.g. the `transcribe_last` command) wants the outcome back
+    pub reply: Option<oneshot::Sender<Result<DictationResult, String>>>,
+}
+
+/// Dictations are processed one at a time in the order they were recorded,
+/// so releasing the shortcut again while the previous dictation is still processing queues it.
+pub struct DictationQueue {
+    sender: mpsc::UnboundedSender<DictationJob>,
+}
+
+impl DictationQueue {
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<DictationJob>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (Self { sender }, receiver)
+    }
+
+    pub fn push(&self, job: DictationJob) -> Result<(), String> {
+        self.sender
+            .send(job)
+            .map_err(|_| "Dictation queue is not running".to_string())
+    }
+}
+
+/// Runs for the lifetime of the app, independently of whether any window is open
+pub async fn run_worker(
+    app: tauri::AppHandle,
+    mut receiver: mpsc::UnboundedReceiver<DictationJob>,
+) {
+    while let Some(job) = receiver.recv().await {
+        let result =
+            crate::process_dictation(&app, job.wav_data, job.duration_ms, job.context).await;
+        if let Err(e) = &result {
+            eprintln!("Dictation failed: {}", e);
+        }
+        if let Some(reply) = job.reply {
+            let _ = reply.send(result);
+        }
+    }
+}
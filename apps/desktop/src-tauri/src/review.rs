@@ -0,0 +1,148 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager, PhysicalPosition, WebviewUrl, WebviewWindowBuilder};
+use tokio::sync::oneshot;
+
+use crate::db::Database;
+
+const REVIEW_WINDOW: &str = "review";
+const WINDOW_WIDTH: f64 = 440.0;
+const WINDOW_HEIGHT: f64 = 280.0;
+/// Gap between the cursor and the overlay's top-left corner
+const CURSOR_OFFSET: i32 = 16;
+
+/// What the review overlay shows; sent with the `review-requested` event
+#[derive(Serialize, Clone, Debug)]
+pub struct ReviewRequest {
+    pub raw_text: String,
+    /// Cleaned (or translated) text, or the raw text when cleanup failed
+    pub cleaned_text: String,
+    /// Style the cleaned text was produced with, as a starting point for re-runs
+    pub writing_style: String,
+    /// False without a cleanup provider, when a re-run would return the same text
+    pub can_rerun: bool,
+}
+
+/// The user's answer from the overlay
+#[derive(Debug)]
+pub enum ReviewDecision {
+    Accept(String),
+    Rerun { writing_style: String },
+    Discard,
+}
+
+struct PendingReview {
+    request: ReviewRequest,
+    reply: oneshot::Sender<ReviewDecision>,
+}
+
+/// The review currently waiting for the user; at most one, since dictations run one at a time
+pub struct ReviewState {
+    pending: Mutex<Option<PendingReview>>,
+}
+
+impl ReviewState {
+    pub fn new() -> Self {
+        Self {
+            pending: Mutex::new(None),
+        }
+    }
+
+    pub fn pending(&self) -> Option<ReviewRequest> {
+        self.pending
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|p| p.request.clone())
+    }
+
+    pub fn decide(&self, decision: ReviewDecision) -> Result<()> {
+        let pending = self
+            .pending
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("No dictation is waiting for review"))?;
+        pending
+            .reply
+            .send(decision)
+            .map_err(|_| anyhow::anyhow!("The dictation is no longer waiting for review"))
+    }
+
+    /// Drop the pending review, e.g. when the dictation was cancelled
+    pub fn clear(&self) {
+        self.pending.lock().unwrap().take();
+    }
+}
+
+/// Setting `review_before_insert`: "true" shows the overlay before every insertion
+pub fn is_enabled(db: &Database) -> bool {
+    db.get_setting("review_before_insert")
+        .ok()
+        .flatten()
+        .is_some_and(|v| v == "true")
+}
+
+/// Show the overlay and wait for the user's decision
+pub async fn request_review(
+    app: &tauri::AppHandle,
+    request: ReviewRequest,
+) -> Result<ReviewDecision> {
+    let (reply, decision) = oneshot::channel();
+    *app.state::<ReviewState>().pending.lock().unwrap() = Some(PendingReview {
+        request: request.clone(),
+        reply,
+    });
+    show_window(app)?;
+    // An overlay that is already open picks the request up from the event; a new one
+    // fetches it with `get_pending_review` once it has loaded
+    let _ = app.emit("review-requested", request);
+    decision
+        .await
+        .map_err(|_| anyhow::anyhow!("Review was abandoned"))
+}
+
+fn show_window(app: &tauri::AppHandle) -> Result<()> {
+    let window = match app.get_webview_window(REVIEW_WINDOW) {
+        Some(window) => window,
+        None => WebviewWindowBuilder::new(app, REVIEW_WINDOW, WebviewUrl::App("review".into()))
+            .title("Review Dictation")
+            .inner_size(WINDOW_WIDTH, WINDOW_HEIGHT)
+            .always_on_top(true)
+            .decorations(false)
+            .resizable(false)
+            .skip_taskbar(true)
+            .visible(false)
+            .build()?,
+    };
+
+    if let Ok(cursor) = app.cursor_position() {
+        let (mut x, mut y) = (
+            cursor.x as i32 + CURSOR_OFFSET,
+            cursor.y as i32 + CURSOR_OFFSET,
+        );
+        // Keep the overlay on the cursor's monitor rather than hanging off its edge
+        if let Ok(Some(monitor)) = app.monitor_from_point(cursor.x, cursor.y) {
+            let origin = monitor.position();
+            let size = monitor.size();
+            let scale = monitor.scale_factor();
+            let width = (WINDOW_WIDTH * scale) as i32;
+            let height = (WINDOW_HEIGHT * scale) as i32;
+            x = x.min(origin.x + size.width as i32 - width).max(origin.x);
+            y = y.min(origin.y + size.height as i32 - height).max(origin.y);
+        }
+        let _ = window.set_position(PhysicalPosition::new(x, y));
+    }
+
+    window.show()?;
+    window.set_focus()?;
+    Ok(())
+}
+
+/// Hide the overlay so focus goes back to the app the text is meant for
+pub fn hide_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window(REVIEW_WINDOW) {
+        let _ = window.hide();
+    }
+}
@@ -0,0 +1,384 @@
+use anyhow::Result;
+
+use crate::db::Database;
+use crate::voice_edit::match_len_ignore_case;
+
+/// When the deterministic cleanup runs, relative to the LLM
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RuleMode {
+    /// Never; without the LLM the raw transcript is inserted
+    Off,
+    /// When the LLM fails or its reply is discarded
+    Fallback,
+    /// Before the LLM, which then gets the tidied text
+    PrePass,
+    /// Instead of the LLM
+    Only,
+}
+
+impl RuleMode {
+    /// Setting `rule_cleanup`: "off", "fallback" (default), "pre_pass" or "only"
+    pub fn load(db: &Database) -> Result<Self> {
+        match db.get_setting("rule_cleanup")? {
+            Some(value) => Self::parse(&value),
+            None => Ok(Self::Fallback),
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self> {
+        Ok(match value {
+            "off" => Self::Off,
+            "" | "fallback" => Self::Fallback,
+            "pre_pass" => Self::PrePass,
+            "only" => Self::Only,
+            other => anyhow::bail!("Unknown rule cleanup mode: {}", other),
+        })
+    }
+}
+
+/// Punctuation that belongs to the word before it
+const TRAILING_PUNCTUATION: &[char] = &[',', '.', ';', ':', '!', '?', '…'];
+/// Punctuation French sets off with a narrow no-break space
+const FRENCH_SPACED: &[char] = &[';', ':', '!', '?'];
+/// Words said twice on purpose ("I know that that works", "bye bye")
+const INTENDED_REPEATS: &[&str] = &[
+    "that", "had", "bye", "no", "so", "ha", "very", "la", "na", "blah",
+];
+/// A word said this many times in a row is meant ("la la la"), not a stutter
+const INTENDED_RUN: usize = 3;
+/// Abbreviations whose period doesn't end a sentence
+const ABBREVIATIONS: &[&str] = &[
+    "e.g.", "i.e.", "etc.", "vs.", "mr.", "mrs.", "ms.", "dr.", "a.m.", "p.m.",
+];
+/// Longest partial word counted as a stutter ("th- the")
+const MAX_STUTTER_LEN: usize = 3;
+
+/// Deterministic cleanup: filler words, stutters, capitalization, spacing, and the
+/// profile's custom-word spellings. Line breaks from spoken commands are kept.
+pub struct RuleCleaner<'a> {
+    language: String,
+    custom_words: &'a [String],
+}
+
+impl<'a> RuleCleaner<'a> {
+    /// `language` is a code such as "de" or "fr-CA"; English rules apply when unknown
+    pub fn new(language: Option<&str>, custom_words: &'a [String]) -> Self {
+        let language = language
+            .and_then(|l| l.split(['-', '_']).next())
+            .map(|l| l.to_lowercase())
+            .unwrap_or_else(|| "en".to_string());
+        Self {
+            language,
+            custom_words,
+        }
+    }
+
+    pub fn clean(&self, text: &str) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        for line in text.lines() {
+            let line = self.clean_line(line);
+            // At most one blank line in a row
+            if line.is_empty() && lines.last().is_none_or(|l| l.is_empty()) {
+                continue;
+            }
+            lines.push(line);
+        }
+        while lines.last().is_some_and(|l| l.is_empty()) {
+            lines.pop();
+        }
+        let mut text = lines.join("\n");
+        // Longest first, so "Visual Studio Code" wins over "Code"
+        let mut words: Vec<&String> = self.custom_words.iter().collect();
+        words.sort_by_key(|w| std::cmp::Reverse(w.len()));
+        for word in words {
+            text = apply_casing(&text, word.trim());
+        }
+        text
+    }
+
+    fn clean_line(&self, line: &str) -> String {
+        let line = space_after_punctuation(line);
+        let mut tokens: Vec<String> = Vec::new();
+        for token in line.split([' ', '\t']).filter(|t| !t.is_empty()) {
+            match tokens.last_mut() {
+                // "hello ," → "hello,"
+                Some(prev) if token.chars().all(|c| TRAILING_PUNCTUATION.contains(&c)) => {
+                    self.attach(prev, token)
+                }
+                _ => tokens.push(token.to_string()),
+            }
+        }
+        self.remove_fillers(&mut tokens);
+        remove_stutters(&mut tokens);
+        self.capitalize(&mut tokens);
+        tokens.join(" ")
+    }
+
+    /// Join punctuation to the word before it, replacing a weaker mark already there
+    fn attach(&self, prev: &mut String, punctuation: &str) {
+        if prev.ends_with(['.', '!', '?', '…']) {
+            return;
+        }
+        if punctuation.ends_with(['.', '!', '?']) {
+            let trimmed = prev.trim_end_matches([',', ';', ':']).len();
+            prev.truncate(trimmed);
+        } else if prev.ends_with(TRAILING_PUNCTUATION) {
+            return;
+        }
+        if self.language == "fr" && punctuation.starts_with(FRENCH_SPACED) {
+            prev.push('\u{202f}');
+        }
+        prev.push_str(punctuation);
+    }
+
+    fn fillers(&self) -> &'static [&'static str] {
+        match self.language.as_str() {
+            "de" => &["äh", "ähm", "öh", "öhm", "hm", "hmm"],
+            "fr" => &["euh", "heu", "hum", "bah"],
+            "es" => &["eh", "em", "mmm", "ehm"],
+            _ => &["um", "umm", "uh", "uhm", "erm", "er", "ah", "hmm", "mm"],
+        }
+    }
+
+    /// Drop filler words; a sentence end they carried moves to the word before them
+    fn remove_fillers(&self, tokens: &mut Vec<String>) {
+        let fillers = self.fillers();
+        let mut i = 0;
+        while i < tokens.len() {
+            if !fillers.contains(&core(&tokens[i]).as_str()) {
+                i += 1;
+                continue;
+            }
+            let filler = tokens.remove(i);
+            let end = filler.trim_start_matches(|c: char| !TRAILING_PUNCTUATION.contains(&c));
+            if i > 0 && end.ends_with(['.', '!', '?']) {
+                let end = end.to_string();
+                self.attach(&mut tokens[i - 1], &end);
+            }
+        }
+    }
+
+    fn capitalize(&self, tokens: &mut [String]) {
+        let english = self.language == "en";
+        let mut sentence_start = true;
+        for token in tokens.iter_mut() {
+            // A bullet marker from spoken commands doesn't start the sentence itself
+            if token == "-" {
+                continue;
+            }
+            if sentence_start || (english && is_english_i(token)) {
+                capitalize_first(token);
+            }
+            let bare = token.trim_end_matches(['"', '\'', ')', '»', '”', '\u{202f}']);
+            sentence_start = bare.ends_with(['.', '!', '?'])
+                && !bare.ends_with("..")
+                && !(english && ABBREVIATIONS.contains(&bare.to_lowercase().as_str()));
+        }
+    }
+}
+
+/// "hello,world" → "hello, world"; periods and colons are left alone for URLs and times
+fn space_after_punctuation(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        out.push(c);
+        if matches!(c, ',' | ';' | '!' | '?') && chars.peek().is_some_and(|n| n.is_alphabetic()) {
+            out.push(' ');
+        }
+    }
+    out
+}
+
+/// "I I think" → "I think", "th- the" → "the"
+fn remove_stutters(tokens: &mut Vec<String>) {
+    let mut i = 0;
+    while i + 1 < tokens.len() {
+        let (word, next) = (core(&tokens[i]), core(&tokens[i + 1]));
+        let partial = tokens[i].ends_with('-')
+            && !word.is_empty()
+            && word.chars().count() <= MAX_STUTTER_LEN
+            && next.starts_with(&word);
+        let run = tokens[i..].iter().take_while(|t| core(t) == word).count();
+        let repeated = !word.is_empty()
+            && word == next
+            && word.chars().all(char::is_alphabetic)
+            && tokens[i].ends_with(char::is_alphabetic)
+            && !INTENDED_REPEATS.contains(&word.as_str());
+        if repeated && run >= INTENDED_RUN {
+            i += run;
+            continue;
+        }
+        if partial {
+            // Keep an opening quote or bracket the partial word carried
+            let lead: String = tokens[i]
+                .chars()
+                .take_while(|c| !c.is_alphanumeric())
+                .collect();
+            tokens.remove(i);
+            tokens[i].insert_str(0, &lead);
+        } else if repeated {
+            // Keep the first copy's casing and the second copy's punctuation
+            let removed = tokens.remove(i + 1);
+            let tail = removed.trim_start_matches(|c: char| !TRAILING_PUNCTUATION.contains(&c));
+            tokens[i].push_str(tail);
+        } else {
+            i += 1;
+        }
+    }
+}
+
+/// Lower-cased word without surrounding punctuation
+fn core(token: &str) -> String {
+    token
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase()
+}
+
+/// "i", "i'm", "i'll", ...
+fn is_english_i(token: &str) -> bool {
+    let word = token.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'');
+    word == "i" || word.starts_with("i'") || word.starts_with("i’")
+}
+
+/// Upper-case the first letter, unless the word already has deliberate casing ("iPhone")
+/// or is code ("user_id", "self.name")
+fn capitalize_first(token: &mut String) {
+    let Some((index, first)) = token.char_indices().find(|(_, c)| c.is_alphanumeric()) else {
+        return;
+    };
+    let rest = &token[index + first.len_utf8()..];
+    let is_code = rest
+        .trim_end_matches(TRAILING_PUNCTUATION)
+        .contains(['_', '(', '[', '.']);
+    if !first.is_lowercase() || rest.chars().any(char::is_uppercase) || is_code {
+        return;
+    }
+    let upper: String = first.to_uppercase().collect();
+    token.replace_range(index..index + first.len_utf8(), &upper);
+}
+
+/// Replace whole-word, case-insensitive occurrences of `word` with its exact spelling
+fn apply_casing(text: &str, word: &str) -> String {
+    if word.is_empty() {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut prev: Option<char> = None;
+    while let Some(c) = rest.chars().next() {
+        let at_boundary = prev.is_none_or(|p| !p.is_alphanumeric());
+        if let Some(len) = at_boundary
+            .then(|| match_len_ignore_case(rest, word))
+            .flatten()
+            .filter(|&len| !rest[len..].starts_with(char::is_alphanumeric))
+        {
+            out.push_str(word);
+            prev = word.chars().last();
+            rest = &rest[len..];
+        } else {
+            out.push(c);
+            prev = Some(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clean(language: &str, custom_words: &[&str], text: &str) -> String {
+        let custom_words: Vec<String> = custom_words.iter().map(|w| w.to_string()).collect();
+        RuleCleaner::new(Some(language), &custom_words).clean(text)
+    }
+
+    #[test]
+    fn cleans_english() {
+        let cases = [
+            // Fillers
+            ("um so we ship on friday", "So we ship on friday"),
+            ("we ship uh on friday um.", "We ship on friday."),
+            ("hmm", ""),
+            // Stutters
+            ("I I think so", "I think so"),
+            ("th- the plan works", "The plan works"),
+            ("it is is fine, fine.", "It is fine, fine."),
+            ("the the report", "The report"),
+            // Intended repeats
+            ("I know that that works", "I know that that works"),
+            ("bye bye", "Bye bye"),
+            ("they sang la la la", "They sang la la la"),
+            ("it went on and on and on", "It went on and on and on"),
+            ("no no no not that one", "No no no not that one"),
+            // Capitalization and abbreviations
+            ("hello. how are you? fine!", "Hello. How are you? Fine!"),
+            ("bring fruit, e.g. apples", "Bring fruit, e.g. apples"),
+            ("see dr. smith", "See dr. smith"),
+            ("3:30 p.m. tomorrow", "3:30 p.m. tomorrow"),
+            ("we met at 9 a.m. yesterday", "We met at 9 a.m. yesterday"),
+            ("i think i'm late", "I think I'm late"),
+            ("my iPhone is new", "My iPhone is new"),
+            ("call user_id. then stop", "Call user_id. Then stop"),
+            // Spacing
+            ("hello , world", "Hello, world"),
+            ("yes,really", "Yes, really"),
+            ("visit example.com at 10:30", "Visit example.com at 10:30"),
+            // Multi-line input
+            ("first line\nsecond line", "First line\nSecond line"),
+            ("one\n\n\n\ntwo\n\n", "One\n\nTwo"),
+            ("- um milk\n- eggs", "- Milk\n- Eggs"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(clean("en", &[], input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn applies_custom_word_casing() {
+        let words = ["GitHub", "Visual Studio Code", "Code", "K8s"];
+        let cases = [
+            ("push it to github", "Push it to GitHub"),
+            ("open visual studio code", "Open Visual Studio Code"),
+            ("the code is on GITHUB.", "The Code is on GitHub."),
+            ("k8s clusters", "K8s clusters"),
+            // Only whole words
+            ("githubber and codes", "Githubber and codes"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(clean("en", &words, input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn cleans_other_languages() {
+        let cases = [
+            ("de", "äh ich komme morgen", "Ich komme morgen"),
+            ("de", "ähm ja. das passt", "Ja. Das passt"),
+            ("fr", "euh bonjour !", "Bonjour\u{202f}!"),
+            ("fr", "vraiment ? oui", "Vraiment\u{202f}? Oui"),
+            ("fr-CA", "quoi : rien", "Quoi\u{202f}: rien"),
+            ("es", "eh hola amigo", "Hola amigo"),
+            // English "i" is only capitalized in English
+            ("de", "ja i bin da", "Ja i bin da"),
+            ("fr", "la i grec", "La i grec"),
+        ];
+        for (language, input, expected) in cases {
+            assert_eq!(
+                clean(language, &[], input),
+                expected,
+                "{}: {:?}",
+                language,
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn parses_modes() {
+        assert_eq!(RuleMode::parse("").unwrap(), RuleMode::Fallback);
+        assert_eq!(RuleMode::parse("pre_pass").unwrap(), RuleMode::PrePass);
+        assert!(RuleMode::parse("sometimes").is_err());
+    }
+}
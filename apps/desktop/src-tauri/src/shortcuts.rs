@@ -0,0 +1,506 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::db::Database;
+use crate::pipeline::Pipeline;
+use crate::DictationMode;
+
+pub const DEFAULT_DICTATION_HOTKEY: &str = "CmdOrCtrl+Shift+Space";
+pub const DEFAULT_TRANSLATION_HOTKEY: &str = "CmdOrCtrl+Alt+Space";
+pub const DEFAULT_COMMAND_HOTKEY: &str = "CmdOrCtrl+Alt+Shift+Space";
+
+/// A press shorter than this is a tap rather than hold-to-talk
+const TAP_MAX: Duration = Duration::from_millis(250);
+/// The second tap of a double tap must start within this window after the first is released
+const DOUBLE_TAP_WINDOW: Duration = Duration::from_millis(350);
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutMode {
+    /// Record while the keys are held
+    PushToTalk,
+    /// Tap to start, tap again to stop
+    Toggle,
+    /// Hold to talk, or double-tap to keep recording until the next tap
+    DoubleTapLock,
+}
+
+/// One global shortcut, stored as JSON in the `shortcuts` setting
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ShortcutBinding {
+    pub accelerator: String,
+    pub mode: ShortcutMode,
+    #[serde(default)]
+    pub action: DictationMode,
+    /// Profile used for dictations started by this shortcut; None uses the default profile
+    #[serde(default)]
+    pub profile_id: Option<i64>,
+}
+
+#[derive(Default)]
+struct TapState {
+    pressed_at: Option<Instant>,
+    released_at: Option<Instant>,
+    locked: bool,
+    /// Bumped on every press so a pending single-tap timeout can tell it is stale
+    generation: u64,
+}
+
+/// What a press or release of a double-tap-to-lock binding does
+#[derive(Debug, PartialEq, Eq)]
+enum TapAction {
+    Start,
+    Stop,
+    Nothing,
+    /// A short tap: the recording is dropped unless another press comes before the
+    /// timeout for this generation
+    AwaitSecondTap(u64),
+}
+
+impl TapState {
+    fn press(&mut self, recording: bool, now: Instant) -> TapAction {
+        self.generation += 1;
+        if self.locked {
+            self.locked = false;
+            return TapAction::Stop;
+        }
+        let second_tap = self
+            .released_at
+            .take()
+            .is_some_and(|t| now.duration_since(t) <= DOUBLE_TAP_WINDOW);
+        if recording {
+            self.locked = second_tap;
+            return TapAction::Nothing;
+        }
+        self.pressed_at = Some(now);
+        TapAction::Start
+    }
+
+    fn release(&mut self, now: Instant) -> TapAction {
+        if self.locked {
+            return TapAction::Nothing;
+        }
+        let Some(pressed_at) = self.pressed_at.take() else {
+            return TapAction::Nothing;
+        };
+        if now.duration_since(pressed_at) >= TAP_MAX {
+            return TapAction::Stop;
+        }
+        self.released_at = Some(now);
+        TapAction::AwaitSecondTap(self.generation)
+    }
+}
+
+/// Shortcuts currently registered with the OS, plus tap tracking for double-tap-to-lock
+pub struct ShortcutRegistry {
+    bindings: Mutex<Vec<(Shortcut, ShortcutBinding)>>,
+    /// Saved bindings that couldn't be registered at startup, and why; kept so saving the
+    /// settings doesn't drop them
+    skipped: Mutex<Vec<(ShortcutBinding, String)>>,
+    tap: Mutex<TapState>,
+    /// The shortcut that started the current recording
+    started_by: Mutex<Option<Shortcut>>,
+}
+
+impl ShortcutRegistry {
+    pub fn new() -> Self {
+        Self {
+            bindings: Mutex::new(Vec::new()),
+            skipped: Mutex::new(Vec::new()),
+            tap: Mutex::new(TapState::default()),
+            started_by: Mutex::new(None),
+        }
+    }
+
+    pub fn bindings(&self) -> Vec<ShortcutBinding> {
+        let skipped = self.skipped.lock().unwrap();
+        self.bindings
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, b)| b.clone())
+            .chain(skipped.iter().map(|(b, _)| b.clone()))
+            .collect()
+    }
+
+    /// Why the bindings skipped at startup aren't active
+    pub fn problems(&self) -> Vec<String> {
+        self.skipped
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, problem)| problem.clone())
+            .collect()
+    }
+
+    /// Replace the registered shortcuts. `reserved` are shortcuts the app registers itself
+    /// (e.g. the cancel key). On any failure the previous shortcuts are restored.
+    pub fn apply(
+        &self,
+        app: &tauri::AppHandle,
+        bindings: Vec<ShortcutBinding>,
+        reserved: &[Shortcut],
+    ) -> Result<()> {
+        let parsed = parse_bindings(bindings, reserved)?;
+        let global_shortcut = app.global_shortcut();
+        let mut current = self.bindings.lock().unwrap();
+
+        for (shortcut, _) in current.iter() {
+            let _ = global_shortcut.unregister(*shortcut);
+        }
+
+        let mut registered = Vec::new();
+        for (shortcut, binding) in &parsed {
+            if let Err(e) = global_shortcut.register(*shortcut) {
+                for done in registered {
+                    let _ = global_shortcut.unregister(done);
+                }
+                for (shortcut, _) in current.iter() {
+                    let _ = global_shortcut.register(*shortcut);
+                }
+                anyhow::bail!(
+                    "{} is already in use by another application: {}",
+                    binding.accelerator,
+                    e
+                );
+            }
+            registered.push(*shortcut);
+        }
+
+        *current = parsed;
+        self.skipped.lock().unwrap().clear();
+        *self.tap.lock().unwrap() = TapState::default();
+        self.started_by.lock().unwrap().take();
+        Ok(())
+    }
+
+    /// Register the saved shortcuts at startup. Unlike `apply`, a binding that is invalid,
+    /// duplicated or taken by another application is skipped so the others still work;
+    /// `problems` then says which were skipped and why.
+    pub fn apply_available(
+        &self,
+        app: &tauri::AppHandle,
+        bindings: Vec<ShortcutBinding>,
+        reserved: &[Shortcut],
+    ) {
+        let global_shortcut = app.global_shortcut();
+        let mut current = self.bindings.lock().unwrap();
+        let mut skipped = self.skipped.lock().unwrap();
+        for binding in bindings {
+            let registered = parse_binding(&binding, reserved, &current).and_then(|shortcut| {
+                global_shortcut.register(shortcut).map_err(|e| {
+                    anyhow::anyhow!(
+                        "{} is already in use by another application: {}",
+                        binding.accelerator,
+                        e
+                    )
+                })?;
+                Ok(shortcut)
+            });
+            match registered {
+                Ok(shortcut) => current.push((shortcut, binding)),
+                Err(e) => {
+                    eprintln!("Skipped shortcut: {}", e);
+                    skipped.push((binding, e.to_string()));
+                }
+            }
+        }
+    }
+
+    pub fn binding_for(&self, shortcut: &Shortcut) -> Option<ShortcutBinding> {
+        self.bindings
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(s, _)| s == shortcut)
+            .map(|(_, b)| b.clone())
+    }
+}
+
+/// Saved bindings, or defaults built from the older single-hotkey settings
+pub fn load_bindings(db: &Database) -> Result<Vec<ShortcutBinding>> {
+    if let Some(json) = db.get_setting("shortcuts")? {
+        return Ok(serde_json::from_str(&json)?);
+    }
+
+    let legacy = |key: &str, default: &str| -> Result<String> {
+        Ok(db
+            .get_setting(key)?
+            .filter(|v| !v.trim().is_empty())
+            .unwrap_or_else(|| default.to_string()))
+    };
+    Ok(vec![
+        push_to_talk(
+            &legacy("hotkey", DEFAULT_DICTATION_HOTKEY)?,
+            DictationMode::Dictate,
+        ),
+        push_to_talk(
+            &legacy("translation_hotkey", DEFAULT_TRANSLATION_HOTKEY)?,
+            DictationMode::Translate,
+        ),
+        push_to_talk(DEFAULT_COMMAND_HOTKEY, DictationMode::Command),
+    ])
+}
+
+pub fn default_bindings() -> Vec<ShortcutBinding> {
+    vec![
+        push_to_talk(DEFAULT_DICTATION_HOTKEY, DictationMode::Dictate),
+        push_to_talk(DEFAULT_TRANSLATION_HOTKEY, DictationMode::Translate),
+        push_to_talk(DEFAULT_COMMAND_HOTKEY, DictationMode::Command),
+    ]
+}
+
+fn push_to_talk(accelerator: &str, action: DictationMode) -> ShortcutBinding {
+    ShortcutBinding {
+        accelerator: accelerator.to_string(),
+        mode: ShortcutMode::PushToTalk,
+        action,
+        profile_id: None,
+    }
+}
+
+/// Parse accelerators and reject duplicates. Comparing parsed shortcuts catches
+/// spellings that differ but resolve to the same keys (e.g. CmdOrCtrl vs Ctrl on Linux).
+fn parse_bindings(
+    bindings: Vec<ShortcutBinding>,
+    reserved: &[Shortcut],
+) -> Result<Vec<(Shortcut, ShortcutBinding)>> {
+    let mut parsed: Vec<(Shortcut, ShortcutBinding)> = Vec::new();
+    for binding in bindings {
+        let shortcut = parse_binding(&binding, reserved, &parsed)?;
+        parsed.push((shortcut, binding));
+    }
+    Ok(parsed)
+}
+
+/// Parse one accelerator, rejecting reserved shortcuts and ones already in `taken`
+fn parse_binding(
+    binding: &ShortcutBinding,
+    reserved: &[Shortcut],
+    taken: &[(Shortcut, ShortcutBinding)],
+) -> Result<Shortcut> {
+    let shortcut: Shortcut = binding
+        .accelerator
+        .parse()
+        .map_err(|e| anyhow::anyhow!("Invalid shortcut {}: {}", binding.accelerator, e))?;
+    if reserved.contains(&shortcut) {
+        anyhow::bail!("{} is reserved by Parrot", binding.accelerator);
+    }
+    if let Some((_, existing)) = taken.iter().find(|(s, _)| *s == shortcut) {
+        anyhow::bail!(
+            "{} conflicts with {}",
+            binding.accelerator,
+            existing.accelerator
+        );
+    }
+    Ok(shortcut)
+}
+
+/// Global shortcut handler for user bindings
+pub fn handle_shortcut(app: &tauri::AppHandle, shortcut: &Shortcut, state: ShortcutState) {
+    let registry = app.state::<ShortcutRegistry>();
+    let Some(binding) = registry.binding_for(shortcut) else {
+        return;
+    };
+
+    let result = match binding.mode {
+        ShortcutMode::PushToTalk => match state {
+            ShortcutState::Pressed => start_if_idle(app, shortcut, &binding),
+            ShortcutState::Released => stop_if_started_by(app, shortcut),
+        },
+        ShortcutMode::Toggle => match state {
+            ShortcutState::Pressed if app.state::<Pipeline>().is_recording() => {
+                crate::finish_and_queue_recording(app)
+            }
+            ShortcutState::Pressed => begin(app, shortcut, &binding),
+            ShortcutState::Released => Ok(()),
+        },
+        ShortcutMode::DoubleTapLock => match state {
+            ShortcutState::Pressed => double_tap_pressed(app, shortcut, &binding),
+            ShortcutState::Released => double_tap_released(app),
+        },
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+    }
+}
+
+fn begin(
+    app: &tauri::AppHandle,
+    shortcut: &Shortcut,
+    binding: &ShortcutBinding,
+) -> Result<(), String> {
+    crate::begin_recording(app, binding.action, binding.profile_id)?;
+    *app.state::<ShortcutRegistry>().started_by.lock().unwrap() = Some(*shortcut);
+    Ok(())
+}
+
+fn start_if_idle(
+    app: &tauri::AppHandle,
+    shortcut: &Shortcut,
+    binding: &ShortcutBinding,
+) -> Result<(), String> {
+    if app.state::<Pipeline>().is_recording() {
+        return Ok(());
+    }
+    begin(app, shortcut, binding)
+}
+
+/// Releasing a push-to-talk binding only ends a recording that binding started
+fn stop_if_started_by(app: &tauri::AppHandle, shortcut: &Shortcut) -> Result<(), String> {
+    let registry = app.state::<ShortcutRegistry>();
+    let started_by = registry
+        .started_by
+        .lock()
+        .unwrap()
+        .take_if(|s| *s == *shortcut);
+    if started_by.is_none() {
+        return Ok(());
+    }
+    stop_if_recording(app)
+}
+
+fn stop_if_recording(app: &tauri::AppHandle) -> Result<(), String> {
+    if !app.state::<Pipeline>().is_recording() {
+        return Ok(());
+    }
+    crate::finish_and_queue_recording(app)
+}
+
+fn double_tap_pressed(
+    app: &tauri::AppHandle,
+    shortcut: &Shortcut,
+    binding: &ShortcutBinding,
+) -> Result<(), String> {
+    let recording = app.state::<Pipeline>().is_recording();
+    let action = app
+        .state::<ShortcutRegistry>()
+        .tap
+        .lock()
+        .unwrap()
+        .press(recording, Instant::now());
+    match action {
+        TapAction::Start => begin(app, shortcut, binding),
+        TapAction::Stop => stop_if_recording(app),
+        _ => Ok(()),
+    }
+}
+
+fn double_tap_released(app: &tauri::AppHandle) -> Result<(), String> {
+    let action = app
+        .state::<ShortcutRegistry>()
+        .tap
+        .lock()
+        .unwrap()
+        .release(Instant::now());
+    let generation = match action {
+        TapAction::Stop => return stop_if_recording(app),
+        TapAction::AwaitSecondTap(generation) => generation,
+        _ => return Ok(()),
+    };
+
+    // A short tap: keep recording briefly in case a second tap locks it
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(DOUBLE_TAP_WINDOW).await;
+        let registry = app.state::<ShortcutRegistry>();
+        let tap = registry.tap.lock().unwrap();
+        if tap.generation != generation || tap.locked {
+            return;
+        }
+        drop(tap);
+        // A lone tap is too short to be a dictation, so drop it instead of queueing it
+        if app.state::<Pipeline>().is_recording() {
+            let _ = crate::finish_recording(&app);
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(accelerator: &str) -> ShortcutBinding {
+        push_to_talk(accelerator, DictationMode::Dictate)
+    }
+
+    #[test]
+    fn rejects_duplicate_chords() {
+        let e = parse_bindings(vec![binding("Ctrl+Shift+D"), binding("shift+ctrl+d")], &[])
+            .unwrap_err();
+        assert_eq!(e.to_string(), "shift+ctrl+d conflicts with Ctrl+Shift+D");
+        let reserved = ["Escape".parse().unwrap()];
+        let e = parse_bindings(vec![binding("Escape")], &reserved).unwrap_err();
+        assert_eq!(e.to_string(), "Escape is reserved by Parrot");
+        assert!(parse_bindings(vec![binding("Ctrl+Shift+D"), binding("Ctrl+Alt+D")], &[]).is_ok());
+        assert!(parse_bindings(vec![binding("Ctrl+Shift+Nope")], &[]).is_err());
+    }
+
+    #[test]
+    fn parses_modes() {
+        let parse = |mode: &str| {
+            serde_json::from_str::<ShortcutBinding>(&format!(
+                r#"{{"accelerator":"Ctrl+D","mode":"{}"}}"#,
+                mode
+            ))
+        };
+        assert_eq!(
+            parse("push_to_talk").unwrap().mode,
+            ShortcutMode::PushToTalk
+        );
+        assert_eq!(
+            parse("double_tap_lock").unwrap().mode,
+            ShortcutMode::DoubleTapLock
+        );
+        assert!(parse("hold").is_err());
+        assert!(parse("PushToTalk").is_err());
+    }
+
+    #[test]
+    fn double_tap_inside_the_window_locks() {
+        let start = Instant::now();
+        let mut tap = TapState::default();
+        assert_eq!(tap.press(false, start), TapAction::Start);
+        let released = start + Duration::from_millis(100);
+        assert_eq!(tap.release(released), TapAction::AwaitSecondTap(1));
+        let second = released + DOUBLE_TAP_WINDOW - Duration::from_millis(1);
+        assert_eq!(tap.press(true, second), TapAction::Nothing);
+        assert!(tap.locked);
+        // Releasing the second tap keeps recording; the next press stops it
+        assert_eq!(
+            tap.release(second + Duration::from_millis(50)),
+            TapAction::Nothing
+        );
+        assert_eq!(
+            tap.press(true, second + Duration::from_secs(5)),
+            TapAction::Stop
+        );
+        assert!(!tap.locked);
+    }
+
+    #[test]
+    fn double_tap_outside_the_window_does_not_lock() {
+        let start = Instant::now();
+        let mut tap = TapState::default();
+        tap.press(false, start);
+        let released = start + Duration::from_millis(100);
+        tap.release(released);
+        let late = released + DOUBLE_TAP_WINDOW + Duration::from_millis(1);
+        assert_eq!(tap.press(true, late), TapAction::Nothing);
+        assert!(!tap.locked);
+    }
+
+    #[test]
+    fn holding_is_push_to_talk() {
+        let start = Instant::now();
+        let mut tap = TapState::default();
+        tap.press(false, start);
+        assert_eq!(tap.release(start + TAP_MAX), TapAction::Stop);
+        assert_eq!(tap.release(start + TAP_MAX), TapAction::Nothing);
+    }
+}
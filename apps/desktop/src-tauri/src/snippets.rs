@@ -0,0 +1,344 @@
+use anyhow::Result;
+use std::collections::HashSet;
+
+use crate::db::{Database, Snippet};
+use crate::guardrails::edit_distance;
+
+/// Trigger characters per allowed misrecognized character ("my signature" → "my signiture")
+const CHARS_PER_TYPO: usize = 6;
+/// Punctuation that ends a sentence, so the next word may start a fuzzy trigger
+const SENTENCE_ENDS: &[char] = &['.', '!', '?', ':', '\n'];
+
+/// Spoken trigger phrases and the text they expand to
+pub struct SnippetSet {
+    entries: Vec<Entry>,
+}
+
+struct Entry {
+    /// Trigger words run together, so "e-mail" and "email" match alike
+    key: Vec<char>,
+    words: usize,
+    text: String,
+}
+
+pub struct Expansion {
+    pub text: String,
+    /// The dictation was nothing but a trigger, so the stored text stands as is
+    pub whole: bool,
+}
+
+/// A word of the dictation and where it sits in the text, without surrounding punctuation
+struct Word {
+    start: usize,
+    end: usize,
+    key: Vec<char>,
+    /// First word of the dictation or of a sentence in it
+    sentence_start: bool,
+}
+
+impl SnippetSet {
+    pub fn load(db: &Database) -> Result<Self> {
+        Ok(Self::new(&db.get_snippets()?))
+    }
+
+    pub fn new(snippets: &[Snippet]) -> Self {
+        let entries = snippets
+            .iter()
+            .map(|s| Entry {
+                key: key(&s.phrase).collect(),
+                words: s.phrase.split_whitespace().filter(|w| has_key(w)).count(),
+                text: s.text.clone(),
+            })
+            .filter(|e| !e.key.is_empty())
+            .collect();
+        Self { entries }
+    }
+
+    /// Replace every trigger in `text` with its snippet, placeholders filled in
+    pub fn expand(&self, text: &str) -> Option<Expansion> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let words = words(text);
+        let mut out = String::with_capacity(text.len());
+        let mut copied = 0;
+        let mut expansions = 0;
+        let mut i = 0;
+        while i < words.len() {
+            let Some((count, entry)) = self.best_match(&words[i..]) else {
+                i += 1;
+                continue;
+            };
+            // Punctuation the recognizer put around a trigger said on its own is dropped
+            if count == words.len() {
+                return Some(Expansion {
+                    text: fill_placeholders(&entry.text),
+                    whole: true,
+                });
+            }
+            out.push_str(&text[copied..words[i].start]);
+            out.push_str(&fill_placeholders(&entry.text));
+            copied = words[i + count - 1].end;
+            expansions += 1;
+            i += count;
+        }
+        if expansions == 0 {
+            return None;
+        }
+        out.push_str(&text[copied..]);
+        Some(Expansion {
+            text: out,
+            whole: false,
+        })
+    }
+
+    /// The closest trigger starting at the first word, and how many words it covers.
+    /// Misheard triggers only count at the start of a sentence; in the middle of one a
+    /// near miss is more likely a real word ("I thank you" isn't "thanks").
+    fn best_match(&self, words: &[Word]) -> Option<(usize, &Entry)> {
+        let typos_allowed = words[0].sentence_start;
+        let mut best: Option<(usize, usize, &Entry)> = None;
+        for entry in &self.entries {
+            // A misheard first letter is more often a different word
+            if words[0].key.first() != entry.key.first() {
+                continue;
+            }
+            // The recognizer may split or merge a word ("log in" / "login")
+            let fewest = entry.words.saturating_sub(1).max(1);
+            for count in fewest..=(entry.words + 1).min(words.len()) {
+                let spoken: Vec<char> = words[..count]
+                    .iter()
+                    .flat_map(|w| w.key.iter().copied())
+                    .collect();
+                let distance = edit_distance(&spoken, &entry.key);
+                let allowed = if typos_allowed {
+                    entry.key.len() / CHARS_PER_TYPO
+                } else {
+                    0
+                };
+                if distance > allowed {
+                    continue;
+                }
+                let better = best.is_none_or(|(best_distance, best_count, _)| {
+                    (distance, count.abs_diff(entry.words))
+                        < (best_distance, best_count.abs_diff(entry.words))
+                });
+                if better {
+                    best = Some((distance, count, entry));
+                }
+            }
+        }
+        best.map(|(_, count, entry)| (count, entry))
+    }
+}
+
+/// Reject snippets without a usable trigger or text, and triggers that sound the same
+pub fn validate(snippets: &[Snippet]) -> Result<()> {
+    let mut seen = HashSet::new();
+    for snippet in snippets {
+        if !has_key(&snippet.phrase) {
+            anyhow::bail!("Snippets need a trigger phrase");
+        }
+        if snippet.text.trim().is_empty() {
+            anyhow::bail!("Snippet \"{}\" has no text", snippet.phrase.trim());
+        }
+        if !seen.insert(key(&snippet.phrase).collect::<String>()) {
+            anyhow::bail!("Trigger \"{}\" is used twice", snippet.phrase.trim());
+        }
+    }
+    Ok(())
+}
+
+/// Words with their byte ranges; tokens of punctuation alone are skipped
+fn words(text: &str) -> Vec<Word> {
+    let mut words: Vec<Word> = Vec::new();
+    let mut start = None;
+    for (index, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        match (c.is_whitespace(), start) {
+            (false, None) => start = Some(index),
+            (true, Some(s)) => {
+                let token = &text[s..index];
+                let core = token.trim_matches(|c: char| !c.is_alphanumeric());
+                if !core.is_empty() {
+                    let lead = token.len()
+                        - token
+                            .trim_start_matches(|c: char| !c.is_alphanumeric())
+                            .len();
+                    let sentence_start = words
+                        .last()
+                        .is_none_or(|w| text[w.end..s + lead].contains(SENTENCE_ENDS));
+                    words.push(Word {
+                        start: s + lead,
+                        end: s + lead + core.len(),
+                        key: key(core).collect(),
+                        sentence_start,
+                    });
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+/// Lower-cased letters and digits, without spaces or punctuation
+fn key(text: &str) -> impl Iterator<Item = char> + '_ {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+}
+
+fn has_key(text: &str) -> bool {
+    text.chars().any(char::is_alphanumeric)
+}
+
+/// `{date}`, `{time}` and `{clipboard}`; date and time take a strftime format
+/// after a colon, as in `{date:%d.%m.%Y}`. Unknown placeholders are left alone.
+fn fill_placeholders(text: &str) -> String {
+    let now = chrono::Local::now();
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let Some(close) = after.find('}') else {
+            rest = &rest[open..];
+            break;
+        };
+        let (name, format) = match after[..close].split_once(':') {
+            Some((name, format)) => (name, Some(format)),
+            None => (&after[..close], None),
+        };
+        let value = match name {
+            "date" => Some(format_time(&now, format.unwrap_or("%Y-%m-%d"))),
+            "time" => Some(format_time(&now, format.unwrap_or("%H:%M"))),
+            "clipboard" if format.is_none() => Some(clipboard_text()),
+            _ => None,
+        };
+        match value {
+            Some(value) => {
+                out.push_str(&value);
+                rest = &after[close + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// An invalid format is kept as typed rather than failing the dictation
+fn format_time(now: &chrono::DateTime<chrono::Local>, format: &str) -> String {
+    use std::fmt::Write;
+    let mut out = String::new();
+    match write!(out, "{}", now.format(format)) {
+        Ok(()) => out,
+        Err(_) => format.to_string(),
+    }
+}
+
+fn clipboard_text() -> String {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .unwrap_or_else(|e| {
+            eprintln!("Clipboard unavailable for snippet: {}", e);
+            String::new()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet_set(snippets: &[(&str, &str)]) -> SnippetSet {
+        SnippetSet::new(
+            &snippets
+                .iter()
+                .map(|(phrase, text)| Snippet {
+                    phrase: phrase.to_string(),
+                    text: text.to_string(),
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    fn expand(set: &SnippetSet, text: &str) -> Option<String> {
+        set.expand(text).map(|e| e.text)
+    }
+
+    #[test]
+    fn expands_whole_dictations() {
+        let set = snippet_set(&[
+            ("my signature", "Best,\nAlex"),
+            ("log in", "https://example.com"),
+        ]);
+        let expansion = set.expand("My signature.").unwrap();
+        assert!(expansion.whole);
+        assert_eq!(expansion.text, "Best,\nAlex");
+        // Near misses, and words the recognizer split or merged
+        assert_eq!(expand(&set, "my signiture").as_deref(), Some("Best,\nAlex"));
+        assert_eq!(expand(&set, "mysignature").as_deref(), Some("Best,\nAlex"));
+        assert_eq!(
+            expand(&set, "login").as_deref(),
+            Some("https://example.com")
+        );
+        assert_eq!(
+            expand(&set, "Log-in.").as_deref(),
+            Some("https://example.com")
+        );
+    }
+
+    #[test]
+    fn expands_inside_dictations() {
+        let set = snippet_set(&[
+            ("thanks", "Thank you for your help!"),
+            ("log in", "https://example.com"),
+        ]);
+        assert_eq!(
+            expand(&set, "See you soon. Thank").as_deref(),
+            Some("See you soon. Thank you for your help!")
+        );
+        assert_eq!(
+            expand(&set, "Use the login page").as_deref(),
+            Some("Use the https://example.com page")
+        );
+        assert_eq!(
+            expand(&set, "Great work, thanks").as_deref(),
+            Some("Great work, Thank you for your help!")
+        );
+    }
+
+    #[test]
+    fn leaves_near_misses_in_sentences_alone() {
+        let set = snippet_set(&[
+            ("thanks", "Thank you for your help!"),
+            ("my signature", "Best,\nAlex"),
+        ]);
+        for text in [
+            "I thank you",
+            "He thanked them",
+            "Sign here with my signiture please",
+            "This is my sig",
+            "",
+        ] {
+            assert_eq!(expand(&set, text), None, "{:?}", text);
+        }
+        assert!(snippet_set(&[]).expand("thanks").is_none());
+    }
+
+    #[test]
+    fn validates_snippets() {
+        let snippet = |phrase: &str, text: &str| Snippet {
+            phrase: phrase.to_string(),
+            text: text.to_string(),
+        };
+        assert!(validate(&[snippet("thanks", "Thank you")]).is_ok());
+        assert!(validate(&[snippet("...", "Thank you")]).is_err());
+        assert!(validate(&[snippet("thanks", " ")]).is_err());
+        assert!(validate(&[snippet("log in", "a"), snippet("Login", "b")]).is_err());
+    }
+}
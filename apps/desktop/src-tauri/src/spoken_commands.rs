@@ -0,0 +1,563 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::db::Database;
+use crate::output_format::OutputFormat;
+
+/// What a spoken formatting command turns into
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "action", content = "text", rename_all = "snake_case")]
+pub enum FormattingAction {
+    NewLine,
+    NewParagraph,
+    /// Start a "- " list item on its own line
+    Bullet,
+    /// Punctuation joined to the previous word, e.g. "." or "," (or " ?" in French)
+    Attach(String),
+    /// Opening symbol joined to the next word, e.g. "(" or an opening quote
+    Open(String),
+    /// Upper-case the next word
+    AllCapsNext,
+    /// Symbol joined to the words on both sides, e.g. "." or "_"
+    Join(String),
+    /// Symbol set off by spaces, e.g. "=" or "->"
+    Symbol(String),
+    /// Start a line with a marker, e.g. "# " for a Markdown heading
+    LinePrefix(String),
+    /// Join the following words into one identifier, up to the next command or punctuation
+    Case(IdentifierCase),
+}
+
+/// How the words of a spoken identifier are joined ("user id" → "user_id", "userId")
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IdentifierCase {
+    Snake,
+    Camel,
+    Pascal,
+    Kebab,
+    Constant,
+}
+
+impl IdentifierCase {
+    fn join(self, words: &[String]) -> String {
+        let lower: Vec<String> = words.iter().map(|w| w.to_lowercase()).collect();
+        match self {
+            Self::Snake => lower.join("_"),
+            Self::Kebab => lower.join("-"),
+            Self::Constant => lower.join("_").to_uppercase(),
+            Self::Camel | Self::Pascal => lower
+                .iter()
+                .enumerate()
+                .map(|(i, word)| match i == 0 && self == Self::Camel {
+                    true => word.clone(),
+                    false => capitalize(word),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// One entry of the command table, as stored in the `formatting_commands` setting
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SpokenCommand {
+    pub phrase: String,
+    #[serde(flatten)]
+    pub action: FormattingAction,
+}
+
+/// Phrases for one locale, matched case-insensitively and ignoring punctuation
+/// the transcriber put around them (Whisper often writes "New line." for "new line")
+pub struct CommandTable {
+    commands: Vec<Command>,
+}
+
+struct Command {
+    words: Vec<String>,
+    action: FormattingAction,
+    /// Built-in one-word phrases such as "period" or "bullet" are also ordinary words,
+    /// so they only count where a command can't be mistaken for prose
+    positional: bool,
+}
+
+impl CommandTable {
+    /// Built-in phrases for `locale` (a language code such as "de" or "fr-CA", English when
+    /// unknown) and for the output format, extended or overridden by the user's entries
+    /// for that language.
+    /// Setting `formatting_commands`: JSON object of language code → list of commands.
+    pub fn load(db: &Database, locale: Option<&str>, format: OutputFormat) -> Result<Self> {
+        let language = locale
+            .and_then(|l| l.split(['-', '_']).next())
+            .map(|l| l.to_lowercase())
+            .unwrap_or_else(|| "en".to_string());
+
+        let mut table = Self {
+            commands: Vec::new(),
+        };
+        for (phrase, action) in builtin_commands(&language)
+            .into_iter()
+            .chain(format.spoken_commands())
+        {
+            table.insert(phrase, action, true);
+        }
+        if let Some(json) = db.get_setting("formatting_commands")? {
+            let custom = parse_custom(&json)?;
+            for command in custom.get(&language).into_iter().flatten() {
+                // The user picked the phrase, so it counts wherever it is said
+                table.insert(&command.phrase, command.action.clone(), false);
+            }
+        }
+        Ok(table)
+    }
+
+    fn insert(&mut self, phrase: &str, action: FormattingAction, builtin: bool) {
+        let words: Vec<String> = phrase.split_whitespace().map(normalize).collect();
+        if words.is_empty() {
+            return;
+        }
+        // "newline" and code symbols mean nothing else
+        let positional = builtin
+            && words.len() == 1
+            && !matches!(
+                action,
+                FormattingAction::NewLine
+                    | FormattingAction::NewParagraph
+                    | FormattingAction::Join(_)
+                    | FormattingAction::Symbol(_)
+                    | FormattingAction::Case(_)
+            );
+        self.commands.retain(|existing| existing.words != words);
+        self.commands.push(Command {
+            words,
+            action,
+            positional,
+        });
+    }
+
+    /// The longest command starting at `normalized[i]`, with how many words it spans.
+    /// A one-word built-in phrase that closes something ("period", "comma") must end the
+    /// sentence; one that opens something ("bullet", "heading") must start it.
+    fn longest_match(
+        &self,
+        tokens: &[&str],
+        normalized: &[String],
+        i: usize,
+        after_command: bool,
+    ) -> Option<(usize, &FormattingAction)> {
+        self.commands
+            .iter()
+            .filter(|c| normalized[i..].starts_with(&c.words))
+            .filter(|c| {
+                !c.positional
+                    || match c.action {
+                        FormattingAction::Attach(_) => self.ends_sentence(tokens, normalized, i),
+                        _ => after_command || starts_sentence(tokens, i),
+                    }
+            })
+            .max_by_key(|c| c.words.len())
+            .map(|c| (c.words.len(), &c.action))
+    }
+
+    /// Whether the word at `i` is the last of the dictation or of a sentence the
+    /// transcriber punctuated, or another command follows it
+    fn ends_sentence(&self, tokens: &[&str], normalized: &[String], i: usize) -> bool {
+        i + 1 == tokens.len()
+            || tokens[i].ends_with(['.', ',', ';', ':', '!', '?'])
+            || self
+                .longest_match(tokens, normalized, i + 1, true)
+                .is_some()
+    }
+
+    /// Replace spoken commands in `text` with the formatting they stand for
+    pub fn apply(&self, text: &str) -> String {
+        let tokens: Vec<&str> = text.split_whitespace().collect();
+        let normalized: Vec<String> = tokens.iter().map(|t| normalize(t)).collect();
+        let mut out = Output::default();
+
+        let mut i = 0;
+        let mut after_command = false;
+        while i < tokens.len() {
+            match self.longest_match(&tokens, &normalized, i, after_command) {
+                Some((len, action)) => {
+                    out.apply(action);
+                    i += len;
+                    after_command = true;
+                }
+                None => {
+                    out.word(tokens[i]);
+                    i += 1;
+                    after_command = false;
+                }
+            }
+        }
+        out.finish_identifier();
+        out.text.trim_end().to_string()
+    }
+}
+
+/// Whether the word at `i` opens the dictation or follows a sentence end
+fn starts_sentence(tokens: &[&str], i: usize) -> bool {
+    i == 0 || tokens[i - 1].ends_with(['.', ':', '!', '?'])
+}
+
+fn parse_custom(json: &str) -> Result<HashMap<String, Vec<SpokenCommand>>> {
+    serde_json::from_str(json)
+        .map_err(|e| anyhow::anyhow!("Invalid formatting_commands setting: {}", e))
+}
+
+/// Check a `formatting_commands` value before it is saved
+pub fn validate_table(json: &str) -> Result<()> {
+    for (language, commands) in parse_custom(json)? {
+        if let Some(command) = commands.iter().find(|c| c.phrase.trim().is_empty()) {
+            anyhow::bail!(
+                "Empty phrase in the \"{}\" commands ({:?})",
+                language,
+                command.action
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Setting `spoken_formatting`: "false" turns the parser off
+pub fn is_enabled(db: &Database) -> bool {
+    db.get_setting("spoken_formatting")
+        .ok()
+        .flatten()
+        .is_none_or(|v| v != "false")
+}
+
+fn normalize(word: &str) -> String {
+    word.trim_matches(|c: char| c.is_ascii_punctuation() && c != '\'' && c != '-')
+        .to_lowercase()
+}
+
+#[derive(Default)]
+struct Output {
+    text: String,
+    space_before_next: bool,
+    capitalize_next: bool,
+    upper_next: bool,
+    /// Words of an identifier still being dictated
+    identifier: Option<(IdentifierCase, Vec<String>)>,
+}
+
+impl Output {
+    fn word(&mut self, word: &str) {
+        if let Some((_, words)) = &mut self.identifier {
+            let core = word.trim_end_matches(|c: char| c.is_ascii_punctuation());
+            if !core.is_empty() {
+                words.push(core.to_string());
+            }
+            // Punctuation the transcriber put after a word ends the identifier
+            if core.len() < word.len() {
+                self.finish_identifier();
+                self.text.push_str(&word[core.len()..]);
+            }
+            return;
+        }
+        if self.space_before_next {
+            self.text.push(' ');
+        }
+        let mut word = word.to_string();
+        if self.upper_next {
+            word = word.to_uppercase();
+            self.upper_next = false;
+        } else if self.capitalize_next {
+            word = capitalize(&word);
+        }
+        self.capitalize_next = false;
+        self.text.push_str(&word);
+        self.space_before_next = true;
+    }
+
+    fn line_break(&mut self, breaks: &str) {
+        self.trim_trailing_punctuation(&[',', ';', ':']);
+        let trimmed = self.text.trim_end_matches([' ', '\n']).len();
+        self.text.truncate(trimmed);
+        if !self.text.is_empty() {
+            self.text.push_str(breaks);
+        }
+        self.space_before_next = false;
+        self.capitalize_next = true;
+    }
+
+    /// Drop punctuation the transcriber already put where a command now adds its own
+    fn trim_trailing_punctuation(&mut self, marks: &[char]) {
+        let trimmed = self.text.trim_end_matches(marks).len();
+        self.text.truncate(trimmed);
+    }
+
+    fn finish_identifier(&mut self) {
+        let Some((case, words)) = self.identifier.take() else {
+            return;
+        };
+        if !words.is_empty() {
+            // Casing is the point of an identifier, so a sentence start doesn't change it
+            self.capitalize_next = false;
+            self.word(&case.join(&words));
+        }
+    }
+
+    fn apply(&mut self, action: &FormattingAction) {
+        self.finish_identifier();
+        match action {
+            FormattingAction::NewLine => self.line_break("\n"),
+            FormattingAction::NewParagraph => self.line_break("\n\n"),
+            FormattingAction::Bullet => {
+                self.line_break("\n");
+                self.text.push_str("- ");
+            }
+            FormattingAction::Attach(mark) => {
+                self.trim_trailing_punctuation(&['.', ',', ';', ':', '!', '?']);
+                self.text.push_str(mark);
+                self.space_before_next = true;
+                if mark.ends_with(['.', '!', '?']) {
+                    self.capitalize_next = true;
+                }
+            }
+            FormattingAction::Open(mark) => {
+                if self.space_before_next {
+                    self.text.push(' ');
+                }
+                self.text.push_str(mark);
+                self.space_before_next = false;
+            }
+            FormattingAction::AllCapsNext => self.upper_next = true,
+            FormattingAction::Join(mark) => {
+                self.text.push_str(mark);
+                self.space_before_next = false;
+            }
+            FormattingAction::Symbol(mark) => {
+                if self.space_before_next {
+                    self.text.push(' ');
+                }
+                self.text.push_str(mark);
+                self.space_before_next = true;
+            }
+            FormattingAction::LinePrefix(prefix) => {
+                self.line_break("\n");
+                self.text.push_str(prefix);
+            }
+            FormattingAction::Case(case) => self.identifier = Some((*case, Vec::new())),
+        }
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn builtin_commands(language: &str) -> Vec<(&'static str, FormattingAction)> {
+    use FormattingAction::*;
+    let attach = |s: &str| Attach(s.to_string());
+    let open = |s: &str| Open(s.to_string());
+
+    match language {
+        "de" => vec![
+            ("neue zeile", NewLine),
+            ("neuer absatz", NewParagraph),
+            ("punkt", attach(".")),
+            ("komma", attach(",")),
+            ("fragezeichen", attach("?")),
+            ("ausrufezeichen", attach("!")),
+            ("doppelpunkt", attach(":")),
+            ("semikolon", attach(";")),
+            ("anführungszeichen auf", open("„")),
+            ("anführungszeichen zu", attach("“")),
+            ("klammer auf", open("(")),
+            ("klammer zu", attach(")")),
+            ("aufzählungspunkt", Bullet),
+            ("großbuchstaben", AllCapsNext),
+        ],
+        // French puts a narrow no-break space before ? ! : ; and inside guillemets
+        "fr" => vec![
+            ("nouvelle ligne", NewLine),
+            ("à la ligne", NewLine),
+            ("nouveau paragraphe", NewParagraph),
+            ("point", attach(".")),
+            ("virgule", attach(",")),
+            ("point d'interrogation", attach("\u{202f}?")),
+            ("point d'exclamation", attach("\u{202f}!")),
+            ("deux points", attach("\u{202f}:")),
+            ("point-virgule", attach("\u{202f};")),
+            ("ouvrez les guillemets", open("«\u{202f}")),
+            ("fermez les guillemets", attach("\u{202f}»")),
+            ("ouvrez la parenthèse", open("(")),
+            ("fermez la parenthèse", attach(")")),
+            ("puce", Bullet),
+            ("tout en majuscules", AllCapsNext),
+        ],
+        "es" => vec![
+            ("nueva línea", NewLine),
+            ("nuevo párrafo", NewParagraph),
+            ("punto", attach(".")),
+            ("coma", attach(",")),
+            ("signo de interrogación", attach("?")),
+            ("signo de exclamación", attach("!")),
+            ("dos puntos", attach(":")),
+            ("punto y coma", attach(";")),
+            ("abrir comillas", open("«")),
+            ("cerrar comillas", attach("»")),
+            ("abrir paréntesis", open("(")),
+            ("cerrar paréntesis", attach(")")),
+            ("viñeta", Bullet),
+            ("todo en mayúsculas", AllCapsNext),
+        ],
+        _ => vec![
+            ("new line", NewLine),
+            ("newline", NewLine),
+            ("new paragraph", NewParagraph),
+            ("period", attach(".")),
+            ("full stop", attach(".")),
+            ("comma", attach(",")),
+            ("question mark", attach("?")),
+            ("exclamation mark", attach("!")),
+            ("exclamation point", attach("!")),
+            ("colon", attach(":")),
+            ("semicolon", attach(";")),
+            ("open quote", open("\"")),
+            ("close quote", attach("\"")),
+            ("end quote", attach("\"")),
+            ("open paren", open("(")),
+            ("close paren", attach(")")),
+            ("bullet point", Bullet),
+            ("bullet", Bullet),
+            ("all caps", AllCapsNext),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(language: &str, format: OutputFormat) -> CommandTable {
+        let mut table = CommandTable {
+            commands: Vec::new(),
+        };
+        for (phrase, action) in builtin_commands(language)
+            .into_iter()
+            .chain(format.spoken_commands())
+        {
+            table.insert(phrase, action, true);
+        }
+        table
+    }
+
+    fn check(language: &str, format: OutputFormat, cases: &[(&str, &str)]) {
+        let table = table(language, format);
+        for (input, expected) in cases {
+            assert_eq!(table.apply(input), *expected, "{}: {:?}", language, input);
+        }
+    }
+
+    #[test]
+    fn applies_commands() {
+        check(
+            "en",
+            OutputFormat::Plain,
+            &[
+                ("hello comma new line world period", "hello\nWorld."),
+                ("dear John comma", "dear John,"),
+                ("first line new line second line", "first line\nSecond line"),
+                ("intro new paragraph body", "intro\n\nBody"),
+                ("Done. New line. Next", "Done.\nNext"),
+                ("is it ready question mark", "is it ready?"),
+                (
+                    "shopping list colon bullet milk. bullet eggs",
+                    "shopping list\n- Milk.\n- Eggs",
+                ),
+                ("bullet point milk bullet point eggs", "- Milk\n- Eggs"),
+                ("he said open quote hi close quote", "he said \"hi\""),
+                ("all caps nasa launched", "NASA launched"),
+                ("wait period. Then go", "wait. Then go"),
+            ],
+        );
+    }
+
+    #[test]
+    fn leaves_command_words_in_prose() {
+        check(
+            "en",
+            OutputFormat::Plain,
+            &[
+                (
+                    "the trial period ends friday",
+                    "the trial period ends friday",
+                ),
+                ("put a comma after the name", "put a comma after the name"),
+                (
+                    "the colon is part of the gut",
+                    "the colon is part of the gut",
+                ),
+                ("a bullet hit the wall", "a bullet hit the wall"),
+                ("take the bullet train", "take the bullet train"),
+            ],
+        );
+        check(
+            "de",
+            OutputFormat::Plain,
+            &[
+                (
+                    "das ist der springende punkt hier",
+                    "das ist der springende punkt hier",
+                ),
+                ("hallo komma wie geht's punkt", "hallo komma wie geht's."),
+            ],
+        );
+        check(
+            "fr",
+            OutputFormat::Plain,
+            &[
+                ("le point de départ", "le point de départ"),
+                ("c'est fini point", "c'est fini."),
+                ("vraiment point d'interrogation", "vraiment\u{202f}?"),
+            ],
+        );
+        check(
+            "es",
+            OutputFormat::Plain,
+            &[
+                ("desde este punto de vista", "desde este punto de vista"),
+                ("hola coma amigo punto", "hola coma amigo."),
+                ("uno punto y coma dos", "uno; dos"),
+            ],
+        );
+    }
+
+    #[test]
+    fn applies_format_commands_anywhere() {
+        check(
+            "en",
+            OutputFormat::Code,
+            &[
+                ("snake case user id equals five", "user_id = five"),
+                ("self dot name", "self.name"),
+            ],
+        );
+        check(
+            "en",
+            OutputFormat::Markdown,
+            &[
+                (
+                    "heading two setup new line install it",
+                    "## Setup\nInstall it",
+                ),
+                ("the heading is wrong", "the heading is wrong"),
+            ],
+        );
+    }
+
+    #[test]
+    fn custom_phrases_count_anywhere() {
+        let mut table = table("en", OutputFormat::Plain);
+        table.insert("comma", FormattingAction::Attach(",".to_string()), false);
+        assert_eq!(table.apply("yes comma sure"), "yes, sure");
+    }
+}
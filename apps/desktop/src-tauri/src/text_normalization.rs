@@ -0,0 +1,1583 @@
+use anyhow::Result;
+
+/// How spoken numbers are written out
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NumberStyle {
+    Off,
+    /// Digits for amounts, dates, times and numbers from ten up; "three ideas" stays prose
+    Standard,
+    /// Digits for every number
+    AllDigits,
+}
+
+impl NumberStyle {
+    /// Profile field `text_normalization`: "" (standard), "all_digits" or "off"
+    pub fn parse(value: &str) -> Result<Self> {
+        Ok(match value {
+            "" | "standard" => Self::Standard,
+            "all_digits" => Self::AllDigits,
+            "off" => Self::Off,
+            other => anyhow::bail!("Unknown number style: {}", other),
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Lang {
+    En,
+    De,
+    Fr,
+    Es,
+}
+
+/// One spoken number word
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum NumberWord {
+    Value(u64),
+    /// "hundred": multiplies what came before it
+    Hundred,
+    /// "thousand", "million": closes a group
+    Scale(u64),
+    /// "and" in "one hundred and five", "et" in "vingt et un"
+    Connector,
+}
+
+/// Running total while number words are read left to right
+#[derive(Clone, Copy, Default)]
+struct Accumulator {
+    total: u64,
+    current: u64,
+    last_scale: Option<u64>,
+    words: usize,
+    zero: bool,
+}
+
+impl Accumulator {
+    /// Add a word if it can continue the number; "twenty five" can, "five twenty" can't
+    fn push(&mut self, word: NumberWord, lang: Lang) -> bool {
+        if self.zero {
+            return false;
+        }
+        let c = self.current;
+        match word {
+            NumberWord::Value(0) if self.words > 0 => return false,
+            NumberWord::Value(0) => self.zero = true,
+            // "quatre-vingt": four twenties
+            NumberWord::Value(20) if lang == Lang::Fr && c % 100 == 4 => self.current += 76,
+            NumberWord::Value(v) => {
+                let after_tens = c.is_multiple_of(10) && c % 100 >= 20 && v < 10;
+                // "soixante-dix", "quatre-vingt-douze"
+                let french_teens =
+                    lang == Lang::Fr && matches!(c % 100, 60 | 80) && (10..20).contains(&v);
+                if !(c == 0 || (c.is_multiple_of(100) && v < 100) || after_tens || french_teens) {
+                    return false;
+                }
+                self.current += v;
+            }
+            NumberWord::Hundred => {
+                if c >= 100 {
+                    return false;
+                }
+                self.current = c.max(1) * 100;
+            }
+            NumberWord::Scale(scale) => {
+                if self.last_scale.is_some_and(|last| scale >= last) {
+                    return false;
+                }
+                // "mille", "mil" stand alone; English needs "a thousand"
+                let bare = c == 0
+                    && self.words == 0
+                    && scale == 1000
+                    && matches!(lang, Lang::Fr | Lang::Es);
+                if c == 0 && !bare {
+                    return false;
+                }
+                self.total += c.max(1) * scale;
+                self.current = 0;
+                self.last_scale = Some(scale);
+            }
+            NumberWord::Connector => {
+                let ok = match lang {
+                    Lang::En => c.is_multiple_of(100) && (c > 0 || self.total > 0),
+                    Lang::Fr | Lang::Es => c.is_multiple_of(10) && c % 100 >= 20,
+                    Lang::De => false,
+                };
+                if !ok {
+                    return false;
+                }
+            }
+        }
+        self.words += 1;
+        true
+    }
+
+    fn value(&self) -> u64 {
+        self.total + self.current
+    }
+}
+
+/// A number read from the transcript
+#[derive(Clone, Debug, Default)]
+struct Number {
+    /// Words it spans
+    len: usize,
+    value: u64,
+    /// Digits after the decimal point, as spoken
+    decimals: String,
+    ordinal: bool,
+    feminine: bool,
+    /// Digits the transcriber already wrote ("25", "3:30"); kept as they are
+    literal: Option<String>,
+}
+
+impl Number {
+    fn is_integer(&self) -> bool {
+        self.decimals.is_empty() && self.literal.as_ref().is_none_or(|l| is_digits(l))
+    }
+}
+
+/// A whitespace-separated token split into surrounding punctuation and the word itself
+struct Word<'a> {
+    text: &'a str,
+    lead: &'a str,
+    core: String,
+    trail: &'a str,
+}
+
+impl<'a> Word<'a> {
+    fn new(text: &'a str) -> Self {
+        let start = text.find(char::is_alphanumeric).unwrap_or(text.len());
+        let end = text.rfind(char::is_alphanumeric).map_or(start, |i| {
+            i + text[i..].chars().next().map_or(0, char::len_utf8)
+        });
+        Self {
+            text,
+            lead: &text[..start],
+            core: text[start..end.max(start)].to_lowercase(),
+            trail: &text[end.max(start)..],
+        }
+    }
+}
+
+/// Inverse text normalization: "twenty five dollars on march third at three thirty pm"
+/// becomes "$25 on March 3 at 3:30 PM". Runs on the transcript before cleanup.
+pub struct Normalizer {
+    lang: Lang,
+    style: NumberStyle,
+}
+
+impl Normalizer {
+    /// `language` is a code such as "de" or "fr-CA"; English rules apply when unknown
+    pub fn new(language: Option<&str>, style: NumberStyle) -> Self {
+        let lang = match language
+            .and_then(|l| l.split(['-', '_']).next())
+            .map(|l| l.to_lowercase())
+            .as_deref()
+        {
+            Some("de") => Lang::De,
+            Some("fr") => Lang::Fr,
+            Some("es") => Lang::Es,
+            _ => Lang::En,
+        };
+        Self { lang, style }
+    }
+
+    pub fn normalize(&self, text: &str) -> String {
+        if self.style == NumberStyle::Off {
+            return text.to_string();
+        }
+        text.split('\n')
+            .map(|line| self.normalize_line(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn normalize_line(&self, line: &str) -> String {
+        let words: Vec<Word> = line.split(' ').map(Word::new).collect();
+        let mut out: Vec<String> = Vec::with_capacity(words.len());
+        let mut i = 0;
+        while i < words.len() {
+            match self.match_at(&words, i) {
+                Some((len, replacement)) => {
+                    let last = &words[i + len - 1];
+                    let mut trail = last.trail;
+                    // "3:30 p.m. with" → "3:30 PM with"; at the end the period also ends the sentence
+                    if matches!(last.core.as_str(), "a.m" | "p.m") && i + len < words.len() {
+                        trail = trail.strip_prefix('.').unwrap_or(trail);
+                    }
+                    out.push(format!("{}{}{}", words[i].lead, replacement, trail));
+                    i += len;
+                }
+                None => {
+                    out.push(words[i].text.to_string());
+                    i += 1;
+                }
+            }
+        }
+        out.join(" ")
+    }
+
+    /// The longest expression starting at `words[i]`, as (words spanned, written form)
+    fn match_at(&self, words: &[Word], i: usize) -> Option<(usize, String)> {
+        if words[i].core.is_empty() {
+            return None;
+        }
+        self.match_phone(words, i)
+            .or_else(|| self.match_date(words, i))
+            .or_else(|| self.match_time(words, i))
+            .or_else(|| self.match_year(words, i))
+            .or_else(|| self.match_amount(words, i))
+    }
+
+    // ---- Numbers ----
+
+    fn number_word(&self, word: &str) -> Option<NumberWord> {
+        if word.contains('-') {
+            return self
+                .compound(word, false)
+                .map(|(n, _)| NumberWord::Value(n));
+        }
+        let value = match self.lang {
+            Lang::En => match word {
+                "hundred" => return Some(NumberWord::Hundred),
+                "thousand" => return Some(NumberWord::Scale(1_000)),
+                "million" => return Some(NumberWord::Scale(1_000_000)),
+                "billion" => return Some(NumberWord::Scale(1_000_000_000)),
+                "and" => return Some(NumberWord::Connector),
+                _ => lookup(EN_NUMBERS, word)?,
+            },
+            Lang::De => match word {
+                "million" | "millionen" => return Some(NumberWord::Scale(1_000_000)),
+                "milliarde" | "milliarden" => return Some(NumberWord::Scale(1_000_000_000)),
+                _ => german_number(word)?,
+            },
+            Lang::Fr => match word {
+                "cent" | "cents" => return Some(NumberWord::Hundred),
+                "mille" => return Some(NumberWord::Scale(1_000)),
+                "million" | "millions" => return Some(NumberWord::Scale(1_000_000)),
+                "milliard" | "milliards" => return Some(NumberWord::Scale(1_000_000_000)),
+                "et" => return Some(NumberWord::Connector),
+                "une" => 1,
+                "vingts" => 20,
+                _ => lookup(FR_NUMBERS, word)?,
+            },
+            Lang::Es => match word {
+                "mil" => return Some(NumberWord::Scale(1_000)),
+                "millón" | "millones" => return Some(NumberWord::Scale(1_000_000)),
+                "y" => return Some(NumberWord::Connector),
+                _ => lookup(ES_NUMBERS, word)?,
+            },
+        };
+        Some(NumberWord::Value(value))
+    }
+
+    /// An ordinal word as the number word it ends on, and whether it is feminine
+    fn ordinal_word(&self, word: &str) -> Option<(NumberWord, bool)> {
+        if word.contains('-') {
+            return self
+                .compound(word, true)
+                .map(|(n, feminine)| (NumberWord::Value(n), feminine));
+        }
+        match self.lang {
+            Lang::En => {
+                let index = EN_ORDINALS.iter().position(|w| *w == word)?;
+                Some((self.number_word(EN_NUMBERS_FOR_ORDINALS[index])?, false))
+            }
+            Lang::De => german_ordinal(word).map(|n| (NumberWord::Value(n), false)),
+            Lang::Fr => match word {
+                "premier" => Some((NumberWord::Value(1), false)),
+                "première" => Some((NumberWord::Value(1), true)),
+                "unième" => Some((NumberWord::Value(1), false)),
+                _ => {
+                    let stem = word.strip_suffix("ième")?;
+                    let candidates = [
+                        stem.to_string(),
+                        format!("{}e", stem),
+                        stem.replace("cinqu", "cinq"),
+                        stem.replace("neuv", "neuf"),
+                    ];
+                    candidates
+                        .iter()
+                        .find_map(|c| self.number_word(c))
+                        .filter(|w| *w != NumberWord::Connector)
+                        .map(|w| (w, false))
+                }
+            },
+            Lang::Es => {
+                let (value, feminine) = ES_ORDINALS.iter().find_map(|(forms, value)| {
+                    forms
+                        .iter()
+                        .position(|f| *f == word)
+                        .map(|i| (*value, word.ends_with('a') && i > 0))
+                })?;
+                Some((NumberWord::Value(value), feminine))
+            }
+        }
+    }
+
+    /// "twenty-five", "quatre-vingt-dix", "vingt-et-unième"
+    fn compound(&self, word: &str, ordinal: bool) -> Option<(u64, bool)> {
+        let parts: Vec<&str> = word.split('-').collect();
+        let (last, init) = parts.split_last()?;
+        let mut acc = Accumulator::default();
+        for part in init {
+            if !acc.push(self.number_word(part)?, self.lang) {
+                return None;
+            }
+        }
+        let (last, feminine) = if ordinal {
+            self.ordinal_word(last)?
+        } else {
+            (self.number_word(last)?, false)
+        };
+        (acc.push(last, self.lang) && last != NumberWord::Connector)
+            .then(|| (acc.value(), feminine))
+    }
+
+    fn digit_word(&self, word: &str) -> Option<u64> {
+        match (self.lang, word) {
+            (Lang::En, "oh") => return Some(0),
+            (Lang::De, "zwo") => return Some(2),
+            _ => {}
+        }
+        if word.contains('-') {
+            return None;
+        }
+        match self.number_word(word)? {
+            NumberWord::Value(v) if v < 10 => Some(v),
+            _ => None,
+        }
+    }
+
+    fn decimal_word(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "point",
+            Lang::De => "komma",
+            Lang::Fr => "virgule",
+            Lang::Es => "coma",
+        }
+    }
+
+    /// The number starting at `words[i]`, spelled out or already in digits
+    fn parse_number(&self, words: &[Word], i: usize, allow_ordinal: bool) -> Option<Number> {
+        let first = &words[i].core;
+        if first.starts_with(|c: char| c.is_ascii_digit())
+            && first
+                .chars()
+                .all(|c| c.is_ascii_digit() || matches!(c, ',' | '.' | ':'))
+        {
+            return Some(Number {
+                len: 1,
+                value: first.parse().unwrap_or(0),
+                literal: Some(first.clone()),
+                ..Default::default()
+            });
+        }
+
+        let mut acc = Accumulator::default();
+        let mut number = Number::default();
+        let mut j = i;
+        while j < words.len() && (j == i || joined(words, j - 1)) {
+            let core = words[j].core.as_str();
+            // "a hundred", "a thousand"
+            let word = if self.lang == Lang::En && core == "a" && j == i {
+                words
+                    .get(j + 1)
+                    .filter(|_| joined(words, j))
+                    .and_then(|next| self.number_word(&next.core))
+                    .filter(|w| matches!(w, NumberWord::Hundred | NumberWord::Scale(_)))
+                    .map(|_| NumberWord::Value(1))
+            } else {
+                self.number_word(core)
+            };
+            let (word, ordinal) = match word {
+                Some(word) => (word, false),
+                None => match allow_ordinal.then(|| self.ordinal_word(core)).flatten() {
+                    Some((word, feminine)) => {
+                        number.feminine = feminine;
+                        (word, true)
+                    }
+                    None => break,
+                },
+            };
+            if word == NumberWord::Connector {
+                // Only part of the number if another number word follows
+                let mut probe = acc;
+                let next = words
+                    .get(j + 1)
+                    .filter(|_| joined(words, j))
+                    .and_then(|next| self.number_word(&next.core));
+                let continues = next.is_some_and(|next| {
+                    next != NumberWord::Connector
+                        && probe.push(word, self.lang)
+                        && probe.push(next, self.lang)
+                });
+                if !continues {
+                    break;
+                }
+            }
+            if !acc.push(word, self.lang) {
+                break;
+            }
+            j += 1;
+            if ordinal {
+                number.ordinal = true;
+                break;
+            }
+        }
+        if acc.words == 0 {
+            return None;
+        }
+        number.len = j - i;
+        number.value = acc.value();
+
+        // "three point one four"
+        if !number.ordinal
+            && j < words.len()
+            && joined(words, j - 1)
+            && words[j].core == self.decimal_word()
+        {
+            let mut k = j + 1;
+            let mut decimals = String::new();
+            while k < words.len() && joined(words, k - 1) {
+                match self.digit_word(&words[k].core) {
+                    Some(d) => decimals.push_str(&d.to_string()),
+                    None => break,
+                }
+                k += 1;
+            }
+            if !decimals.is_empty() {
+                number.decimals = decimals;
+                number.len = k - i;
+            }
+        }
+        Some(number)
+    }
+
+    /// Digits with the locale's separators. Bare four-digit numbers are usually years,
+    /// which are never grouped; amounts are.
+    fn format_number(&self, number: &Number, amount: bool) -> String {
+        if let Some(literal) = &number.literal {
+            return literal.clone();
+        }
+        let (group, decimal) = match self.lang {
+            Lang::En => (",", "."),
+            Lang::De | Lang::Es => (".", ","),
+            Lang::Fr => ("\u{202f}", ","),
+        };
+        let digits = number.value.to_string();
+        let group_from = if amount { 1_000 } else { 10_000 };
+        let mut out = if number.value >= group_from {
+            let mut grouped = String::new();
+            for (index, c) in digits.chars().enumerate() {
+                if index > 0 && (digits.len() - index).is_multiple_of(3) {
+                    grouped.push_str(group);
+                }
+                grouped.push(c);
+            }
+            grouped
+        } else {
+            digits
+        };
+        if !number.decimals.is_empty() {
+            out.push_str(decimal);
+            out.push_str(&number.decimals);
+        }
+        out
+    }
+
+    fn format_ordinal(&self, value: u64, feminine: bool) -> String {
+        match self.lang {
+            Lang::En => {
+                let suffix = match (value % 10, value % 100) {
+                    (_, 11..=13) => "th",
+                    (1, _) => "st",
+                    (2, _) => "nd",
+                    (3, _) => "rd",
+                    _ => "th",
+                };
+                format!("{}{}", value, suffix)
+            }
+            Lang::De => format!("{}.", value),
+            Lang::Fr => match (value, feminine) {
+                (1, false) => "1er".to_string(),
+                (1, true) => "1re".to_string(),
+                _ => format!("{}e", value),
+            },
+            Lang::Es => format!("{}.{}", value, if feminine { "ª" } else { "º" }),
+        }
+    }
+
+    /// Whether a bare number is written in digits; small ones stay words in prose
+    fn writes_digits(&self, number: &Number) -> bool {
+        number.literal.is_none()
+            && (self.style == NumberStyle::AllDigits
+                || number.value >= 10
+                || !number.decimals.is_empty())
+    }
+
+    /// Whether the expression at `words[i..i + len]` has a number right before or after it
+    fn next_to_number(&self, words: &[Word], i: usize, len: usize) -> bool {
+        let is_number = |word: &Word| {
+            word.core.starts_with(|c: char| c.is_ascii_digit())
+                || matches!(
+                    self.number_word(&word.core),
+                    Some(NumberWord::Value(_) | NumberWord::Hundred | NumberWord::Scale(_))
+                )
+        };
+        let before = i > 0 && joined(words, i - 1) && is_number(&words[i - 1]);
+        let end = i + len;
+        let after = end < words.len() && joined(words, end - 1) && is_number(&words[end]);
+        before || after
+    }
+
+    // ---- Expressions ----
+
+    /// Seven or more digits in a row: "five five five one two three four" → 555-1234
+    fn match_phone(&self, words: &[Word], i: usize) -> Option<(usize, String)> {
+        let mut digits = String::new();
+        let mut j = i;
+        while j < words.len() && (j == i || joined(words, j - 1)) {
+            match self.digit_word(&words[j].core) {
+                Some(d) => digits.push_str(&d.to_string()),
+                None => break,
+            }
+            j += 1;
+        }
+        if digits.len() < 7 {
+            return None;
+        }
+        let formatted = match (self.lang, digits.len()) {
+            (Lang::En, 7) => format!("{}-{}", &digits[..3], &digits[3..]),
+            (Lang::En, 10) => format!("{}-{}-{}", &digits[..3], &digits[3..6], &digits[6..]),
+            (Lang::Fr, 10) => digits
+                .as_bytes()
+                .chunks(2)
+                .map(|pair| std::str::from_utf8(pair).unwrap_or_default())
+                .collect::<Vec<_>>()
+                .join(" "),
+            _ => digits,
+        };
+        Some((j - i, formatted))
+    }
+
+    fn month(&self, word: &str) -> Option<&'static str> {
+        let (spoken, written): (&[&str], &[&str]) = match self.lang {
+            Lang::En => (EN_MONTHS_SPOKEN, EN_MONTHS),
+            Lang::De => (DE_MONTHS_SPOKEN, DE_MONTHS),
+            Lang::Fr => (FR_MONTHS, FR_MONTHS),
+            Lang::Es => (ES_MONTHS, ES_MONTHS),
+        };
+        spoken
+            .iter()
+            .position(|m| *m == word)
+            .map(|index| written[index])
+    }
+
+    /// "march third twenty twenty four" → "March 3, 2024", "dritter März" → "3. März"
+    fn match_date(&self, words: &[Word], i: usize) -> Option<(usize, String)> {
+        let at = |j: usize| words.get(j).map(|w| w.core.as_str());
+        let day = |j: usize, ordinal: bool| {
+            self.parse_number(words, j, true)
+                .filter(|n| n.is_integer() && (1..=31).contains(&n.value))
+                .filter(|n| n.literal.is_none() || !ordinal)
+                .filter(|n| !ordinal || n.ordinal)
+        };
+
+        match self.lang {
+            Lang::En => {
+                // "March third", "March 3"
+                if let Some(month) = self.month(&words[i].core) {
+                    if !joined(words, i) {
+                        return None;
+                    }
+                    let day = day(i + 1, false)?;
+                    let mut len = 1 + day.len;
+                    let mut text = format!("{} {}", month, day.value);
+                    // "March 3, 2024" may already carry its comma
+                    let end = i + len - 1;
+                    if words[end].trail.is_empty() || words[end].trail == "," {
+                        if let Some((year_len, year)) = self.parse_year(words, i + len) {
+                            text = format!("{}, {}", text, year);
+                            len += year_len;
+                        }
+                    }
+                    return Some((len, text));
+                }
+                // "the third of March"
+                let start = if at(i) == Some("the") && joined(words, i) {
+                    i + 1
+                } else {
+                    i
+                };
+                let day = day(start, true)?;
+                let of = start + day.len;
+                if at(of) != Some("of") || !joined(words, of - 1) || !joined(words, of) {
+                    return None;
+                }
+                let month = self.month(at(of + 1)?)?;
+                let mut len = of + 2 - i;
+                let mut text = format!("{} {}", month, day.value);
+                if joined(words, of + 1) {
+                    if let Some((year_len, year)) = self.parse_year(words, of + 2) {
+                        text = format!("{}, {}", text, year);
+                        len += year_len;
+                    }
+                }
+                Some((len, text))
+            }
+            Lang::De | Lang::Fr => {
+                // "dritter März", "trois mars", "premier mai"
+                let day = day(i, self.lang == Lang::De)?;
+                let month_at = i + day.len;
+                if day.literal.is_some() || !joined(words, month_at - 1) {
+                    return None;
+                }
+                let month = self.month(at(month_at)?)?;
+                let mut len = day.len + 1;
+                let day_text = match (self.lang, day.ordinal) {
+                    (Lang::De, _) => format!("{}.", day.value),
+                    (_, true) => self.format_ordinal(day.value, day.feminine),
+                    _ => day.value.to_string(),
+                };
+                let mut text = format!("{} {}", day_text, month);
+                if joined(words, month_at) {
+                    if let Some((year_len, year)) = self.parse_year(words, month_at + 1) {
+                        text = format!("{} {}", text, year);
+                        len += year_len;
+                    }
+                }
+                Some((len, text))
+            }
+            Lang::Es => {
+                // "tres de marzo de dos mil veinticuatro"
+                let day = day(i, false)?;
+                let de = i + day.len;
+                if day.literal.is_some()
+                    || at(de) != Some("de")
+                    || !joined(words, de - 1)
+                    || !joined(words, de)
+                {
+                    return None;
+                }
+                let month = self.month(at(de + 1)?)?;
+                let mut len = day.len + 2;
+                let mut text = format!("{} de {}", day.value, month);
+                if at(de + 2) == Some("de") && joined(words, de + 1) && joined(words, de + 2) {
+                    if let Some((year_len, year)) = self.parse_year(words, de + 3) {
+                        text = format!("{} de {}", text, year);
+                        len += year_len + 1;
+                    }
+                }
+                Some((len, text))
+            }
+        }
+    }
+
+    /// A year such as "two thousand twenty four" or, in English, "nineteen ninety nine"
+    fn parse_year(&self, words: &[Word], j: usize) -> Option<(usize, u64)> {
+        let first = words
+            .get(j)
+            .and_then(|_| self.parse_number(words, j, false))?;
+        if !first.is_integer() {
+            return None;
+        }
+        if (1000..3000).contains(&first.value) {
+            return Some((first.len, first.value));
+        }
+        if self.lang != Lang::En || first.literal.is_some() || !(10..30).contains(&first.value) {
+            return None;
+        }
+        let next = j + first.len;
+        if next >= words.len() || !joined(words, next - 1) {
+            return None;
+        }
+        // "twenty oh five"
+        if words[next].core == "oh" && joined(words, next) {
+            let digit = self.digit_word(words.get(next + 1)?.core.as_str())?;
+            return Some((first.len + 2, first.value * 100 + digit));
+        }
+        let second = self.parse_number(words, next, false)?;
+        (second.literal.is_none() && second.is_integer() && (10..100).contains(&second.value))
+            .then(|| (first.len + second.len, first.value * 100 + second.value))
+    }
+
+    /// "in twenty twenty four" → "in 2024". Without a word like "in" before it, only
+    /// pairs that read as a recent year count: "nineteen eighty four", "twenty twenty five".
+    fn match_year(&self, words: &[Word], i: usize) -> Option<(usize, String)> {
+        if self.lang != Lang::En {
+            return None;
+        }
+        let previous = i.checked_sub(1).map(|p| words[p].core.as_str());
+        let in_context =
+            previous.is_some_and(|p| YEAR_CONTEXT.contains(&p)) && joined(words, i - 1);
+        let (len, year) = self.parse_year(words, i)?;
+        (len > 1
+            && (in_context || RECENT_YEARS.contains(&year))
+            && !self.next_to_number(words, i, len))
+        .then(|| (len, year.to_string()))
+    }
+
+    /// "three thirty pm" → "3:30 PM", "drei Uhr dreißig" → "3:30 Uhr"
+    fn match_time(&self, words: &[Word], i: usize) -> Option<(usize, String)> {
+        let hour = self.parse_number(words, i, false)?;
+        let mut j = i + hour.len;
+        let at = |j: usize| {
+            words
+                .get(j)
+                .filter(|_| joined(words, j - 1))
+                .map(|w| w.core.as_str())
+        };
+        let minutes = |j: usize| {
+            self.parse_number(words, j, false)
+                .filter(|m| m.literal.is_none() && m.is_integer() && m.value < 60)
+        };
+
+        match self.lang {
+            Lang::En => {
+                // "3:30 pm" from the transcriber only needs the marker
+                let literal_time = hour.literal.as_ref().is_some_and(|l| l.contains(':'));
+                if !(literal_time || hour.is_integer() && (1..=12).contains(&hour.value)) {
+                    return None;
+                }
+                let mut minute_text = None;
+                if !literal_time {
+                    if at(j) == Some("oh") {
+                        let digit = at(j + 1).and_then(|w| self.digit_word(w))?;
+                        minute_text = Some(format!("0{}", digit));
+                        j += 2;
+                    } else if at(j) == Some("o'clock") {
+                        minute_text = Some("00".to_string());
+                        j += 1;
+                    } else if let Some(m) = at(j).and_then(|_| minutes(j)).filter(|m| m.value >= 10)
+                    {
+                        minute_text = Some(m.value.to_string());
+                        j += m.len;
+                    }
+                }
+                let marker = match at(j) {
+                    Some("am" | "a.m") => Some("AM"),
+                    Some("pm" | "p.m") => Some("PM"),
+                    _ => None,
+                };
+                let after_at = i > 0 && words[i - 1].core == "at" && joined(words, i - 1);
+                let hour_text = hour
+                    .literal
+                    .clone()
+                    .unwrap_or_else(|| hour.value.to_string());
+                match (marker, minute_text) {
+                    (Some(marker), Some(m)) => {
+                        Some((j + 1 - i, format!("{}:{} {}", hour_text, m, marker)))
+                    }
+                    (Some(marker), None) => Some((j + 1 - i, format!("{} {}", hour_text, marker))),
+                    // "at three thirty", "three o'clock"
+                    (None, Some(m)) if after_at || m == "00" => {
+                        Some((j - i, format!("{}:{}", hour_text, m)))
+                    }
+                    _ => None,
+                }
+            }
+            Lang::De | Lang::Fr => {
+                if hour.literal.is_some() || !hour.is_integer() || hour.value > 24 {
+                    return None;
+                }
+                let unit = at(j)?;
+                let is_unit = match self.lang {
+                    Lang::De => unit == "uhr",
+                    _ => unit == "heure" || unit == "heures",
+                };
+                if !is_unit {
+                    return None;
+                }
+                j += 1;
+                let minute = at(j).and_then(|_| minutes(j)).filter(|m| m.value > 0);
+                if let Some(m) = &minute {
+                    j += m.len;
+                }
+                let text = match (self.lang, minute) {
+                    (Lang::De, Some(m)) => format!("{}:{:02} Uhr", hour.value, m.value),
+                    (Lang::De, None) => format!("{} Uhr", hour.value),
+                    (_, Some(m)) => format!("{} h {:02}", hour.value, m.value),
+                    (_, None) => format!("{} h", hour.value),
+                };
+                Some((j - i, text))
+            }
+            // Spanish says times as "las tres y media"; left to the LLM
+            Lang::Es => None,
+        }
+    }
+
+    /// A number with what follows it: currency, percent, a unit, or nothing
+    fn match_amount(&self, words: &[Word], i: usize) -> Option<(usize, String)> {
+        let number = self.parse_number(words, i, true)?;
+        let next = i + number.len;
+        let follows = |phrase: &[&str]| {
+            phrase.iter().enumerate().all(|(k, part)| {
+                words.get(next + k).is_some_and(|w| w.core == *part) && joined(words, next + k - 1)
+            })
+        };
+        let numeric = number.literal.as_ref().is_none_or(|l| !l.contains(':'));
+
+        if !number.ordinal && numeric {
+            let amount = self.format_number(&number, true);
+            let (percent, percent_sep): (&[&str], &str) = match self.lang {
+                Lang::En => (&["percent"], ""),
+                Lang::De => (&["prozent"], " "),
+                Lang::Fr => (&["pour", "cent"], "\u{202f}"),
+                Lang::Es => (&["por", "ciento"], " "),
+            };
+            if follows(percent) || (self.lang == Lang::En && follows(&["per", "cent"])) {
+                let len = if follows(percent) { percent.len() } else { 2 };
+                return Some((number.len + len, format!("{}{}%", amount, percent_sep)));
+            }
+            if let Some(found) = self.match_currency(words, i, &number) {
+                return Some(found);
+            }
+            let units = match self.lang {
+                Lang::En => EN_UNITS,
+                Lang::De => DE_UNITS,
+                Lang::Fr => FR_UNITS,
+                Lang::Es => ES_UNITS,
+            };
+            // Longest phrase first: "degrees celsius" over "degrees"
+            if let Some((phrase, symbol)) = units
+                .iter()
+                .filter(|(phrase, _)| follows(phrase))
+                .max_by_key(|(phrase, _)| phrase.len())
+            {
+                return Some((number.len + phrase.len(), format!("{}{}", amount, symbol)));
+            }
+        }
+
+        // "fifty fifty", "seven eleven", "three fifty": numbers in a row are an idiom, a
+        // price or a time rather than separate quantities, so the run stays spelled out
+        if !self.writes_digits(&number) || self.next_to_number(words, i, number.len) {
+            return None;
+        }
+        if number.ordinal {
+            // "a second", "un segundo" are time, not position
+            if number.len == 1 && AMBIGUOUS_ORDINALS.contains(&words[i].core.as_str()) {
+                return None;
+            }
+            return Some((
+                number.len,
+                self.format_ordinal(number.value, number.feminine),
+            ));
+        }
+        Some((number.len, self.format_number(&number, false)))
+    }
+
+    /// "twenty five dollars and fifty cents" → "$25.50", "zehn Euro" → "10 €"
+    fn match_currency(&self, words: &[Word], i: usize, number: &Number) -> Option<(usize, String)> {
+        let next = i + number.len;
+        let (currencies, minor_words, connector): (&[Currency], &[&str], &str) = match self.lang {
+            Lang::En => (EN_CURRENCIES, &["cent", "cents"], "and"),
+            Lang::De => (DE_CURRENCIES, &["cent", "cents"], "und"),
+            Lang::Fr => (FR_CURRENCIES, &["centime", "centimes"], "et"),
+            Lang::Es => (
+                ES_CURRENCIES,
+                &["céntimo", "céntimos", "centavo", "centavos"],
+                "con",
+            ),
+        };
+        let word = words.get(next).filter(|_| joined(words, next - 1))?;
+        let (_, symbol, prefix) = currencies
+            .iter()
+            .find(|(forms, _, _)| forms.contains(&word.core.as_str()))?;
+        let mut len = number.len + 1;
+        let mut amount = number.clone();
+
+        // "... and fifty cents"
+        let mut k = next + 1;
+        if words.get(k).is_some_and(|w| w.core == connector) && joined(words, k - 1) {
+            k += 1;
+        }
+        if k < words.len() && joined(words, k - 1) && amount.decimals.is_empty() {
+            if let Some(minor) = self.parse_number(words, k, false) {
+                let end = k + minor.len;
+                let is_minor = words
+                    .get(end)
+                    .is_some_and(|w| minor_words.contains(&w.core.as_str()))
+                    && joined(words, end - 1);
+                if is_minor && minor.is_integer() && minor.value < 100 && minor.literal.is_none() {
+                    amount.decimals = format!("{:02}", minor.value);
+                    len = end + 1 - i;
+                }
+            }
+        }
+        let mut text = self.format_number(&amount, true);
+        if amount.literal.is_some() && !amount.decimals.is_empty() {
+            let decimal = if self.lang == Lang::En { "." } else { "," };
+            text = format!("{}{}{}", text, decimal, amount.decimals);
+        }
+        Some((
+            len,
+            match (*prefix, self.lang) {
+                (true, _) => format!("{}{}", symbol, text),
+                (false, Lang::Fr) => format!("{}\u{a0}{}", text, symbol),
+                (false, _) => format!("{} {}", text, symbol),
+            },
+        ))
+    }
+}
+
+/// Whether `words[j]` and the word after it can belong to one expression:
+/// punctuation between them ends it
+fn joined(words: &[Word], j: usize) -> bool {
+    words[j].trail.is_empty()
+        && words
+            .get(j + 1)
+            .is_some_and(|next| next.lead.is_empty() && !next.core.is_empty())
+}
+
+fn is_digits(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_digit())
+}
+
+fn lookup(table: &[(&str, u64)], word: &str) -> Option<u64> {
+    table.iter().find(|(w, _)| *w == word).map(|(_, v)| *v)
+}
+
+/// German writes numbers as one word: "zweitausendvierundzwanzig"
+fn german_number(word: &str) -> Option<u64> {
+    if let Some(value) = lookup(DE_NUMBERS, word) {
+        return Some(value);
+    }
+    for (marker, scale, limit) in [("tausend", 1000, 1000), ("hundert", 100, 100)] {
+        if let Some(pos) = word.find(marker) {
+            let (left, right) = (&word[..pos], &word[pos + marker.len()..]);
+            let left = if left.is_empty() {
+                1
+            } else {
+                german_number(left)?
+            };
+            let right = if right.is_empty() {
+                0
+            } else {
+                german_number(right)?
+            };
+            return (left < limit && right < scale).then_some(left * scale + right);
+        }
+    }
+    let (units, tens) = word.split_once("und")?;
+    let units = lookup(DE_NUMBERS, units).filter(|u| (1..10).contains(u))?;
+    let tens = lookup(DE_NUMBERS, tens).filter(|t| *t >= 20 && t % 10 == 0)?;
+    Some(tens + units)
+}
+
+/// "dritte", "zwanzigsten", "hundertste"
+fn german_ordinal(word: &str) -> Option<u64> {
+    for suffix in ["sten", "ster", "stes", "stem", "ste"] {
+        if let Some(stem) = word.strip_suffix(suffix) {
+            if stem == "er" {
+                return Some(1);
+            }
+            if let Some(value) = german_number(stem).filter(|v| *v >= 20) {
+                return Some(value);
+            }
+        }
+    }
+    for suffix in ["ten", "ter", "tes", "tem", "te"] {
+        if let Some(stem) = word.strip_suffix(suffix) {
+            let value = match stem {
+                "drit" => Some(3),
+                "sieb" => Some(7),
+                "ach" => Some(8),
+                _ => german_number(stem).filter(|v| (2..20).contains(v)),
+            };
+            if value.is_some() {
+                return value;
+            }
+        }
+    }
+    None
+}
+
+const EN_NUMBERS: &[(&str, u64)] = &[
+    ("zero", 0),
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("nine", 9),
+    ("ten", 10),
+    ("eleven", 11),
+    ("twelve", 12),
+    ("thirteen", 13),
+    ("fourteen", 14),
+    ("fifteen", 15),
+    ("sixteen", 16),
+    ("seventeen", 17),
+    ("eighteen", 18),
+    ("nineteen", 19),
+    ("twenty", 20),
+    ("thirty", 30),
+    ("forty", 40),
+    ("fifty", 50),
+    ("sixty", 60),
+    ("seventy", 70),
+    ("eighty", 80),
+    ("ninety", 90),
+];
+const EN_ORDINALS: &[&str] = &[
+    "first",
+    "second",
+    "third",
+    "fourth",
+    "fifth",
+    "sixth",
+    "seventh",
+    "eighth",
+    "ninth",
+    "tenth",
+    "eleventh",
+    "twelfth",
+    "thirteenth",
+    "fourteenth",
+    "fifteenth",
+    "sixteenth",
+    "seventeenth",
+    "eighteenth",
+    "nineteenth",
+    "twentieth",
+    "thirtieth",
+    "fortieth",
+    "fiftieth",
+    "sixtieth",
+    "seventieth",
+    "eightieth",
+    "ninetieth",
+    "hundredth",
+    "thousandth",
+    "millionth",
+];
+/// The cardinal each of `EN_ORDINALS` stands for
+const EN_NUMBERS_FOR_ORDINALS: &[&str] = &[
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+    "twenty",
+    "thirty",
+    "forty",
+    "fifty",
+    "sixty",
+    "seventy",
+    "eighty",
+    "ninety",
+    "hundred",
+    "thousand",
+    "million",
+];
+const DE_NUMBERS: &[(&str, u64)] = &[
+    ("null", 0),
+    ("eins", 1),
+    ("ein", 1),
+    ("eine", 1),
+    ("zwei", 2),
+    ("drei", 3),
+    ("vier", 4),
+    ("fünf", 5),
+    ("sechs", 6),
+    ("sieben", 7),
+    ("acht", 8),
+    ("neun", 9),
+    ("zehn", 10),
+    ("elf", 11),
+    ("zwölf", 12),
+    ("dreizehn", 13),
+    ("vierzehn", 14),
+    ("fünfzehn", 15),
+    ("sechzehn", 16),
+    ("siebzehn", 17),
+    ("achtzehn", 18),
+    ("neunzehn", 19),
+    ("zwanzig", 20),
+    ("dreißig", 30),
+    ("dreissig", 30),
+    ("vierzig", 40),
+    ("fünfzig", 50),
+    ("sechzig", 60),
+    ("siebzig", 70),
+    ("achtzig", 80),
+    ("neunzig", 90),
+];
+const FR_NUMBERS: &[(&str, u64)] = &[
+    ("zéro", 0),
+    ("un", 1),
+    ("deux", 2),
+    ("trois", 3),
+    ("quatre", 4),
+    ("cinq", 5),
+    ("six", 6),
+    ("sept", 7),
+    ("huit", 8),
+    ("neuf", 9),
+    ("dix", 10),
+    ("onze", 11),
+    ("douze", 12),
+    ("treize", 13),
+    ("quatorze", 14),
+    ("quinze", 15),
+    ("seize", 16),
+    ("vingt", 20),
+    ("trente", 30),
+    ("quarante", 40),
+    ("cinquante", 50),
+    ("soixante", 60),
+];
+const ES_NUMBERS: &[(&str, u64)] = &[
+    ("cero", 0),
+    ("uno", 1),
+    ("un", 1),
+    ("una", 1),
+    ("dos", 2),
+    ("tres", 3),
+    ("cuatro", 4),
+    ("cinco", 5),
+    ("seis", 6),
+    ("siete", 7),
+    ("ocho", 8),
+    ("nueve", 9),
+    ("diez", 10),
+    ("once", 11),
+    ("doce", 12),
+    ("trece", 13),
+    ("catorce", 14),
+    ("quince", 15),
+    ("dieciséis", 16),
+    ("diecisiete", 17),
+    ("dieciocho", 18),
+    ("diecinueve", 19),
+    ("veinte", 20),
+    ("veintiuno", 21),
+    ("veintiún", 21),
+    ("veintiuna", 21),
+    ("veintidós", 22),
+    ("veintitrés", 23),
+    ("veinticuatro", 24),
+    ("veinticinco", 25),
+    ("veintiséis", 26),
+    ("veintisiete", 27),
+    ("veintiocho", 28),
+    ("veintinueve", 29),
+    ("treinta", 30),
+    ("cuarenta", 40),
+    ("cincuenta", 50),
+    ("sesenta", 60),
+    ("setenta", 70),
+    ("ochenta", 80),
+    ("noventa", 90),
+    ("cien", 100),
+    ("ciento", 100),
+    ("doscientos", 200),
+    ("doscientas", 200),
+    ("trescientos", 300),
+    ("trescientas", 300),
+    ("cuatrocientos", 400),
+    ("cuatrocientas", 400),
+    ("quinientos", 500),
+    ("quinientas", 500),
+    ("seiscientos", 600),
+    ("seiscientas", 600),
+    ("setecientos", 700),
+    ("setecientas", 700),
+    ("ochocientos", 800),
+    ("ochocientas", 800),
+    ("novecientos", 900),
+    ("novecientas", 900),
+];
+/// Masculine forms first, then feminine
+const ES_ORDINALS: &[(&[&str], u64)] = &[
+    (&["primero", "primera", "primer"], 1),
+    (&["segundo", "segunda"], 2),
+    (&["tercero", "tercera", "tercer"], 3),
+    (&["cuarto", "cuarta"], 4),
+    (&["quinto", "quinta"], 5),
+    (&["sexto", "sexta"], 6),
+    (&["séptimo", "séptima"], 7),
+    (&["octavo", "octava"], 8),
+    (&["noveno", "novena"], 9),
+    (&["décimo", "décima"], 10),
+];
+/// Ordinals that on their own are mostly idiom ("first of all") or mean something else
+const AMBIGUOUS_ORDINALS: &[&str] = &[
+    "first",
+    "second",
+    "premier",
+    "première",
+    "primero",
+    "primera",
+    "primer",
+    "segundo",
+    "segunda",
+    "cuarto",
+    "cuarta",
+];
+/// Words after which two spoken pairs are a year: "in twenty twenty four"
+const YEAR_CONTEXT: &[&str] = &["in", "since", "until", "from"];
+/// Years two spoken pairs stand for even without such a word before them
+const RECENT_YEARS: std::ops::Range<u64> = 1900..2100;
+
+const EN_MONTHS_SPOKEN: &[&str] = &[
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+const EN_MONTHS: &[&str] = &[
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const DE_MONTHS_SPOKEN: &[&str] = &[
+    "januar",
+    "februar",
+    "märz",
+    "april",
+    "mai",
+    "juni",
+    "juli",
+    "august",
+    "september",
+    "oktober",
+    "november",
+    "dezember",
+];
+const DE_MONTHS: &[&str] = &[
+    "Januar",
+    "Februar",
+    "März",
+    "April",
+    "Mai",
+    "Juni",
+    "Juli",
+    "August",
+    "September",
+    "Oktober",
+    "November",
+    "Dezember",
+];
+const FR_MONTHS: &[&str] = &[
+    "janvier",
+    "février",
+    "mars",
+    "avril",
+    "mai",
+    "juin",
+    "juillet",
+    "août",
+    "septembre",
+    "octobre",
+    "novembre",
+    "décembre",
+];
+const ES_MONTHS: &[&str] = &[
+    "enero",
+    "febrero",
+    "marzo",
+    "abril",
+    "mayo",
+    "junio",
+    "julio",
+    "agosto",
+    "septiembre",
+    "octubre",
+    "noviembre",
+    "diciembre",
+];
+
+/// Spoken forms, symbol, and whether the symbol goes before the amount
+type Currency = (&'static [&'static str], &'static str, bool);
+const EN_CURRENCIES: &[Currency] = &[
+    (&["dollar", "dollars", "bucks"], "$", true),
+    (&["euro", "euros"], "€", true),
+    (&["yen"], "¥", true),
+];
+const DE_CURRENCIES: &[Currency] = &[(&["euro", "euros"], "€", false), (&["dollar"], "$", false)];
+const FR_CURRENCIES: &[Currency] = &[
+    (&["euro", "euros"], "€", false),
+    (&["dollar", "dollars"], "$", false),
+];
+const ES_CURRENCIES: &[Currency] = &[
+    (&["euro", "euros"], "€", false),
+    (&["dólar", "dólares", "dolar", "dolares"], "$", true),
+];
+
+/// Spoken unit and what follows the amount, including any space
+type Unit = (&'static [&'static str], &'static str);
+const EN_UNITS: &[Unit] = &[
+    (&["kilometers"], " km"),
+    (&["kilometres"], " km"),
+    (&["kilometer"], " km"),
+    (&["kilometre"], " km"),
+    (&["meters"], " m"),
+    (&["metres"], " m"),
+    (&["meter"], " m"),
+    (&["metre"], " m"),
+    (&["centimeters"], " cm"),
+    (&["centimetres"], " cm"),
+    (&["millimeters"], " mm"),
+    (&["millimetres"], " mm"),
+    (&["kilograms"], " kg"),
+    (&["kilogram"], " kg"),
+    (&["kilos"], " kg"),
+    (&["grams"], " g"),
+    (&["pounds"], " lb"),
+    (&["ounces"], " oz"),
+    (&["liters"], " L"),
+    (&["litres"], " L"),
+    (&["milliliters"], " mL"),
+    (&["millilitres"], " mL"),
+    (&["inches"], " in"),
+    (&["feet"], " ft"),
+    (&["miles", "per", "hour"], " mph"),
+    (&["kilometers", "per", "hour"], " km/h"),
+    (&["kilometres", "per", "hour"], " km/h"),
+    (&["degrees"], "°"),
+    (&["degrees", "celsius"], "°C"),
+    (&["degrees", "fahrenheit"], "°F"),
+    (&["kilobytes"], " KB"),
+    (&["megabytes"], " MB"),
+    (&["gigabytes"], " GB"),
+    (&["terabytes"], " TB"),
+    (&["megahertz"], " MHz"),
+    (&["gigahertz"], " GHz"),
+];
+const DE_UNITS: &[Unit] = &[
+    (&["kilometer"], " km"),
+    (&["meter"], " m"),
+    (&["zentimeter"], " cm"),
+    (&["millimeter"], " mm"),
+    (&["kilogramm"], " kg"),
+    (&["kilo"], " kg"),
+    (&["gramm"], " g"),
+    (&["liter"], " l"),
+    (&["milliliter"], " ml"),
+    (&["stundenkilometer"], " km/h"),
+    (&["kilometer", "pro", "stunde"], " km/h"),
+    (&["grad"], "°"),
+    (&["grad", "celsius"], " °C"),
+    (&["megabyte"], " MB"),
+    (&["gigabyte"], " GB"),
+    (&["terabyte"], " TB"),
+];
+const FR_UNITS: &[Unit] = &[
+    (&["kilomètres"], " km"),
+    (&["kilomètre"], " km"),
+    (&["mètres"], " m"),
+    (&["mètre"], " m"),
+    (&["centimètres"], " cm"),
+    (&["millimètres"], " mm"),
+    (&["kilogrammes"], " kg"),
+    (&["kilos"], " kg"),
+    (&["grammes"], " g"),
+    (&["litres"], " l"),
+    (&["millilitres"], " ml"),
+    (&["kilomètres", "heure"], " km/h"),
+    (&["kilomètres", "par", "heure"], " km/h"),
+    (&["degrés"], "°"),
+    (&["degrés", "celsius"], " °C"),
+    (&["mégaoctets"], " Mo"),
+    (&["gigaoctets"], " Go"),
+    (&["téraoctets"], " To"),
+];
+const ES_UNITS: &[Unit] = &[
+    (&["kilómetros"], " km"),
+    (&["kilómetro"], " km"),
+    (&["metros"], " m"),
+    (&["metro"], " m"),
+    (&["centímetros"], " cm"),
+    (&["milímetros"], " mm"),
+    (&["kilogramos"], " kg"),
+    (&["kilos"], " kg"),
+    (&["gramos"], " g"),
+    (&["litros"], " l"),
+    (&["mililitros"], " ml"),
+    (&["kilómetros", "por", "hora"], " km/h"),
+    (&["grados"], "°"),
+    (&["grados", "centígrados"], " °C"),
+    (&["grados", "celsius"], " °C"),
+    (&["megabytes"], " MB"),
+    (&["gigabytes"], " GB"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(language: &str, style: NumberStyle, cases: &[(&str, &str)]) {
+        let normalizer = Normalizer::new(Some(language), style);
+        for (input, expected) in cases {
+            assert_eq!(
+                normalizer.normalize(input),
+                *expected,
+                "{}: {:?}",
+                language,
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn normalizes_english() {
+        check(
+            "en",
+            NumberStyle::Standard,
+            &[
+                ("three ideas", "three ideas"),
+                ("twenty five people", "25 people"),
+                ("one hundred and five", "105"),
+                ("a thousand", "1000"),
+                ("twelve thousand five hundred", "12,500"),
+                ("three point one four", "3.14"),
+                ("twenty five dollars", "$25"),
+                ("twenty five dollars and fifty cents", "$25.50"),
+                ("ten percent", "10%"),
+                ("march third", "March 3"),
+                ("march third twenty twenty four", "March 3, 2024"),
+                ("the third of march", "March 3"),
+                ("at three thirty pm", "at 3:30 PM"),
+                ("meet at three thirty", "meet at 3:30"),
+                ("three o'clock", "3:00"),
+                ("3:30 p.m. tomorrow", "3:30 PM tomorrow"),
+                ("five five five one two three four", "555-1234"),
+                ("the twenty first floor", "the 21st floor"),
+                ("first of all", "first of all"),
+                ("in twenty twenty four", "in 2024"),
+                ("since nineteen ninety nine", "since 1999"),
+            ],
+        );
+    }
+
+    #[test]
+    fn reads_number_pairs_as_years_or_leaves_them() {
+        check(
+            "en",
+            NumberStyle::Standard,
+            &[
+                ("twenty twenty five", "2025"),
+                ("nineteen eighty four is a book", "1984 is a book"),
+                ("twenty oh five", "2005"),
+                ("it costs three fifty", "it costs three fifty"),
+                ("fifty fifty chance", "fifty fifty chance"),
+                ("seven eleven", "seven eleven"),
+                ("twenty four seven", "twenty four seven"),
+                ("eleven twelve", "eleven twelve"),
+                ("ten, eleven", "10, 11"),
+            ],
+        );
+        check(
+            "en",
+            NumberStyle::AllDigits,
+            &[
+                ("three ideas", "3 ideas"),
+                ("seven eleven", "seven eleven"),
+                ("twenty four seven", "twenty four seven"),
+            ],
+        );
+    }
+
+    #[test]
+    fn normalizes_german() {
+        check(
+            "de",
+            NumberStyle::Standard,
+            &[
+                ("drei Ideen", "drei Ideen"),
+                ("fünfundzwanzig Leute", "25 Leute"),
+                ("zehn Euro", "10 €"),
+                ("zwanzig Prozent", "20 %"),
+                ("dritter März", "3. März"),
+                ("drei Uhr dreißig", "3:30 Uhr"),
+                ("zwölftausend", "12.000"),
+                ("drei komma fünf", "3,5"),
+                ("fünfzig fünfzig", "fünfzig fünfzig"),
+            ],
+        );
+    }
+
+    #[test]
+    fn normalizes_french() {
+        check(
+            "fr",
+            NumberStyle::Standard,
+            &[
+                ("trois idées", "trois idées"),
+                ("vingt et un", "21"),
+                ("quatre-vingt-douze", "92"),
+                ("soixante-dix", "70"),
+                ("dix euros", "10\u{a0}€"),
+                ("trois mars", "3 mars"),
+                ("premier mai", "1er mai"),
+                ("quinze heures trente", "15 h 30"),
+                ("cinquante pour cent", "50\u{202f}%"),
+                ("douze mille", "12\u{202f}000"),
+                ("cinquante cinquante", "cinquante cinquante"),
+            ],
+        );
+    }
+
+    #[test]
+    fn normalizes_spanish() {
+        check(
+            "es",
+            NumberStyle::Standard,
+            &[
+                ("tres ideas", "tres ideas"),
+                ("veinticinco personas", "25 personas"),
+                ("treinta y dos", "32"),
+                ("tres de marzo", "3 de marzo"),
+                ("diez euros", "10 €"),
+                ("cincuenta por ciento", "50 %"),
+                ("mil", "1000"),
+                ("cincuenta cincuenta", "cincuenta cincuenta"),
+            ],
+        );
+    }
+
+    #[test]
+    fn leaves_text_alone_when_off() {
+        check(
+            "en",
+            NumberStyle::Off,
+            &[("twenty five dollars", "twenty five dollars")],
+        );
+    }
+}
@@ -8,29 +8,46 @@ struct BackendTranscribeResponse {
     text: String,
 }
 
+/// `language` is a hint for the spoken language (None lets the model detect it).
+/// `translate` asks Whisper for its translate task, which always outputs English.
 pub async fn transcribe_audio(
+    client: &reqwest::Client,
     wav_data: &[u8],
     mode: &str,
     session_token: Option<&str>,
     api_key: Option<&str>,
+    language: Option<&str>,
+    translate: bool,
 ) -> Result<String> {
     match mode {
-        "local" => transcribe_with_whisper_server(wav_data).await,
-        "cloud" => transcribe_with_backend(wav_data, session_token, api_key).await,
+        "local" => transcribe_with_whisper_server(client, wav_data, language, translate).await,
+        "cloud" => {
+            transcribe_with_backend(client, wav_data, session_token, api_key, language).await
+        }
         _ => anyhow::bail!("Unknown transcription mode: {}", mode),
     }
 }
 
 /// Use local whisper.cpp server for transcription
-async fn transcribe_with_whisper_server(wav_data: &[u8]) -> Result<String> {
-    let client = reqwest::Client::new();
+async fn transcribe_with_whisper_server(
+    client: &reqwest::Client,
+    wav_data: &[u8],
+    language: Option<&str>,
+    translate: bool,
+) -> Result<String> {
     let part = multipart::Part::bytes(wav_data.to_vec())
         .file_name("audio.wav")
         .mime_str("audio/wav")?;
-    let form = multipart::Form::new()
+    let mut form = multipart::Form::new()
         .text("temperature", "0.0")
         .text("response_format", "json")
         .part("file", part);
+    if let Some(language) = language {
+        form = form.text("language", language.to_string());
+    }
+    if translate {
+        form = form.text("translate", "true");
+    }
 
     let resp = client
         .post("http://localhost:8080/inference")
@@ -52,9 +69,11 @@ async fn transcribe_with_whisper_server(wav_data: &[u8]) -> Result<String> {
 /// Use our backend API for transcription (proxies to OpenAI/Deepgram/ElevenLabs)
 /// Provider is decided server-side
 async fn transcribe_with_backend(
+    client: &reqwest::Client,
     wav_data: &[u8],
     session_token: Option<&str>,
     api_key: Option<&str>,
+    language: Option<&str>,
 ) -> Result<String> {
     let session_token = session_token
         .ok_or_else(|| anyhow::anyhow!("Session token required for cloud mode"))?;
@@ -62,9 +81,11 @@ async fn transcribe_with_backend(
     let part = multipart::Part::bytes(wav_data.to_vec())
         .file_name("audio.wav")
         .mime_str("audio/wav")?;
-    let form = multipart::Form::new().part("file", part);
+    let mut form = multipart::Form::new().part("file", part);
+    if let Some(language) = language {
+        form = form.text("language", language.to_string());
+    }
 
-    let client = reqwest::Client::new();
     let mut req_builder = client
         .post("http://localhost:3001/api/transcribe")
         .header("Authorization", format!("Bearer {}", session_token))
@@ -0,0 +1,38 @@
+use anyhow::Result;
+
+use crate::db::Database;
+
+/// Source/target languages for translation mode, read from settings:
+/// - `translation_source_language`: language spoken, "auto" (default) lets Whisper detect it
+/// - `translation_target_language`: language pasted, defaults to English
+#[derive(Clone, Debug)]
+pub struct TranslationSettings {
+    pub source_language: Option<String>,
+    pub target_language: String,
+}
+
+impl TranslationSettings {
+    pub fn load(db: &Database) -> Result<Self> {
+        let source_language = db
+            .get_setting("translation_source_language")?
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty() && !v.eq_ignore_ascii_case("auto"));
+        let target_language = db
+            .get_setting("translation_target_language")?
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| "en".to_string());
+        Ok(Self {
+            source_language,
+            target_language,
+        })
+    }
+
+    /// Whisper's translate task can only produce English
+    pub fn whisper_can_translate(&self) -> bool {
+        matches!(
+            self.target_language.to_lowercase().as_str(),
+            "en" | "english"
+        )
+    }
+}
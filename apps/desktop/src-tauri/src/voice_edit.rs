@@ -0,0 +1,291 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::sync::Mutex;
+
+use crate::cleanup::CleanupProvider;
+use crate::db::Database;
+
+/// What a dictation asks for: new text, or a change to the previous dictation
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "intent", rename_all = "snake_case")]
+pub enum EditIntent {
+    Dictation,
+    /// "scratch that": remove the previous dictation
+    Scratch,
+    /// "replace Tuesday with Wednesday"
+    Replace {
+        find: String,
+        replacement: String,
+    },
+    /// "make that more formal": rewrite the previous dictation in the given style
+    Rewrite {
+        instruction: String,
+    },
+}
+
+/// Which history field holds the text of the last inserted dictation
+#[derive(Clone, Copy, Debug)]
+pub enum HistoryField {
+    Cleaned,
+    Translated,
+}
+
+/// The history entry behind the last insertion, so edits update it instead of adding one
+#[derive(Clone, Debug)]
+pub struct EditTarget {
+    pub history_id: String,
+    pub field: HistoryField,
+}
+
+pub struct EditState {
+    last: Mutex<Option<EditTarget>>,
+}
+
+impl EditState {
+    pub fn new() -> Self {
+        Self {
+            last: Mutex::new(None),
+        }
+    }
+
+    pub fn last(&self) -> Option<EditTarget> {
+        self.last.lock().unwrap().clone()
+    }
+
+    pub fn set(&self, target: Option<EditTarget>) {
+        *self.last.lock().unwrap() = target;
+    }
+}
+
+/// Setting `voice_editing`: "false" treats every dictation as new text
+pub fn is_enabled(db: &Database) -> bool {
+    db.get_setting("voice_editing")
+        .ok()
+        .flatten()
+        .is_none_or(|v| v != "false")
+}
+
+/// Setting `voice_editing_llm`: "true" asks the cleanup LLM about utterances the rules don't catch
+pub fn is_llm_enabled(db: &Database) -> bool {
+    db.get_setting("voice_editing_llm")
+        .ok()
+        .flatten()
+        .is_some_and(|v| v == "true")
+}
+
+/// Utterances longer than this are always dictation; edit commands are short
+const MAX_COMMAND_WORDS: usize = 12;
+
+/// Only the whole utterance counts; "never mind" is left out, people say it in passing
+const SCRATCH_PHRASES: &[&str] = &["scratch that", "delete that", "undo that", "strike that"];
+const REWRITE_PREFIXES: &[&str] = &["rewrite that ", "rewrite it "];
+/// "make it ..." is common in ordinary speech, so it only counts with a comparison
+const MAKE_PREFIXES: &[&str] = &["make that ", "make it "];
+const MAKE_COMPARISONS: &[&str] = &["more ", "less ", "sound "];
+
+/// Rule-based classification. Only whole utterances count ("scratch that", not
+/// "... and scratch that off the list"), so ordinary dictation is left alone.
+pub fn classify(text: &str) -> EditIntent {
+    let text = text
+        .trim()
+        .trim_end_matches(|c: char| c.is_ascii_punctuation())
+        .trim();
+    if text.split_whitespace().count() > MAX_COMMAND_WORDS {
+        return EditIntent::Dictation;
+    }
+    let lower = text.to_lowercase();
+
+    if SCRATCH_PHRASES.contains(&lower.as_str()) {
+        return EditIntent::Scratch;
+    }
+    for (verb, separator) in [("replace ", " with "), ("change ", " to ")] {
+        if let Some(intent) = parse_replace(text, &lower, verb, separator) {
+            return intent;
+        }
+    }
+    let instruction = REWRITE_PREFIXES
+        .iter()
+        .find_map(|prefix| lower.strip_prefix(prefix))
+        .map(|rest| rest.trim_start_matches("as ").trim())
+        .or_else(|| {
+            MAKE_PREFIXES
+                .iter()
+                .find_map(|prefix| lower.strip_prefix(prefix))
+                .filter(|rest| {
+                    // "make it more formal", "make that shorter"
+                    MAKE_COMPARISONS.iter().any(|c| rest.starts_with(c))
+                        || (!rest.contains(' ') && rest.ends_with("er"))
+                })
+                .map(|rest| rest.trim_start_matches("sound ").trim())
+        });
+    match instruction {
+        Some(instruction) if !instruction.is_empty() => EditIntent::Rewrite {
+            instruction: instruction.to_string(),
+        },
+        _ => EditIntent::Dictation,
+    }
+}
+
+/// "replace X with Y", keeping the casing the user dictated for X and Y
+fn parse_replace(text: &str, lower: &str, verb: &str, separator: &str) -> Option<EditIntent> {
+    // Lower-casing can change byte lengths outside ASCII; only slice when it didn't
+    if !lower.starts_with(verb) || lower.len() != text.len() {
+        return None;
+    }
+    let split = lower.find(separator)?;
+    let find = trim_quotes(&text[verb.len()..split]);
+    let replacement = trim_quotes(&text[split + separator.len()..]);
+    if find.is_empty() || replacement.is_empty() {
+        return None;
+    }
+    Some(EditIntent::Replace {
+        find: find.to_string(),
+        replacement: replacement.to_string(),
+    })
+}
+
+fn trim_quotes(s: &str) -> &str {
+    s.trim().trim_matches(['"', '\'', '“', '”', ',']).trim()
+}
+
+const CLASSIFIER_PROMPT: &str = "You decide whether a voice dictation is new text or a command \
+     to edit the previous dictation. Reply with ONLY a JSON object, one of: \
+     {\"intent\":\"dictation\"}, {\"intent\":\"scratch\"}, \
+     {\"intent\":\"replace\",\"find\":\"...\",\"replacement\":\"...\"}, \
+     {\"intent\":\"rewrite\",\"instruction\":\"...\"}. \
+     Only choose an edit when the speaker is clearly talking about what they just dictated.";
+
+/// Ask the cleanup LLM; anything it answers that doesn't parse counts as dictation
+pub async fn classify_with_llm(provider: &dyn CleanupProvider, text: &str) -> Result<EditIntent> {
+    if text.split_whitespace().count() > MAX_COMMAND_WORDS {
+        return Ok(EditIntent::Dictation);
+    }
+    let reply = provider.chat(CLASSIFIER_PROMPT, text).await?;
+    // Models like to wrap JSON in code fences or prose; take the outermost object
+    let json = match (reply.find('{'), reply.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &reply[start..=end],
+        _ => return Ok(EditIntent::Dictation),
+    };
+    Ok(serde_json::from_str(json).unwrap_or(EditIntent::Dictation))
+}
+
+/// Replace every whole-word, case-insensitive occurrence of `find` in `text` ("at" doesn't
+/// touch "that"). None when there is none, so the utterance is dictated as usual.
+pub fn replace_ignore_case(text: &str, find: &str, replacement: &str) -> Option<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut found = false;
+    let mut rest = text;
+    let mut prev: Option<char> = None;
+    while let Some(c) = rest.chars().next() {
+        let at_boundary = prev.is_none_or(|p| !p.is_alphanumeric());
+        if let Some(len) = at_boundary
+            .then(|| match_len_ignore_case(rest, find))
+            .flatten()
+            .filter(|&len| !rest[len..].starts_with(char::is_alphanumeric))
+        {
+            out.push_str(replacement);
+            prev = rest[..len].chars().last();
+            rest = &rest[len..];
+            found = true;
+        } else {
+            out.push(c);
+            prev = Some(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    found.then_some(out)
+}
+
+/// Byte length of the prefix of `haystack` equal to `needle` ignoring case
+pub fn match_len_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    let mut len = 0;
+    let mut chars = haystack.chars();
+    for n in needle.chars() {
+        let h = chars.next()?;
+        if !h.to_lowercase().eq(n.to_lowercase()) {
+            return None;
+        }
+        len += h.len_utf8();
+    }
+    (len > 0).then_some(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replace(find: &str, replacement: &str) -> EditIntent {
+        EditIntent::Replace {
+            find: find.to_string(),
+            replacement: replacement.to_string(),
+        }
+    }
+
+    #[test]
+    fn classifies_commands() {
+        let cases = [
+            ("Scratch that.", EditIntent::Scratch),
+            ("delete that", EditIntent::Scratch),
+            (
+                "replace Tuesday with Wednesday",
+                replace("Tuesday", "Wednesday"),
+            ),
+            ("Change \"Bob\" to \"Rob\".", replace("Bob", "Rob")),
+            (
+                "make that more formal",
+                EditIntent::Rewrite {
+                    instruction: "more formal".to_string(),
+                },
+            ),
+            (
+                "make it shorter",
+                EditIntent::Rewrite {
+                    instruction: "shorter".to_string(),
+                },
+            ),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(classify(text), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn leaves_dictation_alone() {
+        for text in [
+            "never mind",
+            "Never mind, I'll call him later",
+            "and scratch that off the list",
+            "make it happen",
+            "the weather will change",
+            "we need to replace the printer soon and also order paper for the office today",
+        ] {
+            assert_eq!(classify(text), EditIntent::Dictation, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn replaces_whole_words() {
+        let cases = [
+            ("Meet at noon", "noon", "one", Some("Meet at one")),
+            ("That is at home", "at", "in", Some("That is in home")),
+            ("Call BOB and bob", "bob", "Rob", Some("Call Rob and Rob")),
+            (
+                "See you on Tuesday.",
+                "tuesday",
+                "Friday",
+                Some("See you on Friday."),
+            ),
+            ("Für Müller", "müller", "Meier", Some("Für Meier")),
+            ("That works", "at", "in", None),
+            ("The date is Monday", "Friday", "Monday", None),
+        ];
+        for (text, find, replacement, expected) in cases {
+            assert_eq!(
+                replace_ignore_case(text, find, replacement).as_deref(),
+                expected,
+                "{:?}",
+                text
+            );
+        }
+    }
+}
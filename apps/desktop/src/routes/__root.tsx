@@ -1,7 +1,6 @@
 import { createRootRoute, Link, Outlet, useLocation, useNavigate } from "@tanstack/react-router";
 import { useState, useEffect } from "react";
 import { listen } from "@tauri-apps/api/event";
-import { invoke } from "@tauri-apps/api/core";
 import { Badge } from "@/components/ui/badge";
 import { useAuth } from "@/lib/auth";
 
@@ -9,11 +8,16 @@ export const Route = createRootRoute({
   component: RootLayout,
 });
 
-type AppStatus = "idle" | "recording" | "transcribing" | "cleaning";
+type PipelineState =
+  | { state: "idle" | "recording" | "transcribing" | "cleaning" | "reviewing" | "inserting" }
+  | { state: "error"; message: string };
+
+type AppStatus = PipelineState["state"];
 
 interface DictationResult {
   raw_text: string;
   cleaned_text: string;
+  translated_text: string;
   pasted: boolean;
 }
 
@@ -22,9 +26,12 @@ function RootLayout() {
   const location = useLocation();
   const navigate = useNavigate();
 
+  // The review overlay is its own small window without the app chrome
+  const isOverlayRoute = location.pathname.startsWith("/review");
+
   // Auth redirect logic
   useEffect(() => {
-    if (isLoading) return;
+    if (isLoading || isOverlayRoute) return;
 
     const isAuthRoute = location.pathname.startsWith("/login") || location.pathname.startsWith("/signup");
     const onboardingPaths = ["/setup-mode", "/cloud-setup", "/local-setup", "/tour"];
@@ -35,7 +42,7 @@ function RootLayout() {
     } else if (isAuthenticated && !user?.onboarding_completed && !isOnboardingRoute) {
       navigate({ to: "/setup-mode" });
     }
-  }, [isAuthenticated, isLoading, user, location.pathname, navigate]);
+  }, [isAuthenticated, isLoading, user, location.pathname, navigate, isOverlayRoute]);
 
   // Check if we should render the full layout or just the outlet
   const isAuthRoute = location.pathname.startsWith("/login") || location.pathname.startsWith("/signup");
@@ -43,7 +50,7 @@ function RootLayout() {
   const isOnboardingRoute = onboardingPaths.some(p => location.pathname.startsWith(p));
 
   // For auth and onboarding routes, just render the outlet (they have their own layouts)
-  if (isAuthRoute || isOnboardingRoute) {
+  if (isAuthRoute || isOnboardingRoute || isOverlayRoute) {
     return <Outlet />;
   }
 
@@ -68,26 +75,29 @@ function AuthenticatedLayout() {
   const [status, setStatus] = useState<AppStatus>("idle");
   const [result, setResult] = useState<DictationResult | null>(null);
   const [error, setError] = useState<string | null>(null);
+  const [streamed, setStreamed] = useState("");
 
   useEffect(() => {
     const unsubs = [
-      listen("recording-started", () => {
-        setStatus("recording");
-        setResult(null);
-        setError(null);
-      }),
-      listen<number>("recording-stopped", async () => {
-        setStatus("transcribing");
-        try {
-          const res = await invoke<DictationResult>("transcribe_last");
-          setResult(res);
-        } catch (e) {
-          setError(String(e));
+      listen<PipelineState>("pipeline-state", (event) => {
+        setStatus(event.payload.state);
+        if (event.payload.state === "recording") {
+          setResult(null);
+          setError(null);
+        } else if (event.payload.state === "transcribing") {
+          setStreamed("");
+        } else if (event.payload.state === "error") {
+          setError(event.payload.message);
         }
-        setStatus("idle");
       }),
-      listen("cleanup-started", () => {
-        setStatus("cleaning");
+      listen<DictationResult>("dictation-complete", (event) => {
+        setResult(event.payload);
+      }),
+      listen<string>("cleanup-delta", (event) => {
+        setStreamed((text) => text + event.payload);
+      }),
+      listen<DictationResult>("dictation-edited", (event) => {
+        setResult(event.payload);
       }),
     ];
     return () => {
@@ -133,7 +143,8 @@ function AuthenticatedLayout() {
       </main>
       {status === "recording" && <RecordingOverlay />}
       {status === "transcribing" && <ProcessingOverlay label="Transcribing..." />}
-      {status === "cleaning" && <ProcessingOverlay label="Cleaning up..." />}
+      {status === "cleaning" && <ProcessingOverlay label="Cleaning up..." preview={streamed} />}
+      {status === "reviewing" && <ProcessingOverlay label="Waiting for review..." />}
       {result && <ResultOverlay result={result} onDismiss={() => setResult(null)} />}
       {error && <ErrorOverlay message={error} onDismiss={() => setError(null)} />}
     </div>
@@ -169,7 +180,16 @@ function RecordingOverlay() {
   );
 }
 
-function ProcessingOverlay({ label }: { label: string }) {
+function ProcessingOverlay({ label, preview }: { label: string; preview?: string }) {
+  // Streamed cleanup shows the text as it is generated instead of the label
+  if (preview) {
+    return (
+      <div className="fixed top-5 right-5 z-[1000] bg-card border border-border rounded-xl px-[18px] py-3.5 max-w-[400px] shadow-[0_8px_32px_rgba(0,0,0,0.12)]">
+        <p className="text-sm leading-relaxed text-foreground whitespace-pre-wrap">{preview}</p>
+      </div>
+    );
+  }
+
   return (
     <div className="fixed top-5 right-5 z-[1000] flex items-center gap-2.5 px-[18px] py-2.5 rounded-3xl text-sm font-semibold text-white bg-blue-500/90 shadow-[0_4px_20px_rgba(59,130,246,0.4)]">
       <div className="w-4 h-4 border-2 border-white/30 border-t-white rounded-full animate-spin-fast" />
@@ -184,7 +204,7 @@ function ResultOverlay({ result, onDismiss }: { result: DictationResult; onDismi
     return () => clearTimeout(timer);
   }, [onDismiss]);
 
-  const display = result.cleaned_text || result.raw_text;
+  const display = result.translated_text || result.cleaned_text || result.raw_text;
 
   return (
     <div
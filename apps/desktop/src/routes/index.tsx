@@ -1,6 +1,7 @@
 import { Button } from "@/components/ui/button";
 import { Card, CardContent } from "@/components/ui/card";
 import { Input } from "@/components/ui/input";
+import { Textarea } from "@/components/ui/textarea";
 import { createFileRoute } from "@tanstack/react-router";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
@@ -14,6 +15,10 @@ interface DictationEntry {
 	provider: string;
 	duration_ms: number;
 	created_at: string;
+	translated_text: string;
+	target_language: string;
+	app_name: string;
+	cleanup_note: string;
 }
 
 export const Route = createFileRoute("/")({
@@ -25,6 +30,9 @@ function HistoryPage() {
 	const [search, setSearch] = useState("");
 	const [copiedId, setCopiedId] = useState<string | null>(null);
 	const [expandedId, setExpandedId] = useState<string | null>(null);
+	const [editingId, setEditingId] = useState<string | null>(null);
+	const [editText, setEditText] = useState("");
+	const [editError, setEditError] = useState<string | null>(null);
 
 	const loadHistory = useCallback(async () => {
 		try {
@@ -45,16 +53,18 @@ function HistoryPage() {
 	}, [loadHistory]);
 
 	useEffect(() => {
-		const unsub = listen("dictation-complete", () => {
-			loadHistory();
-		});
+		const unsubs = [
+			listen("dictation-complete", () => loadHistory()),
+			// Voice edits rewrite the previous entry rather than adding one
+			listen("dictation-edited", () => loadHistory()),
+		];
 		return () => {
-			unsub.then((f) => f());
+			unsubs.forEach((p) => p.then((f) => f()));
 		};
 	}, [loadHistory]);
 
 	async function copyEntry(entry: DictationEntry) {
-		const text = entry.cleaned_text || entry.raw_text;
+		const text = entry.translated_text || entry.cleaned_text || entry.raw_text;
 		try {
 			await writeText(text);
 			setCopiedId(entry.id);
@@ -64,6 +74,23 @@ function HistoryPage() {
 		}
 	}
 
+	function startEditing(entry: DictationEntry) {
+		setEditingId(entry.id);
+		setEditText(entry.cleaned_text || entry.raw_text);
+		setEditError(null);
+	}
+
+	// Corrections are kept so cleanup can learn the user's preferences
+	async function saveCorrection(entry: DictationEntry) {
+		try {
+			await invoke("correct_dictation", { id: entry.id, text: editText });
+			setEditingId(null);
+			await loadHistory();
+		} catch (e) {
+			setEditError(String(e));
+		}
+	}
+
 	function formatDuration(ms: number): string {
 		const secs = Math.round(ms / 1000);
 		if (secs < 60) return `${secs}s`;
@@ -101,10 +128,10 @@ function HistoryPage() {
 			) : (
 				<div className="flex flex-col gap-2">
 					{entries.map((entry) => {
-						const display = entry.cleaned_text || entry.raw_text;
+						const display =
+							entry.translated_text || entry.cleaned_text || entry.raw_text;
 						const isExpanded = expandedId === entry.id;
-						const hasCleaned =
-							entry.cleaned_text && entry.cleaned_text !== entry.raw_text;
+						const hasCleaned = display !== entry.raw_text;
 
 						return (
 							<Card
@@ -118,6 +145,10 @@ function HistoryPage() {
 											<span>{formatTime(entry.created_at)}</span>
 											<span className="capitalize">{entry.provider}</span>
 											<span>{formatDuration(entry.duration_ms)}</span>
+											{entry.target_language && (
+												<span>→ {entry.target_language}</span>
+											)}
+											{entry.app_name && <span>in {entry.app_name}</span>}
 										</div>
 										<Button
 											variant="outline"
@@ -130,21 +161,70 @@ function HistoryPage() {
 											{copiedId === entry.id ? "Copied!" : "Copy"}
 										</Button>
 									</div>
-									<p
-										className={`text-sm leading-relaxed text-foreground ${isExpanded ? "" : "line-clamp-3"}`}
-									>
-										{display}
-									</p>
+									{editingId === entry.id ? (
+										<div
+											className="flex flex-col gap-1.5"
+											onClick={(e) => e.stopPropagation()}
+										>
+											<Textarea
+												value={editText}
+												onChange={(e) => setEditText(e.target.value)}
+												rows={4}
+												autoFocus
+											/>
+											<div className="flex gap-1.5 justify-end">
+												{editError && (
+													<span className="text-xs text-red-500 mr-auto self-center">
+														{editError}
+													</span>
+												)}
+												<Button
+													variant="outline"
+													size="sm"
+													onClick={() => setEditingId(null)}
+												>
+													Cancel
+												</Button>
+												<Button size="sm" onClick={() => saveCorrection(entry)}>
+													Save
+												</Button>
+											</div>
+										</div>
+									) : (
+										<p
+											className={`text-sm leading-relaxed text-foreground ${isExpanded ? "" : "line-clamp-3"}`}
+										>
+											{display}
+										</p>
+									)}
 									{isExpanded && hasCleaned && (
 										<div className="mt-2.5 pt-2.5 border-t border-border">
 											<span className="text-[11px] font-semibold text-muted-foreground uppercase tracking-wide block mb-1">
-												Raw transcription:
+												{entry.translated_text ? "Original:" : "Raw transcription:"}
 											</span>
 											<p className="text-[13px] leading-relaxed text-muted-foreground">
 												{entry.raw_text}
 											</p>
 										</div>
 									)}
+									{isExpanded && entry.cleanup_note && (
+										<p className="mt-2 text-xs text-muted-foreground">
+											{entry.cleanup_note}
+										</p>
+									)}
+									{isExpanded && !entry.translated_text && editingId !== entry.id && (
+										<Button
+											variant="outline"
+											size="sm"
+											className="mt-2"
+											onClick={(e) => {
+												e.stopPropagation();
+												startEditing(entry);
+											}}
+										>
+											Correct
+										</Button>
+									)}
 								</CardContent>
 							</Card>
 						);
@@ -2,6 +2,13 @@ import { Badge } from "@/components/ui/badge";
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
+import {
+	Select,
+	SelectContent,
+	SelectItem,
+	SelectTrigger,
+	SelectValue,
+} from "@/components/ui/select";
 import { Textarea } from "@/components/ui/textarea";
 import { createFileRoute } from "@tanstack/react-router";
 import { invoke } from "@tauri-apps/api/core";
@@ -13,6 +20,25 @@ interface Profile {
 	writing_style: string;
 }
 
+// Named profiles (local mode only); shortcuts can each use a different one
+interface NamedProfile extends Profile {
+	id: number;
+	name: string;
+	language: string;
+	cleanup_model: string;
+	insertion_method: string;
+	text_normalization: string;
+	output_format: string;
+}
+
+// A term the user keeps correcting in cleaned dictations
+interface WordSuggestion {
+	word: string;
+	count: number;
+}
+
+const DEFAULT_PROFILE_ID = 1;
+
 export const Route = createFileRoute("/profile")({
 	component: ProfilePage,
 });
@@ -23,37 +49,117 @@ function ProfilePage() {
 	const [contextPrompt, setContextPrompt] = useState("");
 	const [writingStyle, setWritingStyle] = useState("");
 	const [saved, setSaved] = useState(false);
+	const [localMode, setLocalMode] = useState(false);
+	const [profiles, setProfiles] = useState<NamedProfile[]>([]);
+	const [selectedId, setSelectedId] = useState(DEFAULT_PROFILE_ID);
+	const [name, setName] = useState("");
+	const [language, setLanguage] = useState("");
+	const [cleanupModel, setCleanupModel] = useState("");
+	const [insertionMethod, setInsertionMethod] = useState("");
+	const [textNormalization, setTextNormalization] = useState("");
+	const [outputFormat, setOutputFormat] = useState("");
+	const [suggestions, setSuggestions] = useState<WordSuggestion[]>([]);
+	const [error, setError] = useState<string | null>(null);
 
 	useEffect(() => {
 		loadProfile();
 	}, []);
 
-	async function loadProfile() {
+	function showProfile(profile: Profile) {
+		try {
+			setWords(JSON.parse(profile.custom_words));
+		} catch {
+			setWords([]);
+		}
+		setContextPrompt(profile.context_prompt);
+		setWritingStyle(profile.writing_style);
+	}
+
+	function selectProfile(profile: NamedProfile) {
+		setSelectedId(profile.id);
+		setName(profile.name);
+		setLanguage(profile.language);
+		setCleanupModel(profile.cleanup_model);
+		setInsertionMethod(profile.insertion_method);
+		setTextNormalization(profile.text_normalization);
+		setOutputFormat(profile.output_format);
+		showProfile(profile);
+		invoke<WordSuggestion[]>("suggest_custom_words", { profileId: profile.id })
+			.then(setSuggestions)
+			.catch((e) => console.error("Failed to load word suggestions:", e));
+	}
+
+	async function loadProfile(selectId = DEFAULT_PROFILE_ID) {
 		try {
-			const profile = await invoke<Profile>("get_profile");
-			try {
-				setWords(JSON.parse(profile.custom_words));
-			} catch {
-				setWords([]);
+			const mode = await invoke<string | null>("get_setting", {
+				key: "setup_mode",
+			});
+			if ((mode ?? "local") !== "local") {
+				showProfile(await invoke<Profile>("get_profile"));
+				return;
 			}
-			setContextPrompt(profile.context_prompt);
-			setWritingStyle(profile.writing_style);
+			setLocalMode(true);
+			const list = await invoke<NamedProfile[]>("list_profiles");
+			setProfiles(list);
+			const selected = list.find((p) => p.id === selectId) ?? list[0];
+			if (selected) selectProfile(selected);
 		} catch (e) {
 			console.error("Failed to load profile:", e);
 		}
 	}
 
 	async function saveProfile() {
+		setError(null);
 		try {
-			await invoke("update_profile", {
-				customWords: JSON.stringify(words),
-				contextPrompt,
-				writingStyle,
-			});
+			if (localMode) {
+				await invoke("save_profile", {
+					id: selectedId,
+					profile: {
+						name,
+						custom_words: JSON.stringify(words),
+						context_prompt: contextPrompt,
+						writing_style: writingStyle,
+						language,
+						cleanup_model: cleanupModel,
+						insertion_method: insertionMethod,
+						text_normalization: textNormalization,
+						output_format: outputFormat,
+					},
+				});
+				await loadProfile(selectedId);
+			} else {
+				await invoke("update_profile", {
+					customWords: JSON.stringify(words),
+					contextPrompt,
+					writingStyle,
+				});
+			}
 			setSaved(true);
 			setTimeout(() => setSaved(false), 2000);
 		} catch (e) {
-			console.error("Failed to save profile:", e);
+			setError(String(e));
+		}
+	}
+
+	async function newProfile() {
+		setError(null);
+		try {
+			const created = await invoke<NamedProfile>("create_profile", {
+				profile: { name: `Profile ${profiles.length + 1}` },
+			});
+			await loadProfile(created.id);
+		} catch (e) {
+			setError(String(e));
+		}
+	}
+
+	async function deleteProfile() {
+		setError(null);
+		try {
+			await invoke("delete_profile", { id: selectedId });
+			await loadProfile();
+		} catch (e) {
+			setError(String(e));
 		}
 	}
 
@@ -65,6 +171,10 @@ function ProfilePage() {
 		}
 	}
 
+	function addSuggestion(word: string) {
+		if (!words.includes(word)) setWords([...words, word]);
+	}
+
 	function removeWord(word: string) {
 		setWords(words.filter((w) => w !== word));
 	}
@@ -74,6 +184,134 @@ function ProfilePage() {
 			<h2 className="text-[22px] font-semibold mb-4">Profile</h2>
 
 			<div className="flex flex-col gap-5 max-w-[480px]">
+				{localMode && (
+					<>
+						<div className="flex gap-2">
+							<Select
+								value={String(selectedId)}
+								onValueChange={(id) => {
+									const profile = profiles.find((p) => p.id === Number(id));
+									if (profile) selectProfile(profile);
+								}}
+							>
+								<SelectTrigger className="flex-1">
+									<SelectValue />
+								</SelectTrigger>
+								<SelectContent>
+									{profiles.map((p) => (
+										<SelectItem key={p.id} value={String(p.id)}>
+											{p.name}
+										</SelectItem>
+									))}
+								</SelectContent>
+							</Select>
+							<Button variant="outline" onClick={newProfile}>
+								New
+							</Button>
+							<Button
+								variant="outline"
+								onClick={deleteProfile}
+								disabled={selectedId === DEFAULT_PROFILE_ID}
+							>
+								Delete
+							</Button>
+						</div>
+
+						<div className="flex flex-col gap-1.5">
+							<Label htmlFor="profileName">Name</Label>
+							<Input
+								id="profileName"
+								value={name}
+								onChange={(e) => setName(e.target.value)}
+							/>
+						</div>
+
+						<div className="flex gap-2">
+							<div className="flex flex-col gap-1.5 flex-1">
+								<Label htmlFor="language">Language</Label>
+								<Input
+									id="language"
+									value={language}
+									onChange={(e) => setLanguage(e.target.value)}
+									placeholder="Auto-detect"
+								/>
+							</div>
+							<div className="flex flex-col gap-1.5 flex-1">
+								<Label htmlFor="cleanupModel">Cleanup Model</Label>
+								<Input
+									id="cleanupModel"
+									value={cleanupModel}
+									onChange={(e) => setCleanupModel(e.target.value)}
+									placeholder="Default model"
+								/>
+							</div>
+						</div>
+
+						<div className="flex flex-col gap-1.5">
+							<Label>Insert By</Label>
+							<Select
+								value={insertionMethod || "default"}
+								onValueChange={(v) => setInsertionMethod(v === "default" ? "" : v)}
+							>
+								<SelectTrigger>
+									<SelectValue />
+								</SelectTrigger>
+								<SelectContent>
+									<SelectItem value="default">Same as settings</SelectItem>
+									<SelectItem value="clipboard">Pasting from clipboard</SelectItem>
+									<SelectItem value="type">Typing the text</SelectItem>
+								</SelectContent>
+							</Select>
+							<span className="text-xs text-muted-foreground">
+								Use typing for remote desktops and VMs that ignore paste.
+							</span>
+						</div>
+
+						<div className="flex flex-col gap-1.5">
+							<Label>Numbers & Dates</Label>
+							<Select
+								value={textNormalization || "standard"}
+								onValueChange={(v) => setTextNormalization(v === "standard" ? "" : v)}
+							>
+								<SelectTrigger>
+									<SelectValue />
+								</SelectTrigger>
+								<SelectContent>
+									<SelectItem value="standard">Digits from ten up</SelectItem>
+									<SelectItem value="all_digits">Digits for every number</SelectItem>
+									<SelectItem value="off">As transcribed</SelectItem>
+								</SelectContent>
+							</Select>
+							<span className="text-xs text-muted-foreground">
+								Writes spoken amounts, dates, times, percentages, phone numbers
+								and units in digits, following the profile's language.
+							</span>
+						</div>
+
+						<div className="flex flex-col gap-1.5">
+							<Label>Output Format</Label>
+							<Select
+								value={outputFormat || "plain"}
+								onValueChange={(v) => setOutputFormat(v === "plain" ? "" : v)}
+							>
+								<SelectTrigger>
+									<SelectValue />
+								</SelectTrigger>
+								<SelectContent>
+									<SelectItem value="plain">Plain text</SelectItem>
+									<SelectItem value="markdown">Markdown</SelectItem>
+									<SelectItem value="code">Code</SelectItem>
+								</SelectContent>
+							</Select>
+							<span className="text-xs text-muted-foreground">
+								Markdown adds "heading one", "numbered item" and "block quote";
+								code adds symbols like "equals" and "dot" and identifiers like
+								"snake case user id". App rules can pick a format per app.
+							</span>
+						</div>
+					</>
+				)}
+
 				<div className="flex flex-col gap-1.5">
 					<Label>Custom Vocabulary</Label>
 					<span className="text-xs text-muted-foreground">
@@ -117,6 +355,26 @@ function ProfilePage() {
 							))}
 						</div>
 					)}
+					{suggestions.some((s) => !words.includes(s.word)) && (
+						<div className="flex flex-wrap items-center gap-1.5 mt-1">
+							<span className="text-xs text-muted-foreground">
+								Often corrected:
+							</span>
+							{suggestions
+								.filter((s) => !words.includes(s.word))
+								.map((s) => (
+									<Badge key={s.word} variant="outline" className="gap-1">
+										{s.word}
+										<button
+											className="bg-transparent border-none text-muted-foreground cursor-pointer text-xs p-0 px-0.5 leading-none hover:text-foreground"
+											onClick={() => addSuggestion(s.word)}
+										>
+											+
+										</button>
+									</Badge>
+								))}
+						</div>
+					)}
 				</div>
 
 				<div className="flex flex-col gap-1.5">
@@ -151,6 +409,7 @@ function ProfilePage() {
 				<Button className="self-start" onClick={saveProfile}>
 					{saved ? "Saved!" : "Save Profile"}
 				</Button>
+				{error && <span className="text-xs text-red-500">{error}</span>}
 			</div>
 		</div>
 	);
@@ -0,0 +1,153 @@
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Textarea } from "@/components/ui/textarea";
+import { createFileRoute } from "@tanstack/react-router";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { useEffect, useState } from "react";
+
+export const Route = createFileRoute("/review")({
+	component: ReviewPage,
+});
+
+interface ReviewRequest {
+	raw_text: string;
+	cleaned_text: string;
+	writing_style: string;
+	can_rerun: boolean;
+}
+
+type Version = "cleaned" | "raw";
+
+// Review overlay: a separate always-on-top window shown before insertion
+// when the review_before_insert setting is on
+function ReviewPage() {
+	const [request, setRequest] = useState<ReviewRequest | null>(null);
+	const [version, setVersion] = useState<Version>("cleaned");
+	const [text, setText] = useState("");
+	const [writingStyle, setWritingStyle] = useState("");
+	const [busy, setBusy] = useState(false);
+	const [error, setError] = useState<string | null>(null);
+
+	function showRequest(next: ReviewRequest) {
+		setRequest(next);
+		setVersion("cleaned");
+		setText(next.cleaned_text);
+		setWritingStyle(next.writing_style);
+		setBusy(false);
+		setError(null);
+	}
+
+	useEffect(() => {
+		invoke<ReviewRequest | null>("get_pending_review").then((pending) => {
+			if (pending) showRequest(pending);
+		});
+		const unlisten = listen<ReviewRequest>("review-requested", (event) =>
+			showRequest(event.payload),
+		);
+		return () => {
+			unlisten.then((f) => f());
+		};
+	}, []);
+
+	function switchVersion(next: Version) {
+		if (!request) return;
+		setVersion(next);
+		setText(next === "raw" ? request.raw_text : request.cleaned_text);
+	}
+
+	async function run(command: string, args: Record<string, unknown> = {}) {
+		setBusy(true);
+		setError(null);
+		try {
+			await invoke(command, args);
+		} catch (e) {
+			setError(String(e));
+			setBusy(false);
+		}
+	}
+
+	if (!request) {
+		return (
+			<div className="h-screen flex items-center justify-center bg-background text-sm text-muted-foreground">
+				Waiting for dictation...
+			</div>
+		);
+	}
+
+	return (
+		<div
+			className="h-screen flex flex-col gap-2 p-3 bg-background"
+			onKeyDown={(e) => {
+				if (e.key === "Enter" && (e.metaKey || e.ctrlKey)) {
+					e.preventDefault();
+					run("accept_review", { text });
+				}
+			}}
+		>
+			<div data-tauri-drag-region className="flex gap-1.5 cursor-default">
+				<Button
+					size="sm"
+					variant={version === "cleaned" ? "default" : "outline"}
+					onClick={() => switchVersion("cleaned")}
+				>
+					Cleaned
+				</Button>
+				<Button
+					size="sm"
+					variant={version === "raw" ? "default" : "outline"}
+					onClick={() => switchVersion("raw")}
+				>
+					Raw
+				</Button>
+			</div>
+			<Textarea
+				className="flex-1 resize-none"
+				value={text}
+				onChange={(e) => setText(e.target.value)}
+				autoFocus
+			/>
+			<div className="flex gap-1.5">
+				<Input
+					className="flex-1"
+					value={writingStyle}
+					onChange={(e) => setWritingStyle(e.target.value)}
+					placeholder={
+						request.can_rerun
+							? "Writing style for a re-run"
+							: "Re-running needs a cleanup provider"
+					}
+					disabled={!request.can_rerun}
+				/>
+				<Button
+					size="sm"
+					variant="outline"
+					disabled={busy || !request.can_rerun}
+					onClick={() => run("rerun_review_cleanup", { writingStyle })}
+				>
+					Re-run
+				</Button>
+			</div>
+			<div className="flex gap-1.5 justify-end">
+				{error && (
+					<span className="text-xs text-red-500 mr-auto self-center">{error}</span>
+				)}
+				<Button
+					size="sm"
+					variant="outline"
+					disabled={busy}
+					onClick={() => run("discard_review")}
+				>
+					Discard
+				</Button>
+				<Button
+					size="sm"
+					disabled={busy}
+					onClick={() => run("accept_review", { text })}
+				>
+					Insert
+				</Button>
+			</div>
+		</div>
+	);
+}
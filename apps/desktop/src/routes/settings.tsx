@@ -1,6 +1,14 @@
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
+import {
+	Select,
+	SelectContent,
+	SelectItem,
+	SelectTrigger,
+	SelectValue,
+} from "@/components/ui/select";
+import { Textarea } from "@/components/ui/textarea";
 import { createFileRoute } from "@tanstack/react-router";
 import { invoke } from "@tauri-apps/api/core";
 import { useEffect, useState } from "react";
@@ -9,36 +17,455 @@ export const Route = createFileRoute("/settings")({
 	component: SettingsPage,
 });
 
+type ShortcutMode = "push_to_talk" | "toggle" | "double_tap_lock";
+type ShortcutAction = "dictate" | "translate" | "command";
+
+interface ShortcutBinding {
+	accelerator: string;
+	mode: ShortcutMode;
+	action: ShortcutAction;
+	profile_id: number | null;
+}
+
+interface AppRule {
+	pattern: string;
+	match_on: "app" | "title";
+	profile_id: number | null;
+	context: string;
+	output_format: string;
+}
+
+interface Snippet {
+	phrase: string;
+	text: string;
+}
+
+interface PromptTemplate {
+	format: string;
+	system_prompt: string;
+	user_prompt: string;
+}
+
+interface PromptPreview {
+	system_prompt: string;
+	user_prompt: string;
+	output: string | null;
+}
+
+interface ProfileSummary {
+	id: number;
+	name: string;
+}
+
 function SettingsPage() {
-	const [hotkey, setHotkey] = useState("CmdOrCtrl+Shift+Space");
+	const [shortcuts, setShortcuts] = useState<ShortcutBinding[]>([]);
+	const [shortcutError, setShortcutError] = useState<string | null>(null);
+	const [cancelHotkey, setCancelHotkey] = useState("");
+	const [undoHotkey, setUndoHotkey] = useState("");
+	const [hotkeyError, setHotkeyError] = useState<string | null>(null);
+	const [profiles, setProfiles] = useState<ProfileSummary[]>([]);
+	const [appRules, setAppRules] = useState<AppRule[]>([]);
+	const [ruleError, setRuleError] = useState<string | null>(null);
+	const [snippets, setSnippets] = useState<Snippet[]>([]);
+	const [snippetError, setSnippetError] = useState<string | null>(null);
+	const [templateFormat, setTemplateFormat] = useState("plain");
+	const [promptTemplate, setPromptTemplate] = useState<PromptTemplate | null>(null);
+	const [templateEdited, setTemplateEdited] = useState(false);
+	const [templateError, setTemplateError] = useState<string | null>(null);
+	const [sampleText, setSampleText] = useState("");
+	const [preview, setPreview] = useState<PromptPreview | null>(null);
+	const [previewing, setPreviewing] = useState(false);
 	const [apiKey, setApiKey] = useState("");
 	const [llmApiKey, setLlmApiKey] = useState("");
+	const [pasteBackend, setPasteBackend] = useState("auto");
+	const [pasteShortcut, setPasteShortcut] = useState("auto");
+	const [detectedBackend, setDetectedBackend] = useState("");
+	const [pasteMethod, setPasteMethod] = useState("clipboard");
+	const [restoreDelay, setRestoreDelay] = useState("500");
+	const [typeDelay, setTypeDelay] = useState("");
+	const [reviewBeforeInsert, setReviewBeforeInsert] = useState("false");
+	const [spokenFormatting, setSpokenFormatting] = useState("true");
+	const [ruleCleanup, setRuleCleanup] = useState("fallback");
+	const [formattingCommands, setFormattingCommands] = useState("");
+	const [commandsError, setCommandsError] = useState<string | null>(null);
+	const [voiceEditing, setVoiceEditing] = useState("rules");
+	const [cleanupProvider, setCleanupProvider] = useState("default");
+	const [cleanupBaseUrl, setCleanupBaseUrl] = useState("");
+	const [llmModel, setLlmModel] = useState("");
+	const [cleanupTemperature, setCleanupTemperature] = useState("");
+	const [cleanupMaxTokens, setCleanupMaxTokens] = useState("");
+	const [providerError, setProviderError] = useState<string | null>(null);
+	const [streaming, setStreaming] = useState("off");
+	const [guardrails, setGuardrails] = useState("on");
+	const [learnCorrections, setLearnCorrections] = useState("on");
+	const [correctionsCleared, setCorrectionsCleared] = useState(false);
+	const [httpProxy, setHttpProxy] = useState("");
+	const [caCertPath, setCaCertPath] = useState("");
+	const [connectTimeout, setConnectTimeout] = useState("");
+	const [readTimeout, setReadTimeout] = useState("");
+	const [networkError, setNetworkError] = useState<string | null>(null);
 	const [saved, setSaved] = useState(false);
 
 	useEffect(() => {
 		loadSettings();
 	}, []);
 
+	useEffect(() => {
+		loadPromptTemplate(templateFormat);
+	}, [templateFormat]);
+
 	async function loadSettings() {
 		try {
-			const hk = await invoke<string | null>("get_setting", { key: "hotkey" });
-			if (hk) setHotkey(hk);
+			setShortcuts(await invoke<ShortcutBinding[]>("get_shortcuts"));
+			const problems = await invoke<string[]>("get_shortcut_problems");
+			if (problems.length > 0) setShortcutError(problems.join("; "));
+			const cancel = await invoke<string | null>("get_setting", { key: "cancel_hotkey" });
+			if (cancel) setCancelHotkey(cancel);
+			const undo = await invoke<string | null>("get_setting", { key: "undo_hotkey" });
+			if (undo) setUndoHotkey(undo);
+			setProfiles(await invoke<ProfileSummary[]>("list_profiles"));
+			setAppRules(await invoke<AppRule[]>("get_app_rules"));
+			setSnippets(await invoke<Snippet[]>("get_snippets"));
 			const ak = await invoke<string | null>("get_setting", { key: "api_key" });
 			if (ak) setApiKey(ak);
 			const lk = await invoke<string | null>("get_setting", {
 				key: "llm_api_key",
 			});
 			if (lk) setLlmApiKey(lk);
+			const pb = await invoke<string | null>("get_setting", {
+				key: "paste_backend",
+			});
+			if (pb) setPasteBackend(pb);
+			const ps = await invoke<string | null>("get_setting", {
+				key: "paste_shortcut",
+			});
+			if (ps) setPasteShortcut(ps);
+			const pm = await invoke<string | null>("get_setting", {
+				key: "paste_method",
+			});
+			if (pm) setPasteMethod(pm);
+			const restore = await invoke<string | null>("get_setting", {
+				key: "paste_restore_clipboard",
+			});
+			const delay = await invoke<string | null>("get_setting", {
+				key: "paste_restore_delay_ms",
+			});
+			const td = await invoke<string | null>("get_setting", {
+				key: "paste_type_delay_ms",
+			});
+			if (td) setTypeDelay(td);
+			const review = await invoke<string | null>("get_setting", {
+				key: "review_before_insert",
+			});
+			if (review) setReviewBeforeInsert(review);
+			const formatting = await invoke<string | null>("get_setting", {
+				key: "spoken_formatting",
+			});
+			if (formatting) setSpokenFormatting(formatting);
+			const rules = await invoke<string | null>("get_setting", {
+				key: "rule_cleanup",
+			});
+			if (rules) setRuleCleanup(rules);
+			const commands = await invoke<string | null>("get_setting", {
+				key: "formatting_commands",
+			});
+			if (commands) setFormattingCommands(commands);
+			const editing = await invoke<string | null>("get_setting", {
+				key: "voice_editing",
+			});
+			const editingLlm = await invoke<string | null>("get_setting", {
+				key: "voice_editing_llm",
+			});
+			if (editing === "false") setVoiceEditing("off");
+			else if (editingLlm === "true") setVoiceEditing("llm");
+			const provider = await invoke<string | null>("get_setting", {
+				key: "cleanup_provider",
+			});
+			if (provider) setCleanupProvider(provider);
+			const baseUrl = await invoke<string | null>("get_setting", {
+				key: "cleanup_base_url",
+			});
+			if (baseUrl) setCleanupBaseUrl(baseUrl);
+			const model = await invoke<string | null>("get_setting", {
+				key: "llm_model",
+			});
+			if (model) setLlmModel(model);
+			const temperature = await invoke<string | null>("get_setting", {
+				key: "cleanup_temperature",
+			});
+			if (temperature) setCleanupTemperature(temperature);
+			const maxTokens = await invoke<string | null>("get_setting", {
+				key: "cleanup_max_tokens",
+			});
+			if (maxTokens) setCleanupMaxTokens(maxTokens);
+			const stream = await invoke<string | null>("get_setting", {
+				key: "cleanup_streaming",
+			});
+			const streamTyping = await invoke<string | null>("get_setting", {
+				key: "cleanup_stream_typing",
+			});
+			if (stream === "true") setStreaming(streamTyping === "true" ? "typing" : "events");
+			const guard = await invoke<string | null>("get_setting", {
+				key: "cleanup_guardrails",
+			});
+			if (guard === "false") setGuardrails("off");
+			const learn = await invoke<string | null>("get_setting", {
+				key: "learn_from_corrections",
+			});
+			if (learn === "false") setLearnCorrections("off");
+			const proxy = await invoke<string | null>("get_setting", {
+				key: "http_proxy",
+			});
+			if (proxy) setHttpProxy(proxy);
+			const caPath = await invoke<string | null>("get_setting", {
+				key: "http_ca_cert_path",
+			});
+			if (caPath) setCaCertPath(caPath);
+			const connect = await invoke<string | null>("get_setting", {
+				key: "http_connect_timeout_secs",
+			});
+			if (connect) setConnectTimeout(connect);
+			const read = await invoke<string | null>("get_setting", {
+				key: "http_read_timeout_secs",
+			});
+			if (read) setReadTimeout(read);
+			if (restore === "false") setRestoreDelay("");
+			else if (delay) setRestoreDelay(delay);
+			setDetectedBackend(await invoke<string>("get_paste_backend"));
 		} catch (e) {
 			console.error("Failed to load settings:", e);
 		}
 	}
 
+	function updateShortcut(index: number, patch: Partial<ShortcutBinding>) {
+		setShortcuts((prev) =>
+			prev.map((s, i) => (i === index ? { ...s, ...patch } : s)),
+		);
+	}
+
+	function updateRule(index: number, patch: Partial<AppRule>) {
+		setAppRules((prev) =>
+			prev.map((r, i) => (i === index ? { ...r, ...patch } : r)),
+		);
+	}
+
+	function updateSnippet(index: number, patch: Partial<Snippet>) {
+		setSnippets((prev) =>
+			prev.map((s, i) => (i === index ? { ...s, ...patch } : s)),
+		);
+	}
+
+	async function loadPromptTemplate(format: string) {
+		setTemplateError(null);
+		setPreview(null);
+		try {
+			setPromptTemplate(await invoke<PromptTemplate>("get_prompt_template", { format }));
+			setTemplateEdited(false);
+		} catch (e) {
+			console.error("Failed to load prompt template:", e);
+		}
+	}
+
+	function updateTemplate(patch: Partial<PromptTemplate>) {
+		setPromptTemplate((prev) => prev && { ...prev, ...patch });
+		setTemplateEdited(true);
+	}
+
+	async function resetTemplate() {
+		setTemplateError(null);
+		setPreview(null);
+		try {
+			setPromptTemplate(
+				await invoke<PromptTemplate>("reset_prompt_template", {
+					format: templateFormat,
+				}),
+			);
+			setTemplateEdited(false);
+		} catch (e) {
+			setTemplateError(String(e));
+		}
+	}
+
+	async function previewTemplate(run: boolean) {
+		if (!promptTemplate) return;
+		setTemplateError(null);
+		setPreviewing(true);
+		try {
+			setPreview(
+				await invoke<PromptPreview>("preview_prompt_template", {
+					template: promptTemplate,
+					sampleText,
+					profileId: null,
+					run,
+				}),
+			);
+		} catch (e) {
+			setPreview(null);
+			setTemplateError(String(e));
+		} finally {
+			setPreviewing(false);
+		}
+	}
+
+	async function clearCorrections() {
+		try {
+			await invoke("clear_corrections");
+			setCorrectionsCleared(true);
+			setTimeout(() => setCorrectionsCleared(false), 2000);
+		} catch (e) {
+			console.error("Failed to clear corrections:", e);
+		}
+	}
+
 	async function saveSettings() {
+		setShortcutError(null);
+		setHotkeyError(null);
+		setRuleError(null);
+		setSnippetError(null);
+		setTemplateError(null);
+		setCommandsError(null);
+		setProviderError(null);
+		setNetworkError(null);
+		try {
+			await invoke("set_shortcuts", { bindings: shortcuts });
+		} catch (e) {
+			setShortcutError(String(e));
+			return;
+		}
+		try {
+			await invoke("set_setting", { key: "cancel_hotkey", value: cancelHotkey.trim() });
+			await invoke("set_setting", { key: "undo_hotkey", value: undoHotkey.trim() });
+		} catch (e) {
+			setHotkeyError(String(e));
+			return;
+		}
+		try {
+			await invoke("save_app_rules", { rules: appRules });
+		} catch (e) {
+			setRuleError(String(e));
+			return;
+		}
+		try {
+			await invoke("save_snippets", { snippets });
+		} catch (e) {
+			setSnippetError(String(e));
+			return;
+		}
+		if (promptTemplate && templateEdited) {
+			try {
+				await invoke("save_prompt_template", { template: promptTemplate });
+				setTemplateEdited(false);
+			} catch (e) {
+				setTemplateError(String(e));
+				return;
+			}
+		}
+		try {
+			await invoke("set_setting", {
+				key: "formatting_commands",
+				value: formattingCommands.trim() || "{}",
+			});
+		} catch (e) {
+			setCommandsError(String(e));
+			return;
+		}
+		try {
+			await invoke("set_setting", { key: "llm_model", value: llmModel.trim() });
+			await invoke("set_setting", {
+				key: "cleanup_provider",
+				value: cleanupProvider === "default" ? "" : cleanupProvider,
+			});
+			await invoke("set_setting", {
+				key: "cleanup_base_url",
+				value: cleanupBaseUrl.trim(),
+			});
+			await invoke("set_setting", {
+				key: "cleanup_temperature",
+				value: cleanupTemperature.trim(),
+			});
+			await invoke("set_setting", {
+				key: "cleanup_max_tokens",
+				value: cleanupMaxTokens.trim(),
+			});
+			await invoke("set_setting", {
+				key: "cleanup_streaming",
+				value: streaming === "off" ? "false" : "true",
+			});
+			await invoke("set_setting", {
+				key: "cleanup_stream_typing",
+				value: streaming === "typing" ? "true" : "false",
+			});
+			await invoke("set_setting", {
+				key: "cleanup_guardrails",
+				value: guardrails === "off" ? "false" : "true",
+			});
+		} catch (e) {
+			setProviderError(String(e));
+			return;
+		}
+		try {
+			await invoke("set_setting", { key: "http_proxy", value: httpProxy.trim() });
+			await invoke("set_setting", {
+				key: "http_ca_cert_path",
+				value: caCertPath.trim(),
+			});
+			await invoke("set_setting", {
+				key: "http_connect_timeout_secs",
+				value: connectTimeout.trim(),
+			});
+			await invoke("set_setting", {
+				key: "http_read_timeout_secs",
+				value: readTimeout.trim(),
+			});
+		} catch (e) {
+			setNetworkError(String(e));
+			return;
+		}
 		try {
-			await invoke("set_setting", { key: "hotkey", value: hotkey });
 			await invoke("set_setting", { key: "api_key", value: apiKey });
 			await invoke("set_setting", { key: "llm_api_key", value: llmApiKey });
+			await invoke("set_setting", { key: "paste_backend", value: pasteBackend });
+			await invoke("set_setting", {
+				key: "paste_shortcut",
+				value: pasteShortcut,
+			});
+			await invoke("set_setting", { key: "paste_method", value: pasteMethod });
+			await invoke("set_setting", {
+				key: "paste_restore_clipboard",
+				value: restoreDelay.trim() ? "true" : "false",
+			});
+			if (restoreDelay.trim()) {
+				await invoke("set_setting", {
+					key: "paste_restore_delay_ms",
+					value: restoreDelay.trim(),
+				});
+			}
+			await invoke("set_setting", {
+				key: "paste_type_delay_ms",
+				value: typeDelay.trim(),
+			});
+			await invoke("set_setting", {
+				key: "review_before_insert",
+				value: reviewBeforeInsert,
+			});
+			await invoke("set_setting", {
+				key: "spoken_formatting",
+				value: spokenFormatting,
+			});
+			await invoke("set_setting", { key: "rule_cleanup", value: ruleCleanup });
+			await invoke("set_setting", {
+				key: "learn_from_corrections",
+				value: learnCorrections === "off" ? "false" : "true",
+			});
+			await invoke("set_setting", {
+				key: "voice_editing",
+				value: voiceEditing === "off" ? "false" : "true",
+			});
+			await invoke("set_setting", {
+				key: "voice_editing_llm",
+				value: voiceEditing === "llm" ? "true" : "false",
+			});
+			setDetectedBackend(await invoke<string>("get_paste_backend"));
 			setSaved(true);
 			setTimeout(() => setSaved(false), 2000);
 		} catch (e) {
@@ -52,19 +479,428 @@ function SettingsPage() {
 
 			<div className="flex flex-col gap-5 max-w-[480px]">
 				<div className="flex flex-col gap-1.5">
-					<Label htmlFor="hotkey">Hotkey</Label>
-					<Input
-						id="hotkey"
-						value={hotkey}
-						onChange={(e) => setHotkey(e.target.value)}
-						placeholder="CmdOrCtrl+Shift+Space"
-					/>
+					<Label>Shortcuts</Label>
+					{shortcuts.map((shortcut, i) => (
+						<div key={i} className="flex gap-2">
+							<Input
+								value={shortcut.accelerator}
+								onChange={(e) => updateShortcut(i, { accelerator: e.target.value })}
+								placeholder="CmdOrCtrl+Shift+Space"
+							/>
+							<Select
+								value={shortcut.mode}
+								onValueChange={(mode) => updateShortcut(i, { mode: mode as ShortcutMode })}
+							>
+								<SelectTrigger className="w-[170px] shrink-0">
+									<SelectValue />
+								</SelectTrigger>
+								<SelectContent>
+									<SelectItem value="push_to_talk">Push to talk</SelectItem>
+									<SelectItem value="toggle">Toggle</SelectItem>
+									<SelectItem value="double_tap_lock">Double-tap to lock</SelectItem>
+								</SelectContent>
+							</Select>
+							<Select
+								value={shortcut.action}
+								onValueChange={(action) =>
+									updateShortcut(i, { action: action as ShortcutAction })
+								}
+							>
+								<SelectTrigger className="w-[120px] shrink-0">
+									<SelectValue />
+								</SelectTrigger>
+								<SelectContent>
+									<SelectItem value="dictate">Dictate</SelectItem>
+									<SelectItem value="translate">Translate</SelectItem>
+									<SelectItem value="command">Command</SelectItem>
+								</SelectContent>
+							</Select>
+							<Select
+								value={String(shortcut.profile_id ?? "default")}
+								onValueChange={(id) =>
+									updateShortcut(i, {
+										profile_id: id === "default" ? null : Number(id),
+									})
+								}
+							>
+								<SelectTrigger className="w-[130px] shrink-0">
+									<SelectValue />
+								</SelectTrigger>
+								<SelectContent>
+									<SelectItem value="default">Default profile</SelectItem>
+									{profiles
+										.filter((p) => p.id !== 1)
+										.map((p) => (
+											<SelectItem key={p.id} value={String(p.id)}>
+												{p.name}
+											</SelectItem>
+										))}
+								</SelectContent>
+							</Select>
+							<Button
+								variant="outline"
+								onClick={() => setShortcuts((prev) => prev.filter((_, j) => j !== i))}
+							>
+								Remove
+							</Button>
+						</div>
+					))}
+					<Button
+						variant="outline"
+						className="self-start"
+						onClick={() =>
+							setShortcuts((prev) => [
+								...prev,
+								{
+									accelerator: "",
+									mode: "push_to_talk",
+									action: "dictate",
+									profile_id: null,
+								},
+							])
+						}
+					>
+						Add shortcut
+					</Button>
 					<span className="text-xs text-muted-foreground">
-						Accelerator format, e.g. CmdOrCtrl+Shift+Space
+						Accelerator format, e.g. CmdOrCtrl+Shift+Space. Command shortcuts
+						apply a spoken instruction ("summarize this") to the selected text.
 					</span>
+					{shortcutError && (
+						<span className="text-xs text-red-500">{shortcutError}</span>
+					)}
+				</div>
+
+				<div className="flex flex-col gap-1.5">
+					<Label htmlFor="cancelHotkey">Cancel and Undo Shortcuts</Label>
+					<div className="flex gap-2">
+						<Input
+							id="cancelHotkey"
+							className="flex-1"
+							value={cancelHotkey}
+							onChange={(e) => setCancelHotkey(e.target.value)}
+							placeholder="Escape"
+						/>
+						<Input
+							className="flex-1"
+							value={undoHotkey}
+							onChange={(e) => setUndoHotkey(e.target.value)}
+							placeholder="CmdOrCtrl+Shift+Backspace"
+						/>
+					</div>
+					<span className="text-xs text-muted-foreground">
+						Cancel stops a dictation while it's being processed; undo removes
+						the last inserted one. Empty uses the default.
+					</span>
+					{hotkeyError && (
+						<span className="text-xs text-red-500">{hotkeyError}</span>
+					)}
 				</div>
 
 	<div className="flex flex-col gap-1.5">
+					<Label>App Rules</Label>
+					{appRules.map((rule, i) => (
+						<div key={i} className="flex flex-col gap-2 border rounded-md p-2">
+							<div className="flex gap-2">
+								<Select
+									value={rule.match_on}
+									onValueChange={(v) =>
+										updateRule(i, { match_on: v as AppRule["match_on"] })
+									}
+								>
+									<SelectTrigger className="w-[110px] shrink-0">
+										<SelectValue />
+									</SelectTrigger>
+									<SelectContent>
+										<SelectItem value="app">App</SelectItem>
+										<SelectItem value="title">Title</SelectItem>
+									</SelectContent>
+								</Select>
+								<Input
+									value={rule.pattern}
+									onChange={(e) => updateRule(i, { pattern: e.target.value })}
+									placeholder="contains, e.g. thunderbird"
+								/>
+								<Select
+									value={String(rule.profile_id ?? "default")}
+									onValueChange={(id) =>
+										updateRule(i, {
+											profile_id: id === "default" ? null : Number(id),
+										})
+									}
+								>
+									<SelectTrigger className="w-[130px] shrink-0">
+										<SelectValue />
+									</SelectTrigger>
+									<SelectContent>
+										<SelectItem value="default">Default profile</SelectItem>
+										{profiles
+											.filter((p) => p.id !== 1)
+											.map((p) => (
+												<SelectItem key={p.id} value={String(p.id)}>
+													{p.name}
+												</SelectItem>
+											))}
+									</SelectContent>
+								</Select>
+								<Button
+									variant="outline"
+									onClick={() => setAppRules((prev) => prev.filter((_, j) => j !== i))}
+								>
+									Remove
+								</Button>
+							</div>
+							<div className="flex gap-2">
+								<Input
+									value={rule.context}
+									onChange={(e) => updateRule(i, { context: e.target.value })}
+									placeholder="Extra instructions, e.g. This is an email: use greetings."
+								/>
+								<Select
+									value={rule.output_format || "profile"}
+									onValueChange={(v) =>
+										updateRule(i, { output_format: v === "profile" ? "" : v })
+									}
+								>
+									<SelectTrigger className="w-[130px] shrink-0">
+										<SelectValue />
+									</SelectTrigger>
+									<SelectContent>
+										<SelectItem value="profile">Profile format</SelectItem>
+										<SelectItem value="plain">Plain text</SelectItem>
+										<SelectItem value="markdown">Markdown</SelectItem>
+										<SelectItem value="code">Code</SelectItem>
+									</SelectContent>
+								</Select>
+							</div>
+						</div>
+					))}
+					<Button
+						variant="outline"
+						className="self-start"
+						onClick={() =>
+							setAppRules((prev) => [
+								...prev,
+								{
+									pattern: "",
+									match_on: "app",
+									profile_id: null,
+									context: "",
+									output_format: "",
+								},
+							])
+						}
+					>
+						Add rule
+					</Button>
+					<span className="text-xs text-muted-foreground">
+						The first rule matching the focused app picks the profile, adds
+						context for cleanup and can override the output format. A shortcut's
+						own profile takes precedence.
+					</span>
+					{ruleError && <span className="text-xs text-red-500">{ruleError}</span>}
+				</div>
+
+				<div className="flex flex-col gap-1.5">
+					<Label>Snippets</Label>
+					{snippets.map((snippet, i) => (
+						<div key={i} className="flex flex-col gap-2 border rounded-md p-2">
+							<div className="flex gap-2">
+								<Input
+									value={snippet.phrase}
+									onChange={(e) => updateSnippet(i, { phrase: e.target.value })}
+									placeholder="Trigger phrase, e.g. my signature"
+								/>
+								<Button
+									variant="outline"
+									onClick={() => setSnippets((prev) => prev.filter((_, j) => j !== i))}
+								>
+									Remove
+								</Button>
+							</div>
+							<Textarea
+								value={snippet.text}
+								onChange={(e) => updateSnippet(i, { text: e.target.value })}
+								placeholder="Text to insert"
+								rows={3}
+							/>
+						</div>
+					))}
+					<Button
+						variant="outline"
+						className="self-start"
+						onClick={() => setSnippets((prev) => [...prev, { phrase: "", text: "" }])}
+					>
+						Add snippet
+					</Button>
+					<span className="text-xs text-muted-foreground">
+						Saying a trigger phrase inserts its text; said on its own, the text
+						is inserted exactly as stored. Use {"{date}"}, {"{time}"} and{" "}
+						{"{clipboard}"} for the current date, time and clipboard, or a
+						format like {"{date:%d.%m.%Y}"}.
+					</span>
+					{snippetError && (
+						<span className="text-xs text-red-500">{snippetError}</span>
+					)}
+				</div>
+
+				<div className="flex flex-col gap-1.5">
+					<Label>Paste Method</Label>
+					<div className="flex gap-2">
+						<Select value={pasteBackend} onValueChange={setPasteBackend}>
+							<SelectTrigger className="flex-1">
+								<SelectValue />
+							</SelectTrigger>
+							<SelectContent>
+								<SelectItem value="auto">Auto-detect</SelectItem>
+								<SelectItem value="enigo">Keyboard simulation</SelectItem>
+								<SelectItem value="wtype">wtype (Wayland)</SelectItem>
+								<SelectItem value="ydotool">ydotool (Wayland)</SelectItem>
+							</SelectContent>
+						</Select>
+						<Select value={pasteShortcut} onValueChange={setPasteShortcut}>
+							<SelectTrigger className="flex-1">
+								<SelectValue />
+							</SelectTrigger>
+							<SelectContent>
+								<SelectItem value="auto">Auto chord</SelectItem>
+								<SelectItem value="ctrl_v">Ctrl+V</SelectItem>
+								<SelectItem value="ctrl_shift_v">Ctrl+Shift+V</SelectItem>
+								<SelectItem value="cmd_v">Cmd+V</SelectItem>
+							</SelectContent>
+						</Select>
+					</div>
+					{detectedBackend && (
+						<span className="text-xs text-muted-foreground">
+							Currently using: {detectedBackend}
+						</span>
+					)}
+				</div>
+
+				<div className="flex flex-col gap-1.5">
+					<Label htmlFor="restoreDelay">Insert By</Label>
+					<div className="flex gap-2">
+						<Select value={pasteMethod} onValueChange={setPasteMethod}>
+							<SelectTrigger className="flex-1">
+								<SelectValue />
+							</SelectTrigger>
+							<SelectContent>
+								<SelectItem value="clipboard">Pasting from clipboard</SelectItem>
+								<SelectItem value="type">Typing the text</SelectItem>
+							</SelectContent>
+						</Select>
+						<Input
+							id="restoreDelay"
+							className="flex-1"
+							value={restoreDelay}
+							onChange={(e) => setRestoreDelay(e.target.value)}
+							placeholder="Don't restore clipboard"
+							disabled={pasteMethod !== "clipboard"}
+						/>
+						<Input
+							className="flex-1"
+							value={typeDelay}
+							onChange={(e) => setTypeDelay(e.target.value)}
+							placeholder="No typing delay"
+							disabled={pasteMethod !== "type"}
+						/>
+					</div>
+					<span className="text-xs text-muted-foreground">
+						Milliseconds before your previous clipboard contents are restored
+						(empty keeps the dictation on the clipboard), or between typed
+						characters. Profiles can override the method.
+					</span>
+				</div>
+
+				<div className="flex flex-col gap-1.5">
+					<Label>Review Before Inserting</Label>
+					<Select value={reviewBeforeInsert} onValueChange={setReviewBeforeInsert}>
+						<SelectTrigger>
+							<SelectValue />
+						</SelectTrigger>
+						<SelectContent>
+							<SelectItem value="false">Insert immediately</SelectItem>
+							<SelectItem value="true">Show review overlay</SelectItem>
+						</SelectContent>
+					</Select>
+					<span className="text-xs text-muted-foreground">
+						Check, edit or re-run the cleaned text in a small window near the
+						cursor before it is inserted.
+					</span>
+				</div>
+
+				<div className="flex flex-col gap-1.5">
+					<Label>Spoken Commands</Label>
+					<Select value={spokenFormatting} onValueChange={setSpokenFormatting}>
+						<SelectTrigger>
+							<SelectValue />
+						</SelectTrigger>
+						<SelectContent>
+							<SelectItem value="true">Apply formatting commands</SelectItem>
+							<SelectItem value="false">Keep as dictated</SelectItem>
+						</SelectContent>
+					</Select>
+					<Textarea
+						className="font-mono text-xs"
+						rows={4}
+						value={formattingCommands}
+						onChange={(e) => setFormattingCommands(e.target.value)}
+						placeholder={'{"en": [{"phrase": "dash", "action": "attach", "text": " -"}]}'}
+						disabled={spokenFormatting === "false"}
+					/>
+					{commandsError && (
+						<span className="text-xs text-red-500">{commandsError}</span>
+					)}
+					<span className="text-xs text-muted-foreground">
+						Say "new line", "period", "open quote", "bullet" or "all caps" to
+						format as you speak, in the profile's language. Built-in one-word
+						commands like "period" or "bullet" only count at the end or start
+						of a sentence, so they stay words in prose. Add or override
+						phrases per language code, which count anywhere; actions are
+						new_line, new_paragraph, bullet, attach, open, all_caps_next, join,
+						symbol, line_prefix and case.
+					</span>
+				</div>
+
+				<div className="flex flex-col gap-1.5">
+					<Label>Rule-based Cleanup</Label>
+					<Select value={ruleCleanup} onValueChange={setRuleCleanup}>
+						<SelectTrigger>
+							<SelectValue />
+						</SelectTrigger>
+						<SelectContent>
+							<SelectItem value="fallback">When the LLM fails</SelectItem>
+							<SelectItem value="pre_pass">Before the LLM</SelectItem>
+							<SelectItem value="only">Instead of the LLM</SelectItem>
+							<SelectItem value="off">Off</SelectItem>
+						</SelectContent>
+					</Select>
+					<span className="text-xs text-muted-foreground">
+						Removes filler words and stutters, fixes capitalization and spacing,
+						and applies the profile's custom word spellings. Works offline.
+					</span>
+				</div>
+
+				<div className="flex flex-col gap-1.5">
+					<Label>Voice Editing</Label>
+					<Select value={voiceEditing} onValueChange={setVoiceEditing}>
+						<SelectTrigger>
+							<SelectValue />
+						</SelectTrigger>
+						<SelectContent>
+							<SelectItem value="rules">Recognize edit commands</SelectItem>
+							<SelectItem value="llm">Recognize with local LLM too</SelectItem>
+							<SelectItem value="off">Always insert as new text</SelectItem>
+						</SelectContent>
+					</Select>
+					<span className="text-xs text-muted-foreground">
+						Say "scratch that", "replace Tuesday with Wednesday" or "make that
+						more formal" right after a dictation to change it in place. Only
+						works where Parrot can tell the window kept focus (X11).
+					</span>
+				</div>
+
+				<div className="flex flex-col gap-1.5">
 					<Label htmlFor="apiKey">Transcription API Key</Label>
 					<Input
 						id="apiKey"
@@ -76,17 +912,234 @@ function SettingsPage() {
 				</div>
 
 				<div className="flex flex-col gap-1.5">
-					<Label htmlFor="llmApiKey">LLM API Key (OpenAI)</Label>
+					<Label>Cleanup Provider</Label>
+					<Select value={cleanupProvider} onValueChange={setCleanupProvider}>
+						<SelectTrigger>
+							<SelectValue />
+						</SelectTrigger>
+						<SelectContent>
+							<SelectItem value="default">Default for setup mode</SelectItem>
+							<SelectItem value="ollama">Ollama</SelectItem>
+							<SelectItem value="open_ai">OpenAI-compatible</SelectItem>
+							<SelectItem value="anthropic">Anthropic</SelectItem>
+							<SelectItem value="parrot">Parrot backend</SelectItem>
+						</SelectContent>
+					</Select>
+					<Input
+						value={cleanupBaseUrl}
+						onChange={(e) => setCleanupBaseUrl(e.target.value)}
+						placeholder="Base URL (provider default)"
+					/>
+					<div className="flex gap-2">
+						<Input
+							value={llmModel}
+							onChange={(e) => setLlmModel(e.target.value)}
+							placeholder="Model (provider default)"
+						/>
+						<Input
+							className="w-[120px] shrink-0"
+							value={cleanupTemperature}
+							onChange={(e) => setCleanupTemperature(e.target.value)}
+							placeholder="Temp 0.3"
+						/>
+						<Input
+							className="w-[120px] shrink-0"
+							value={cleanupMaxTokens}
+							onChange={(e) => setCleanupMaxTokens(e.target.value)}
+							placeholder="Max tokens"
+						/>
+					</div>
+					<Select value={streaming} onValueChange={setStreaming}>
+						<SelectTrigger>
+							<SelectValue />
+						</SelectTrigger>
+						<SelectContent>
+							<SelectItem value="off">Insert when cleanup finishes</SelectItem>
+							<SelectItem value="events">Stream a preview while cleaning</SelectItem>
+							<SelectItem value="typing">Type into the app as it streams</SelectItem>
+						</SelectContent>
+					</Select>
+					<Select value={guardrails} onValueChange={setGuardrails}>
+						<SelectTrigger>
+							<SelectValue />
+						</SelectTrigger>
+						<SelectContent>
+							<SelectItem value="on">Discard replies that stray from the dictation</SelectItem>
+							<SelectItem value="off">Insert whatever the model returns</SelectItem>
+						</SelectContent>
+					</Select>
+					{providerError && (
+						<span className="text-xs text-red-500">{providerError}</span>
+					)}
+					<span className="text-xs text-muted-foreground">
+						By default local mode uses Ollama and cloud mode the Parrot backend.
+						Profiles can override the model. Typing as it streams is skipped
+						when review is on. Discarded replies fall back to rule-based
+						cleanup; history shows why.
+					</span>
+				</div>
+
+				<div className="flex flex-col gap-1.5">
+					<Label>Learn from Corrections</Label>
+					<div className="flex gap-2">
+						<Select value={learnCorrections} onValueChange={setLearnCorrections}>
+							<SelectTrigger className="flex-1">
+								<SelectValue />
+							</SelectTrigger>
+							<SelectContent>
+								<SelectItem value="on">Use my corrections as examples</SelectItem>
+								<SelectItem value="off">Don't keep corrections</SelectItem>
+							</SelectContent>
+						</Select>
+						<Button variant="outline" onClick={clearCorrections}>
+							{correctionsCleared ? "Cleared!" : "Forget corrections"}
+						</Button>
+					</div>
+					<span className="text-xs text-muted-foreground">
+						Edits made in review, in history or with "replace X with Y" are
+						kept. Similar past corrections are shown to the model, and names
+						you keep fixing are suggested as custom words on the profile page.
+					</span>
+				</div>
+
+				<div className="flex flex-col gap-1.5">
+					<Label>Prompt Template</Label>
+					<div className="flex gap-2">
+						<Select value={templateFormat} onValueChange={setTemplateFormat}>
+							<SelectTrigger className="flex-1">
+								<SelectValue />
+							</SelectTrigger>
+							<SelectContent>
+								<SelectItem value="plain">Plain text</SelectItem>
+								<SelectItem value="markdown">Markdown</SelectItem>
+								<SelectItem value="code">Code</SelectItem>
+							</SelectContent>
+						</Select>
+						<Button variant="outline" onClick={resetTemplate}>
+							Reset to default
+						</Button>
+					</div>
+					<Textarea
+						className="font-mono text-xs"
+						rows={10}
+						value={promptTemplate?.system_prompt ?? ""}
+						onChange={(e) => updateTemplate({ system_prompt: e.target.value })}
+						placeholder="System prompt"
+					/>
+					<Textarea
+						className="font-mono text-xs"
+						rows={2}
+						value={promptTemplate?.user_prompt ?? ""}
+						onChange={(e) => updateTemplate({ user_prompt: e.target.value })}
+						placeholder="User message, e.g. {{raw_text}}"
+					/>
+					<div className="flex gap-2">
+						<Input
+							value={sampleText}
+							onChange={(e) => setSampleText(e.target.value)}
+							placeholder="Sample dictation, e.g. um so the meeting is on tuesday"
+						/>
+						<Button
+							variant="outline"
+							disabled={previewing}
+							onClick={() => previewTemplate(false)}
+						>
+							Preview
+						</Button>
+						<Button
+							variant="outline"
+							disabled={previewing || !sampleText.trim()}
+							onClick={() => previewTemplate(true)}
+						>
+							{previewing ? "Running..." : "Dry run"}
+						</Button>
+					</div>
+					{preview && (
+						<div className="flex flex-col gap-2 border rounded-md p-2">
+							<p className="text-xs font-mono whitespace-pre-wrap">
+								{preview.system_prompt}
+							</p>
+							<p className="text-xs font-mono whitespace-pre-wrap border-t pt-2">
+								{preview.user_prompt}
+							</p>
+							{preview.output !== null && (
+								<p className="text-sm whitespace-pre-wrap border-t pt-2">
+									{preview.output}
+								</p>
+							)}
+						</div>
+					)}
+					{templateError && (
+						<span className="text-xs text-red-500">{templateError}</span>
+					)}
+					<span className="text-xs text-muted-foreground">
+						The cleanup prompt for each output format. Use {"{{raw_text}}"},{" "}
+						{"{{custom_words}}"}, {"{{app_name}}"}, {"{{language}}"},{" "}
+						{"{{previous_dictation}}"}, {"{{context_prompt}}"},{" "}
+						{"{{writing_style}}"}, {"{{app_context}}"}, {"{{examples}}"} and{" "}
+						{"{{target_language}}"}; text between {"{{#name}}"} and{" "}
+						{"{{/name}}"} is left out when the variable is empty. Translations
+						use the Plain template, with the built-in translation instruction
+						added if it doesn't use {"{{target_language}}"}. Preview fills it in
+						with the default profile; a dry run also sends it to the model
+						without inserting anything.
+					</span>
+				</div>
+
+				<div className="flex flex-col gap-1.5">
+					<Label htmlFor="llmApiKey">LLM API Key</Label>
 					<Input
 						id="llmApiKey"
 						type="password"
 						value={llmApiKey}
 						onChange={(e) => setLlmApiKey(e.target.value)}
-						placeholder="For text cleanup with GPT-4o-mini"
+						placeholder="For OpenAI or Anthropic cleanup"
 					/>
 					<span className="text-xs text-muted-foreground">
-						Optional. Without this, raw transcription is used as-is.
+						Optional. Sent to the chosen provider; the Parrot backend uses its
+						own key when this is empty.
+					</span>
+				</div>
+
+				<div className="flex flex-col gap-1.5">
+					<Label htmlFor="httpProxy">Network</Label>
+					<div className="flex gap-2">
+						<Input
+							id="httpProxy"
+							className="flex-1"
+							value={httpProxy}
+							onChange={(e) => setHttpProxy(e.target.value)}
+							placeholder="Proxy from environment"
+						/>
+						<Input
+							className="flex-1"
+							value={caCertPath}
+							onChange={(e) => setCaCertPath(e.target.value)}
+							placeholder="No extra CA certificates"
+						/>
+					</div>
+					<div className="flex gap-2">
+						<Input
+							className="flex-1"
+							value={connectTimeout}
+							onChange={(e) => setConnectTimeout(e.target.value)}
+							placeholder="Connect timeout: 10"
+						/>
+						<Input
+							className="flex-1"
+							value={readTimeout}
+							onChange={(e) => setReadTimeout(e.target.value)}
+							placeholder="Read timeout: 120"
+						/>
+					</div>
+					<span className="text-xs text-muted-foreground">
+						Proxy URL (e.g. http://proxy:8080), path to a PEM file of extra root
+						certificates, and timeouts in seconds. Local services such as
+						Ollama are never proxied.
 					</span>
+					{networkError && (
+						<span className="text-xs text-red-500">{networkError}</span>
+					)}
 				</div>
 
 				<Button className="self-start" onClick={saveSettings}>